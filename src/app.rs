@@ -1,23 +1,30 @@
 use std::io;
 use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::time::{Duration, Instant};
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::terminal::size;
 
 const MIN_COLS: u16 = 40;
 const MIN_ROWS: u16 = 10;
+/// How long a status line message stays up
+const MESSAGE_SECONDS: u64 = 5;
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use crate::config::Config;
 use crate::editor::Editor;
 use crate::input::{self, Action, InputState, Mode};
 use crate::project::Project;
-use crate::search::Search;
+use crate::project_search::{self, ProjectSearch};
+use crate::replace::Replace;
+use crate::search::{Search, SearchHistory};
 use crate::session::Session;
-use crate::spell::SpellChecker;
+use crate::lint::{LintIssue, Linter};
+use crate::spell::{self, DictLayer, Misspelling, SpellChecker, SpellReview};
 use crate::stats::StatsTracker;
-use crate::theme::Theme;
+use crate::theme::{ColorSupport, Theme};
 use crate::ui::{self, RenderState};
 use crate::versions::{Version, VersionStore};
 
@@ -32,6 +39,16 @@ pub enum Overlay {
     VersionDiff(i64),  // Showing diff for version ID
     ProjectDocs,       // Project document picker
     QuitConfirm,
+    ReplaceConfirm,
+    ProjectSearch,
+    SpellReview,
+    /// Choosing which word list to add a word to
+    AddWord {
+        word: String,
+        from_review: bool,
+    },
+    /// Explanation of the style problem under the cursor
+    LintInfo(String),
     SpellSuggestions {
         word: String,
         suggestions: Vec<String>,
@@ -54,9 +71,18 @@ pub struct App {
     pub status_timer: Option<Instant>,
     pub overlay: Overlay,
     pub search_input: String,
+    pub search_origin: usize,
+    pub search_history: SearchHistory,
+    pub replace: Replace,
+    pub project_search: ProjectSearch,
+    pub spell_review: SpellReview,
     pub should_quit: bool,
     pub last_save: Instant,
     pub saved_indicator: Option<Instant>, // Shows "Saved" briefly per spec 5.3
+    /// A message for the status line, and when it was shown
+    pub message: Option<(String, Instant)>,
+    /// Failures reported by exports running in the background
+    export_failures: (Sender<String>, Receiver<String>),
     pub terminal_too_small: bool,
     pub stats: Option<StatsTracker>,
     pub streak: usize,
@@ -67,16 +93,21 @@ pub struct App {
     // Project state
     pub project: Option<Project>,
     pub project_doc_index: usize,
+    /// Front matter titles of the project documents, read when the picker opens
+    pub project_doc_titles: Vec<Option<String>>,
     // Theme
     pub theme: Theme,
     // Spell checking
     pub spell_checker: SpellChecker,
+    // Prose linting
+    pub linter: Linter,
 }
 
 impl App {
     /// Create a new application instance
     pub fn new(file_path: PathBuf, config: Config) -> io::Result<Self> {
         let mut editor = Editor::new();
+        editor.set_tab_width(config.editor.tab_width);
         editor.load(&file_path)?;
 
         let initial_word_count = editor.word_count();
@@ -108,14 +139,15 @@ impl App {
             .filter(|p| p.exists())
             .and_then(|p| Project::load(&p).ok());
 
-        // Load theme from config
-        let theme = config.theme.get_theme();
+        // Load theme from config, reduced to what the terminal can show
+        let theme = config.theme.get_theme().for_terminal(ColorSupport::detect());
 
         // Initialize spell checker
-        let mut spell_checker = SpellChecker::new(&config.spelling.language);
-        spell_checker.set_enabled(config.spelling.enabled);
+        let linter = Linter::new(&config.lint);
+        let mut spell_checker = SpellChecker::from_config(&config.spelling);
+        spell_checker.set_project_dict(project.as_ref().and_then(|p| p.dictionary_path()));
 
-        Ok(Self {
+        let mut app = Self {
             editor,
             session,
             search: Search::new(),
@@ -126,9 +158,16 @@ impl App {
             status_timer: None,
             overlay: Overlay::None,
             search_input: String::new(),
+            search_origin: 0,
+            search_history: SearchHistory::load(),
+            replace: Replace::new(),
+            project_search: ProjectSearch::new(),
+            spell_review: SpellReview::new(),
             should_quit: false,
             last_save: Instant::now(),
             saved_indicator: None,
+            message: None,
+            export_failures: mpsc::channel(),
             terminal_too_small: false,
             writing_stats: None,
             stats,
@@ -138,10 +177,14 @@ impl App {
             version_index: 0,
             project,
             project_doc_index: 0,
+            project_doc_titles: Vec::new(),
             theme,
             spell_checker,
+            linter,
             config,
-        })
+        };
+        app.apply_document_languages();
+        Ok(app)
     }
 
     /// Run the main application loop
@@ -170,6 +213,7 @@ impl App {
                 let content = self.editor.content().to_string();
                 let (cursor_line, cursor_col) = self.editor.cursor_position();
                 let matches = self.search.all_matches(self.editor.content());
+                let search_status = self.search_status(&matches);
 
                 let word_count = self.editor.word_count();
                 let (goal_progress, goal_met) = if let Some(ref stats) = self.stats {
@@ -198,6 +242,8 @@ impl App {
 
                 // Check spelling if enabled
                 let spell_result = self.spell_checker.check_text(&content);
+                let dictionary_layers = self.spell_checker.writable_layers();
+                let lint_issues = self.lint_issues(&content);
                 
                 let state = RenderState {
                     content: &content,
@@ -214,8 +260,18 @@ impl App {
                     search_active: self.mode == Mode::Search,
                     search_query: &self.search_input,
                     search_matches: &matches,
+                    search_status: &search_status,
+                    replace_active: self.mode == Mode::Replace,
+                    replace_find: &self.replace.find,
+                    replace_with: &self.replace.with,
+                    replace_editing_with: self.replace.editing_with,
+                    replace_confirm: self.overlay == Overlay::ReplaceConfirm,
+                    current_match: self.replace.current(),
                     text_width: self.config.editor.text_width,
+                    tab_width: self.config.editor.tab_width,
+                    line_spacing: self.config.display.line_spacing,
                     show_saved_indicator: self.saved_indicator.is_some(),
+                    message: self.message.as_ref().map(|(message, _)| message.as_str()),
                     daily_goal: self.config.goals.daily_goal,
                     goal_progress,
                     streak: self.streak,
@@ -229,17 +285,32 @@ impl App {
                     version_diff: version_diff_opt.as_deref(),
                     version_time: version_time_opt.as_deref(),
                     show_project_docs: self.overlay == Overlay::ProjectDocs,
+                    project_search: (self.overlay == Overlay::ProjectSearch).then_some(&self.project_search),
                     project_name: self.project.as_ref().map(|p| p.name.as_str()),
                     project_docs: self.project.as_ref()
                         .map(|p| p.documents.as_slice())
                         .unwrap_or(&[]),
                     project_doc_index: self.project_doc_index,
+                    project_doc_titles: &self.project_doc_titles,
                     current_doc: self.file_path.file_name()
                         .and_then(|n| n.to_str())
                         .unwrap_or(""),
                     theme: &self.theme,
+                    spell_review: (self.overlay == Overlay::SpellReview).then_some(&self.spell_review),
+                    add_word: match &self.overlay {
+                        Overlay::AddWord { word, .. } => Some(word.as_str()),
+                        _ => None,
+                    },
+                    dictionary_layers: &dictionary_layers,
                     spell_enabled: self.spell_checker.is_enabled(),
+                    spell_languages: self.spell_checker.languages(),
                     misspellings: &spell_result.misspellings,
+                    lint_enabled: self.linter.is_enabled(),
+                    lint_issues: &lint_issues,
+                    lint_info: match &self.overlay {
+                        Overlay::LintInfo(message) => Some(message.as_str()),
+                        _ => None,
+                    },
                     show_spell_suggestions: matches!(&self.overlay, Overlay::SpellSuggestions { .. }),
                     spell_suggestion_word: match &self.overlay {
                         Overlay::SpellSuggestions { word, .. } => word.as_str(),
@@ -267,6 +338,7 @@ impl App {
 
             // Check auto-save
             self.check_auto_save()?;
+            self.check_background_exports();
 
             // Check status timeout
             self.check_status_timeout();
@@ -299,6 +371,40 @@ impl App {
             return;
         }
 
+        // Handle replace confirmation
+        if self.overlay == Overlay::ReplaceConfirm {
+            match key.code {
+                KeyCode::Char('y') => self.replace.replace_current(&mut self.editor, &self.search),
+                KeyCode::Char('n') => self.replace.skip(&self.editor, &self.search),
+                KeyCode::Char('a') => self.replace.replace_all(&mut self.editor, &self.search),
+                KeyCode::Char('q') | KeyCode::Esc => self.replace.finish(),
+                _ => {}
+            }
+            match self.replace.current() {
+                Some((start, _)) => self.set_cursor_from_char_pos(start),
+                None => self.overlay = Overlay::None,
+            }
+            return;
+        }
+
+        // Handle the dictionary picker
+        if let Overlay::AddWord { ref word, from_review } = self.overlay.clone() {
+            self.handle_add_word_key(key, word, from_review);
+            return;
+        }
+
+        // Handle spelling review
+        if self.overlay == Overlay::SpellReview {
+            self.handle_spell_review_key(key);
+            return;
+        }
+
+        // Handle project search overlay
+        if self.overlay == Overlay::ProjectSearch {
+            self.handle_project_search_key(key);
+            return;
+        }
+
         // Handle help overlay
         if self.overlay == Overlay::Help {
             self.overlay = Overlay::None;
@@ -405,6 +511,11 @@ impl App {
             return;
         }
 
+        if let Overlay::LintInfo(_) = self.overlay {
+            self.overlay = Overlay::None;
+            return;
+        }
+
         // Handle spell suggestions overlay
         if let Overlay::SpellSuggestions { ref word, ref suggestions, index, line, col } = self.overlay.clone() {
             match key.code {
@@ -440,9 +551,8 @@ impl App {
                     }
                 }
                 KeyCode::Tab => {
-                    // Add word to personal dictionary
-                    let word = word.clone();
-                    self.add_to_personal_dict(&word);
+                    // Ask which dictionary to add the word to
+                    self.overlay = Overlay::AddWord { word: word.clone(), from_review: false };
                 }
                 KeyCode::Esc | KeyCode::Char('q') => {
                     self.overlay = Overlay::None;
@@ -467,6 +577,7 @@ impl App {
                     self.saved_indicator = Some(Instant::now());
                     self.record_stats();
                     self.save_version(true); // manual save always saves version
+                    self.export_on_save();
                 }
             }
 
@@ -475,6 +586,10 @@ impl App {
                 let _ = self.editor.create_backup_if_needed(&self.file_path);
                 self.editor.insert_char(c);
             }
+            Action::InsertTab => {
+                let _ = self.editor.create_backup_if_needed(&self.file_path);
+                self.editor.insert_tab(self.config.editor.expand_tabs);
+            }
             Action::InsertNewline => {
                 let _ = self.editor.create_backup_if_needed(&self.file_path);
                 self.editor.insert_newline();
@@ -527,9 +642,18 @@ impl App {
                 self.show_status = true;
                 self.status_timer = Some(Instant::now());
             }
+            Action::ToggleLint => {
+                self.linter.toggle();
+                self.show_status = true;
+                self.status_timer = Some(Instant::now());
+            }
             Action::ShowSpellSuggestions => {
                 self.show_spell_suggestions();
             }
+            Action::NextMisspelling => self.jump_to_misspelling(true),
+            Action::PrevMisspelling => self.jump_to_misspelling(false),
+            Action::StartSpellReview => self.start_spell_review(),
+            Action::CycleSpellLanguage => self.cycle_spell_language(),
             Action::ShowHelp => self.overlay = Overlay::Help,
             Action::ShowStats => {
                 // Refresh stats before showing
@@ -544,28 +668,43 @@ impl App {
                 self.overlay = Overlay::Versions;
             }
             Action::ShowProjectDocs => {
-                if self.project.is_some() {
+                if let Some(project) = &self.project {
+                    self.project_doc_titles = project.documents.iter().map(|doc| project.document_title(doc)).collect();
                     self.project_doc_index = 0;
                     self.overlay = Overlay::ProjectDocs;
                 }
             }
+            Action::ShowProjectSearch => {
+                if self.project.is_some() {
+                    self.project_search.preview = None;
+                    self.project_search.editing_with = false;
+                    self.overlay = Overlay::ProjectSearch;
+                    self.run_project_search();
+                }
+            }
             Action::HideOverlay => self.overlay = Overlay::None,
 
             // Search
             Action::StartSearch => {
                 self.mode = Mode::Search;
                 self.search_input.clear();
+                self.search_origin = self.cursor_to_char_pos();
+                self.search_history.reset();
             }
             Action::SubmitSearch => {
                 self.search.set_query(&self.search_input);
-                self.mode = Mode::Navigate;
-                // Find first match from cursor position
-                self.jump_to_next_match();
+                // Stay in the prompt so an invalid regex can be fixed;
+                // the cursor is already on the nearest match
+                if self.search.error().is_none() {
+                    self.mode = Mode::Navigate;
+                    self.search_history.push(&self.search_input);
+                }
             }
             Action::CancelSearch => {
                 self.mode = Mode::Navigate;
                 self.search_input.clear();
                 self.search.clear();
+                self.set_cursor_from_char_pos(self.search_origin);
             }
             Action::SearchNext => {
                 if self.search.is_active() {
@@ -577,9 +716,63 @@ impl App {
                     self.jump_to_prev_match();
                 }
             }
-            Action::SearchInput(c) => self.search_input.push(c),
+            Action::SearchInput(c) => {
+                self.search_input.push(c);
+                self.update_incremental_search();
+            }
             Action::SearchBackspace => {
                 self.search_input.pop();
+                self.update_incremental_search();
+            }
+            Action::SearchHistoryPrev => {
+                if let Some(query) = self.search_history.prev(&self.search_input) {
+                    self.search_input = query.to_string();
+                    self.update_incremental_search();
+                }
+            }
+            Action::SearchHistoryNext => {
+                if let Some(query) = self.search_history.next() {
+                    self.search_input = query.to_string();
+                    self.update_incremental_search();
+                }
+            }
+
+            // Replace
+            Action::StartReplace => {
+                self.mode = Mode::Replace;
+                self.replace.reset();
+                self.replace.find = self.search.query().to_string();
+            }
+            Action::SubmitReplace => {
+                self.search.set_query(&self.replace.find);
+                // Stay in the prompt so an invalid regex can be fixed
+                if self.search.error().is_none() {
+                    self.mode = Mode::Navigate;
+                    let _ = self.editor.create_backup_if_needed(&self.file_path);
+                    if self.replace.start(&self.editor, &self.search) {
+                        if let Some((start, _)) = self.replace.current() {
+                            self.set_cursor_from_char_pos(start);
+                        }
+                        self.overlay = Overlay::ReplaceConfirm;
+                    }
+                }
+            }
+            Action::CancelReplace => self.mode = Mode::Navigate,
+            Action::ReplaceInput(c) => self.replace.push(c),
+            Action::ReplaceBackspace => self.replace.pop(),
+            Action::ReplaceSwitchField => self.replace.editing_with = !self.replace.editing_with,
+            Action::ToggleReplaceScope => self.replace.toggle_scope(),
+            Action::ToggleSearchRegex => {
+                self.search.toggle_regex();
+                self.update_incremental_search();
+            }
+            Action::ToggleSearchWholeWord => {
+                self.search.toggle_whole_word();
+                self.update_incremental_search();
+            }
+            Action::CycleSearchCase => {
+                self.search.cycle_case();
+                self.update_incremental_search();
             }
         }
 
@@ -613,6 +806,7 @@ impl App {
             if self.config.versions.save_on_autosave {
                 self.save_version(false);
             }
+            self.export_on_save();
         }
 
         // Clear saved indicator after 2 seconds
@@ -625,6 +819,60 @@ impl App {
         Ok(())
     }
     
+    /// Export again in the `[export] on_save` format, the whole project when
+    /// the document is part of one. The export runs as a separate
+    /// `hollow export` so a slow format doesn't hold up typing; if it fails,
+    /// its last line of errors is shown in the status line.
+    fn export_on_save(&self) {
+        let Some(format) = &self.config.export.on_save else { return };
+        let Ok(exe) = std::env::current_exe() else { return };
+        let input = self.project.as_ref()
+            .and_then(|p| p.path.clone())
+            .unwrap_or_else(|| self.file_path.clone());
+        let child = Command::new(exe)
+            .arg("export")
+            .arg(input)
+            .args(["--format", format])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn();
+        let failures = self.export_failures.0.clone();
+        let child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = failures.send(format!("Export failed: {}", e));
+                return;
+            }
+        };
+        // Wait in the background so the finished process is cleaned up
+        std::thread::spawn(move || {
+            let Ok(output) = child.wait_with_output() else { return };
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let message = match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+                    Some(line) => line.trim().to_string(),
+                    None => format!("Export failed ({})", output.status),
+                };
+                let _ = failures.send(message);
+            }
+        });
+    }
+
+    /// Show the latest failure from a background export
+    fn check_background_exports(&mut self) {
+        if let Some(message) = self.export_failures.1.try_iter().last() {
+            self.show_message(message);
+        }
+    }
+
+    /// Show a message in the status line for a few seconds
+    fn show_message(&mut self, message: String) {
+        self.message = Some((message, Instant::now()));
+        self.show_status = true;
+        self.status_timer = Some(Instant::now());
+    }
+
     /// Record writing stats to database
     fn record_stats(&mut self) {
         if let Some(ref stats) = self.stats {
@@ -639,6 +887,9 @@ impl App {
     }
 
     fn check_status_timeout(&mut self) {
+        if self.message.as_ref().is_some_and(|(_, shown)| shown.elapsed().as_secs() >= MESSAGE_SECONDS) {
+            self.message = None;
+        }
         if self.config.display.status_timeout == 0 {
             return;
         }
@@ -651,6 +902,59 @@ impl App {
         }
     }
 
+    /// Flags and any regex error to show beside the search or replace prompt
+    fn search_status(&self, matches: &[(usize, usize)]) -> String {
+        if self.overlay == Overlay::ReplaceConfirm {
+            return format!(
+                "{} {} | {} replaced",
+                self.search.flags().label(),
+                self.replace.scope.label(),
+                self.replace.replaced()
+            );
+        }
+        let input = if self.mode == Mode::Replace {
+            &self.replace.find
+        } else {
+            &self.search_input
+        };
+        let (flags, _) = self.search.base_flags().parse(input);
+        let label = if self.mode == Mode::Replace {
+            format!("{} {}", flags.label(), self.replace.scope.label())
+        } else {
+            flags.label()
+        };
+        match self.search.error() {
+            Some(err) if self.search.query() == input => {
+                // Regex errors span several lines; the last one says what is wrong
+                let reason = err.lines().last().unwrap_or(err).trim_start_matches("error: ");
+                format!("{} | {}", label, reason)
+            }
+            _ if self.mode == Mode::Search && self.search.is_active() => {
+                let cursor = self.cursor_to_char_pos();
+                match matches.iter().position(|m| m.0 == cursor) {
+                    Some(i) => format!("{} | match {} of {}", label, i + 1, matches.len()),
+                    None => format!("{} | no matches", label),
+                }
+            }
+            _ => label,
+        }
+    }
+
+    /// Re-run the search as the query is typed, moving to the nearest match
+    /// from where the search started, or back there if nothing matches
+    fn update_incremental_search(&mut self) {
+        if self.mode != Mode::Search {
+            return;
+        }
+        self.search.set_query(&self.search_input);
+        let target = self
+            .search
+            .find_next(self.editor.content(), self.search_origin)
+            .map(|(start, _)| start)
+            .unwrap_or(self.search_origin);
+        self.set_cursor_from_char_pos(target);
+    }
+
     /// Jump to next search match
     fn jump_to_next_match(&mut self) {
         let cursor_char = self.cursor_to_char_pos();
@@ -669,33 +973,12 @@ impl App {
 
     /// Convert current cursor position to char offset in rope
     fn cursor_to_char_pos(&self) -> usize {
-        let content = self.editor.content();
-        let (line, col) = self.editor.cursor_position();
-        if line >= content.len_lines() {
-            return content.len_chars();
-        }
-        let line_start = content.line_to_char(line);
-        let line_len = content.line(line).len_chars();
-        line_start + col.min(line_len)
+        self.editor.cursor_char_pos()
     }
 
     /// Set cursor position from char offset in rope
     fn set_cursor_from_char_pos(&mut self, char_pos: usize) {
-        let content = self.editor.content();
-        let clamped = char_pos.min(content.len_chars());
-        let target_line = content.char_to_line(clamped);
-        let line_start = content.line_to_char(target_line);
-        let target_col = clamped - line_start;
-
-        // Move to document start first, then navigate to target
-        self.editor.move_cursor(crate::editor::Direction::Up, crate::editor::Unit::Document);
-        for _ in 0..target_line {
-            self.editor.move_cursor(crate::editor::Direction::Down, crate::editor::Unit::Line);
-        }
-        self.editor.move_cursor(crate::editor::Direction::Left, crate::editor::Unit::Line);
-        for _ in 0..target_col {
-            self.editor.move_cursor(crate::editor::Direction::Right, crate::editor::Unit::Char);
-        }
+        self.editor.set_cursor_from_char_pos(char_pos);
     }
 
     /// Load versions for the current file
@@ -752,6 +1035,136 @@ impl App {
         }
     }
 
+    /// Keys for the project search overlay: typing edits the active field,
+    /// Enter opens the selected match or, in the replace field, previews the replace
+    fn handle_project_search_key(&mut self, key: KeyEvent) {
+        if self.project_search.preview.is_some() {
+            match key.code {
+                KeyCode::Char('y') => self.apply_project_replace(),
+                KeyCode::Char('n') | KeyCode::Esc => self.project_search.preview = None,
+                _ => {}
+            }
+            return;
+        }
+
+        let alt = key.modifiers == KeyModifiers::ALT;
+        match key.code {
+            KeyCode::Esc => self.overlay = Overlay::None,
+            KeyCode::Up => self.project_search.select_prev(),
+            KeyCode::Down => self.project_search.select_next(),
+            KeyCode::Tab => self.project_search.editing_with = !self.project_search.editing_with,
+            KeyCode::Enter if self.project_search.editing_with => self.preview_project_replace(),
+            KeyCode::Enter => self.open_project_match(),
+            KeyCode::Char('r') if alt => {
+                self.search.toggle_regex();
+                self.run_project_search();
+            }
+            KeyCode::Char('w') if alt => {
+                self.search.toggle_whole_word();
+                self.run_project_search();
+            }
+            KeyCode::Char('c') if alt => {
+                self.search.cycle_case();
+                self.run_project_search();
+            }
+            KeyCode::Backspace if self.project_search.editing_with => {
+                self.project_search.with.pop();
+            }
+            KeyCode::Backspace => {
+                self.project_search.query.pop();
+                self.run_project_search();
+            }
+            KeyCode::Char(c) if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT => {
+                if self.project_search.editing_with {
+                    self.project_search.with.push(c);
+                } else {
+                    self.project_search.query.push(c);
+                    self.run_project_search();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Search every project document for the overlay's query
+    fn run_project_search(&mut self) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+        self.search.set_query(&self.project_search.query);
+        self.project_search.results = project_search::search_project(
+            project,
+            &self.search,
+            Some((&self.file_path, self.editor.content())),
+        );
+        self.project_search.selected = 0;
+    }
+
+    /// Open the selected project search match
+    fn open_project_match(&mut self) {
+        let Some((file, m)) = self.project_search.selected_match() else {
+            return;
+        };
+        let (path, start) = (file.path.clone(), m.start);
+
+        if !project_search::same_file(&path, &self.file_path) {
+            self.switch_document(path);
+            // Switching clears the search; keep the matches highlighted
+            self.search.set_query(&self.project_search.query);
+        }
+        self.set_cursor_from_char_pos(start);
+        self.overlay = Overlay::None;
+    }
+
+    /// Work out the project-wide replace and show it for confirmation
+    fn preview_project_replace(&mut self) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+        self.search.set_query(&self.project_search.query);
+        if !self.search.is_active() {
+            return;
+        }
+        let changes = project_search::plan_replace(
+            project,
+            &self.search,
+            &self.project_search.with,
+            Some((&self.file_path, self.editor.content())),
+        );
+        if !changes.is_empty() {
+            self.project_search.preview = Some(changes);
+        }
+    }
+
+    /// Write the previewed replace, saving a version of every file first.
+    /// The open document is changed through the editor, so the replace can
+    /// be undone there.
+    fn apply_project_replace(&mut self) {
+        let Some(changes) = self.project_search.preview.take() else {
+            return;
+        };
+        let (open, others): (Vec<_>, Vec<_>) = changes
+            .into_iter()
+            .partition(|c| project_search::same_file(&c.path, &self.file_path));
+
+        let mut result = Ok(());
+        if let Some(change) = open.first() {
+            self.save_version(true);
+            self.editor.replace_ranges(&change.ranges);
+            result = self.editor.save(&self.file_path).map_err(|e| format!("{}: {}", change.document, e));
+            self.load_versions();
+        }
+        if result.is_ok() {
+            result = project_search::apply_replace(&others, self.version_store.as_ref())
+                .map(|_| ())
+                .map_err(|e| e.to_string());
+        }
+        if let Err(e) = result {
+            self.show_message(format!("Replace failed: {}", e));
+        }
+        self.run_project_search();
+    }
+
     /// Switch to a different document in the project
     fn switch_document(&mut self, path: PathBuf) {
         // Save current document if modified
@@ -765,12 +1178,50 @@ impl App {
             self.session = Session::new(self.editor.word_count());
             self.search.clear();
             self.load_versions();
+            self.apply_document_languages();
+        }
+    }
+
+    /// Languages the document asks for: its front matter `lang:`, else the
+    /// project default, else config
+    fn default_languages(&self) -> Vec<String> {
+        let from_document = spell::document_languages(&self.editor.content().to_string());
+        if !from_document.is_empty() {
+            return from_document;
+        }
+        self.project
+            .as_ref()
+            .and_then(|p| p.settings.languages.clone())
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| self.config.spelling.active_languages())
+    }
+
+    /// Set the spell checker's languages for the current document
+    fn apply_document_languages(&mut self) {
+        let languages = self.default_languages();
+        self.spell_checker.set_languages(&languages);
+    }
+
+    /// Cycle through the known languages: all together, then each alone
+    fn cycle_spell_language(&mut self) {
+        let mut known = self.default_languages();
+        let others = self.config.spelling.active_languages().into_iter()
+            .chain(self.project.as_ref().and_then(|p| p.settings.languages.clone()).unwrap_or_default());
+        for language in others {
+            if !known.contains(&language) {
+                known.push(language);
+            }
         }
+        let next = spell::next_languages(&known, self.spell_checker.languages());
+        self.spell_checker.set_languages(&next);
+        self.show_status = true;
+        self.status_timer = Some(Instant::now());
     }
 
     /// Show spell suggestions for word under cursor
     fn show_spell_suggestions(&mut self) {
         if !self.spell_checker.is_enabled() {
+            self.show_lint_info();
             return;
         }
 
@@ -782,11 +1233,20 @@ impl App {
         }
 
         let line_text = content.line(line).to_string();
-        
+        // Cursor column is in bytes, word lookup works in chars
+        let col = line_text[..col.min(line_text.len())].chars().count();
+
         // Get word at cursor position
         if let Some((word, start, _end)) = self.spell_checker.word_at_position(&line_text, col) {
-            // Check if it's actually misspelled
-            if !self.spell_checker.check_word(&word) {
+            // Check if it's actually misspelled, as the whole-document check
+            // sees it (so inline words, code and URLs are left alone)
+            let flagged = self
+                .spell_checker
+                .check_text(&content.to_string())
+                .misspellings
+                .iter()
+                .any(|m| m.line == line && m.col == start);
+            if flagged {
                 let suggestions = self.spell_checker.suggest(&word);
                 self.overlay = Overlay::SpellSuggestions {
                     word,
@@ -795,8 +1255,31 @@ impl App {
                     line,
                     col: start,
                 };
+                return;
             }
         }
+        self.show_lint_info();
+    }
+
+    /// Style problems in the document, if linting is on
+    fn lint_issues(&self, content: &str) -> Vec<LintIssue> {
+        if self.linter.is_enabled() {
+            self.linter.lint(content)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Explain the style problem under the cursor
+    fn show_lint_info(&mut self) {
+        let (line, col) = self.editor.cursor_position();
+        let issue = self
+            .lint_issues(&self.editor.content().to_string())
+            .into_iter()
+            .find(|i| i.line == line && i.start <= col && col <= i.end);
+        if let Some(issue) = issue {
+            self.overlay = Overlay::LintInfo(issue.message);
+        }
     }
 
     /// Apply a spell suggestion - replace misspelled word with suggestion
@@ -812,14 +1295,8 @@ impl App {
         // Get word boundaries at the saved position
         if let Some((_word, start, end)) = self.spell_checker.word_at_position(&line_text, col) {
             // Move to the word
-            self.editor.move_cursor(crate::editor::Direction::Up, crate::editor::Unit::Document);
-            for _ in 0..line {
-                self.editor.move_cursor(crate::editor::Direction::Down, crate::editor::Unit::Line);
-            }
-            self.editor.move_cursor(crate::editor::Direction::Left, crate::editor::Unit::Line);
-            for _ in 0..start {
-                self.editor.move_cursor(crate::editor::Direction::Right, crate::editor::Unit::Char);
-            }
+            let line_start = content.line_to_char(line);
+            self.set_cursor_from_char_pos(line_start + start);
             
             // Delete the misspelled word
             for _ in start..end {
@@ -835,9 +1312,157 @@ impl App {
         self.overlay = Overlay::None;
     }
 
-    /// Add word to personal dictionary
-    fn add_to_personal_dict(&mut self, word: &str) {
-        self.spell_checker.add_to_personal(word);
-        self.overlay = Overlay::None;
+    /// Handle the dictionary layer picker: a number picks the layer
+    fn handle_add_word_key(&mut self, key: KeyEvent, word: &str, from_review: bool) {
+        let layers = self.spell_checker.writable_layers();
+        let layer = match key.code {
+            // Numbers past the last layer are ignored like any other key
+            KeyCode::Char(c @ '1'..='9') => match layers.get(c as usize - '1' as usize) {
+                Some(&layer) => Some(layer),
+                None => return,
+            },
+            KeyCode::Esc | KeyCode::Char('q') => None,
+            _ => return,
+        };
+
+        if let Some(layer) = layer {
+            self.add_word_to_layer(word, layer);
+        }
+
+        match (&self.spell_review.current, from_review) {
+            (Some(current), true) if layer.is_some() => {
+                let (line, col) = (current.line, current.col);
+                self.advance_spell_review(line, col);
+            }
+            (Some(_), true) => self.overlay = Overlay::SpellReview,
+            _ => self.overlay = Overlay::None,
+        }
+    }
+
+    /// Add a word to a dictionary layer. Document words go into a
+    /// `hollow-spell` comment in the text, as one undo step.
+    fn add_word_to_layer(&mut self, word: &str, layer: DictLayer) {
+        if layer == DictLayer::Document {
+            let text = self.editor.content().to_string();
+            let (byte, insert) = spell::inline_word_edit(&text, word);
+            let pos = self.editor.content().byte_to_char(byte);
+            let cursor = self.cursor_to_char_pos();
+            self.editor.replace_ranges(&[(pos, pos, insert)]);
+            self.set_cursor_from_char_pos(cursor);
+        } else {
+            let _ = self.spell_checker.add_word(word, layer);
+        }
+    }
+
+    /// Cursor position as (line, char column), the way misspellings are indexed
+    fn cursor_line_char_col(&self) -> (usize, usize) {
+        let content = self.editor.content();
+        let pos = self.cursor_to_char_pos();
+        let line = content.char_to_line(pos);
+        (line, pos - content.line_to_char(line))
+    }
+
+    /// Char range of a misspelling in the document
+    fn misspelling_range(&self, m: &Misspelling) -> (usize, usize) {
+        let start = self.editor.content().line_to_char(m.line) + m.col;
+        (start, start + m.word.chars().count())
+    }
+
+    /// Move the cursor to the next or previous misspelling, wrapping around
+    fn jump_to_misspelling(&mut self, forward: bool) {
+        let result = self.spell_checker.check_text(&self.editor.content().to_string());
+        let (line, col) = self.cursor_line_char_col();
+        let target = if forward {
+            result.next_after(line, col)
+        } else {
+            result.prev_before(line, col)
+        };
+        if let Some(m) = target {
+            let (start, _) = self.misspelling_range(m);
+            self.set_cursor_from_char_pos(start);
+        }
+    }
+
+    /// Review every misspelling from the top of the document
+    fn start_spell_review(&mut self) {
+        if !self.spell_checker.is_enabled() {
+            return;
+        }
+        self.spell_review = SpellReview::new();
+        self.advance_spell_review(0, 0);
+    }
+
+    /// Show the first misspelling at or after (line, char col), or end the review
+    fn advance_spell_review(&mut self, line: usize, col: usize) {
+        let result = self.spell_checker.check_text(&self.editor.content().to_string());
+        match result.first_from(line, col) {
+            Some(m) => {
+                let suggestions = self.spell_checker.suggest(&m.word);
+                let (start, _) = self.misspelling_range(m);
+                self.spell_review.show(m.clone(), suggestions);
+                self.set_cursor_from_char_pos(start);
+                self.overlay = Overlay::SpellReview;
+            }
+            None => {
+                self.spell_review.current = None;
+                self.overlay = Overlay::None;
+            }
+        }
+    }
+
+    fn handle_spell_review_key(&mut self, key: KeyEvent) {
+        let Some(current) = self.spell_review.current.clone() else {
+            self.overlay = Overlay::None;
+            return;
+        };
+        let word_len = current.word.chars().count();
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.spell_review.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.spell_review.select_prev(),
+            KeyCode::Enter => {
+                let Some(suggestion) = self.spell_review.selected().map(str::to_string) else {
+                    return;
+                };
+                let (start, end) = self.misspelling_range(&current);
+                self.editor.replace_ranges(&[(start, end, suggestion.clone())]);
+                self.spell_review.changed += 1;
+                self.advance_spell_review(current.line, current.col + suggestion.chars().count());
+            }
+            KeyCode::Char('a') => {
+                // Replace this and every other occurrence of the same spelling
+                let Some(suggestion) = self.spell_review.selected().map(str::to_string) else {
+                    return;
+                };
+                let result = self.spell_checker.check_text(&self.editor.content().to_string());
+                let ranges: Vec<(usize, usize, String)> = result
+                    .misspellings
+                    .iter()
+                    .filter(|m| m.word == current.word)
+                    .map(|m| {
+                        let (start, end) = self.misspelling_range(m);
+                        (start, end, suggestion.clone())
+                    })
+                    .collect();
+                self.editor.replace_ranges(&ranges);
+                self.spell_review.changed += ranges.len();
+                self.advance_spell_review(current.line, current.col + suggestion.chars().count());
+            }
+            KeyCode::Char('n') | KeyCode::Char(' ') => {
+                self.advance_spell_review(current.line, current.col + word_len);
+            }
+            KeyCode::Char('i') => {
+                self.spell_checker.ignore_for_session(&current.word);
+                self.advance_spell_review(current.line, current.col);
+            }
+            KeyCode::Tab => {
+                self.overlay = Overlay::AddWord { word: current.word.clone(), from_review: true };
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.spell_review.current = None;
+                self.overlay = Overlay::None;
+            }
+            _ => {}
+        }
     }
 }
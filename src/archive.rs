@@ -0,0 +1,262 @@
+//! Minimal ZIP reader and writer for packaged formats (EPUB, DOCX)
+//!
+//! Supports stored and deflated entries, which is all those formats need.
+//! There is no ZIP64 support, so archives are limited to 4 GB.
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::{self, Read, Write};
+
+/// DOS date for 1980-01-01; entries carry no meaningful timestamp
+const DOS_DATE: u16 = (1 << 5) | 1;
+const VERSION: u16 = 20;
+/// General purpose flag: file name is UTF-8
+const UTF8_NAME: u16 = 1 << 11;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// A file already written to the archive
+struct Entry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Builds a ZIP archive in memory
+#[derive(Default)]
+pub struct ZipWriter {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file without compression (EPUB needs this for `mimetype`)
+    pub fn add_stored(&mut self, name: &str, contents: &[u8]) {
+        self.add_entry(name, STORED, contents, contents.to_vec());
+    }
+
+    /// Add a deflate-compressed file
+    pub fn add_deflated(&mut self, name: &str, contents: &[u8]) -> io::Result<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+        self.add_entry(name, DEFLATED, contents, compressed);
+        Ok(())
+    }
+
+    fn add_entry(&mut self, name: &str, method: u16, contents: &[u8], compressed: Vec<u8>) {
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let entry = Entry {
+            name: name.to_string(),
+            method,
+            crc: crc.sum(),
+            compressed_size: compressed.len() as u32,
+            size: contents.len() as u32,
+            offset: self.data.len() as u32,
+        };
+
+        let data = &mut self.data;
+        put_u32(data, 0x0403_4b50);
+        put_u16(data, VERSION);
+        put_u16(data, name_flags(name));
+        put_u16(data, method);
+        put_u16(data, 0);
+        put_u16(data, DOS_DATE);
+        put_u32(data, entry.crc);
+        put_u32(data, entry.compressed_size);
+        put_u32(data, entry.size);
+        put_u16(data, name.len() as u16);
+        put_u16(data, 0);
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&compressed);
+
+        self.entries.push(entry);
+    }
+
+    /// Write the central directory and return the archive bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory_start = self.data.len() as u32;
+        let data = &mut self.data;
+        for entry in &self.entries {
+            put_u32(data, 0x0201_4b50);
+            put_u16(data, VERSION);
+            put_u16(data, VERSION);
+            put_u16(data, name_flags(&entry.name));
+            put_u16(data, entry.method);
+            put_u16(data, 0);
+            put_u16(data, DOS_DATE);
+            put_u32(data, entry.crc);
+            put_u32(data, entry.compressed_size);
+            put_u32(data, entry.size);
+            put_u16(data, entry.name.len() as u16);
+            put_u16(data, 0);
+            put_u16(data, 0);
+            put_u16(data, 0);
+            put_u16(data, 0);
+            put_u32(data, 0);
+            put_u32(data, entry.offset);
+            data.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = data.len() as u32 - directory_start;
+
+        put_u32(data, 0x0605_4b50);
+        put_u16(data, 0);
+        put_u16(data, 0);
+        put_u16(data, self.entries.len() as u16);
+        put_u16(data, self.entries.len() as u16);
+        put_u32(data, directory_size);
+        put_u32(data, directory_start);
+        put_u16(data, 0);
+
+        self.data
+    }
+}
+
+/// Read one file from a ZIP archive, or `None` if the archive has no such entry.
+pub fn read_entry(data: &[u8], name: &str) -> io::Result<Option<Vec<u8>>> {
+    let malformed = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bad zip archive: {}", what));
+
+    // The end of central directory record is followed by a comment of up to 64 KB
+    let search_from = data.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (search_from..data.len().saturating_sub(21))
+        .rev()
+        .find(|&at| get_u32(data, at) == Some(0x0605_4b50))
+        .ok_or_else(|| malformed("no central directory"))?;
+    let count = get_u16(data, end + 10).unwrap_or(0) as usize;
+    let mut at = get_u32(data, end + 16).unwrap_or(0) as usize;
+
+    for _ in 0..count {
+        if get_u32(data, at) != Some(0x0201_4b50) {
+            return Err(malformed("truncated central directory"));
+        }
+        let field = |offset: usize| get_u16(data, at + offset).unwrap_or(0) as usize;
+        let (name_len, extra_len, comment_len) = (field(28), field(30), field(32));
+        let entry_name = data.get(at + 46..at + 46 + name_len).ok_or_else(|| malformed("truncated name"))?;
+        if entry_name == name.as_bytes() {
+            let method = field(10) as u16;
+            let compressed_size = get_u32(data, at + 20).unwrap_or(0) as usize;
+            let offset = get_u32(data, at + 42).unwrap_or(0) as usize;
+            let local_name_len = get_u16(data, offset + 26).unwrap_or(0) as usize;
+            let local_extra_len = get_u16(data, offset + 28).unwrap_or(0) as usize;
+            let start = offset + 30 + local_name_len + local_extra_len;
+            let stored = data.get(start..start + compressed_size).ok_or_else(|| malformed("truncated entry"))?;
+            return match method {
+                STORED => Ok(Some(stored.to_vec())),
+                DEFLATED => {
+                    let mut contents = Vec::new();
+                    DeflateDecoder::new(stored).read_to_end(&mut contents)?;
+                    Ok(Some(contents))
+                }
+                _ => Err(malformed(&format!("{} uses compression method {}", name, method))),
+            };
+        }
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(None)
+}
+
+fn get_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn get_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn name_flags(name: &str) -> u16 {
+    if name.is_ascii() {
+        0
+    } else {
+        UTF8_NAME
+    }
+}
+
+fn put_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(data: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([data[at], data[at + 1]])
+    }
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+    }
+
+    #[test]
+    fn test_stored_entry_comes_first_uncompressed() {
+        let mut zip = ZipWriter::new();
+        zip.add_stored("mimetype", b"application/epub+zip");
+        let data = zip.finish();
+
+        assert_eq!(u32_at(&data, 0), 0x0403_4b50);
+        assert_eq!(u16_at(&data, 8), STORED);
+        assert_eq!(&data[30..38], b"mimetype");
+        assert_eq!(&data[38..58], b"application/epub+zip");
+    }
+
+    #[test]
+    fn test_deflated_entry_round_trips() {
+        let text = "All work and no play. ".repeat(50);
+        let mut zip = ZipWriter::new();
+        zip.add_stored("a.txt", b"first");
+        zip.add_deflated("dir/b.txt", text.as_bytes()).unwrap();
+        let data = zip.finish();
+
+        // End of central directory: two entries
+        let end = data.len() - 22;
+        assert_eq!(u32_at(&data, end), 0x0605_4b50);
+        assert_eq!(u16_at(&data, end + 10), 2);
+
+        // Second central directory record points at the deflated entry
+        let directory = u32_at(&data, end + 16) as usize;
+        let second = directory + 46 + "a.txt".len();
+        assert_eq!(u32_at(&data, second), 0x0201_4b50);
+        let offset = u32_at(&data, second + 42) as usize;
+        let compressed_size = u32_at(&data, offset + 18) as usize;
+        assert!(compressed_size < text.len());
+
+        let start = offset + 30 + "dir/b.txt".len();
+        let mut inflated = String::new();
+        DeflateDecoder::new(&data[start..start + compressed_size])
+            .read_to_string(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated, text);
+
+        let mut crc = Crc::new();
+        crc.update(text.as_bytes());
+        assert_eq!(u32_at(&data, offset + 14), crc.sum());
+    }
+
+    #[test]
+    fn test_read_entry() {
+        let text = "Deflated text. ".repeat(20);
+        let mut zip = ZipWriter::new();
+        zip.add_stored("mimetype", b"application/epub+zip");
+        zip.add_deflated("word/document.xml", text.as_bytes()).unwrap();
+        let data = zip.finish();
+
+        assert_eq!(read_entry(&data, "mimetype").unwrap().unwrap(), b"application/epub+zip");
+        assert_eq!(read_entry(&data, "word/document.xml").unwrap().unwrap(), text.as_bytes());
+        assert!(read_entry(&data, "missing.xml").unwrap().is_none());
+        assert!(read_entry(b"not a zip", "mimetype").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Compile a project into one manuscript
+//!
+//! Documents are taken in project order. Notes (listed under `compile.notes`
+//! in the project file, or marked `note: true` in their front matter) are
+//! left out, each chapter gets a heading, and an optional separator goes
+//! between chapters.
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde_yaml::Value;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::export::SourceDocument;
+use crate::frontmatter;
+use crate::project::{ChapterHeadings, CompileSettings, Project, ProjectError};
+
+/// A project assembled for export
+pub struct Manuscript {
+    /// One document per chapter, headings and separators included
+    pub chapters: Vec<SourceDocument>,
+    /// Documents left out as notes
+    pub skipped: Vec<String>,
+}
+
+impl Manuscript {
+    /// The whole manuscript as one markdown document
+    pub fn to_markdown(&self) -> String {
+        let chapters: Vec<&str> = self.chapters.iter().map(|c| c.markdown.trim_end()).collect();
+        let mut markdown = chapters.join("\n\n");
+        markdown.push('\n');
+        markdown
+    }
+}
+
+/// Assemble a project's documents into chapters.
+pub fn compile(project: &Project) -> Result<Manuscript, ProjectError> {
+    let settings = &project.compile;
+    let mut chapters = Vec::new();
+    let mut skipped = Vec::new();
+
+    for doc in &project.documents {
+        let path = project.resolve_document(doc).unwrap_or_else(|| PathBuf::from(doc));
+        let text = fs::read_to_string(&path).map_err(|e| ProjectError::Io(format!("{}: {}", doc, e)))?;
+        let marked_note = matches!(frontmatter::get(&text, "note"), Some(Value::Bool(true)));
+        if settings.is_note(doc) || marked_note {
+            skipped.push(doc.clone());
+            continue;
+        }
+
+        let number = chapters.len() + 1;
+        chapters.push(SourceDocument {
+            name: file_title(&path),
+            markdown: chapter_markdown(frontmatter::body(&text), &path, number, settings),
+            front_matter: frontmatter::parse(&text).unwrap_or_default(),
+            base_dir: path.parent().map(PathBuf::from),
+        });
+    }
+
+    // Separators go between chapters, not after the last
+    if let Some(separator) = &settings.separator {
+        let count = chapters.len();
+        for chapter in chapters.iter_mut().take(count.saturating_sub(1)) {
+            chapter.markdown = format!("{}\n\n{}\n", chapter.markdown.trim_end(), separator);
+        }
+    }
+
+    Ok(Manuscript { chapters, skipped })
+}
+
+/// A document with its chapter heading
+fn chapter_markdown(body: &str, path: &Path, number: usize, settings: &CompileSettings) -> String {
+    if settings.headings == ChapterHeadings::None {
+        return body.to_string();
+    }
+    let (first_heading, rest) = take_first_h1(body);
+    let title = match settings.headings {
+        ChapterHeadings::FirstHeading => first_heading.unwrap_or_else(|| file_title(path)),
+        _ => file_title(path),
+    };
+    let heading = settings
+        .heading_format
+        .replace("{n}", &number.to_string())
+        .replace("{title}", &title);
+    format!("# {}\n\n{}", heading.trim(), rest.trim_start_matches('\n'))
+}
+
+/// Remove the first H1 heading (ATX or setext, outside code blocks) from a
+/// document, returning its text and what remains.
+fn take_first_h1(text: &str) -> (Option<String>, String) {
+    let mut heading: Option<(Range<usize>, String)> = None;
+    for (event, range) in Parser::new_ext(text, Options::all()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level: HeadingLevel::H1, .. }) => heading = Some((range, String::new())),
+            Event::Text(part) | Event::Code(part) => {
+                if let Some((_, title)) = heading.as_mut() {
+                    title.push_str(&part);
+                }
+            }
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) => {
+                let Some((range, title)) = heading.take() else { continue };
+                let rest = format!("{}{}", &text[..range.start], &text[range.end..]);
+                return (Some(title.trim().to_string()), rest);
+            }
+            _ => {}
+        }
+    }
+    (None, text.to_string())
+}
+
+/// A title from a file name: "03-the_long-road.md" becomes "The long road"
+fn file_title(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // Drop ordering prefixes such as "03-" or "1. "
+    let name = stem
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['-', '_', '.', ' ']);
+    let name = if name.is_empty() { stem.as_str() } else { name };
+    let words = name.replace(['-', '_'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Untitled".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn project(dir: &Path, files: &[(&str, &str)]) -> Project {
+        let mut project = Project::new("Book");
+        project.path = Some(dir.join(".hollow-project"));
+        for (name, content) in files {
+            if let Some(parent) = dir.join(name).parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(dir.join(name), content).unwrap();
+            project.add_document(*name);
+        }
+        project
+    }
+
+    #[test]
+    fn test_file_title() {
+        assert_eq!(file_title(Path::new("03-the_long-road.md")), "The long road");
+        assert_eq!(file_title(Path::new("1. arrival.md")), "Arrival");
+        assert_eq!(file_title(Path::new("2024.md")), "2024");
+    }
+
+    #[test]
+    fn test_take_first_h1() {
+        let (title, rest) = take_first_h1("> Epigraph\n\n```\n# not a heading\n```\n# Storm #\n\nText\n");
+        assert_eq!(title.as_deref(), Some("Storm"));
+        assert_eq!(rest, "> Epigraph\n\n```\n# not a heading\n```\n\nText\n");
+        assert_eq!(take_first_h1("## Sub\n").0, None);
+
+        // Setext headings count; indented and longer fences hide headings
+        let (title, rest) = take_first_h1("  ````\n# code\n  ````\n\nThe *Storm*\n===\nText\n");
+        assert_eq!(title.as_deref(), Some("The Storm"));
+        assert_eq!(rest, "  ````\n# code\n  ````\n\nText\n");
+    }
+
+    #[test]
+    fn test_compile_headings_notes_and_separators() {
+        let dir = tempdir().unwrap();
+        let mut project = project(
+            dir.path(),
+            &[
+                ("01-arrival.md", "Ann arrives.\n"),
+                ("notes/cast.md", "Ann: tall.\n"),
+                ("02-storm.md", "---\nstatus: draft\n---\n# The Storm\n\nRain.\n"),
+                ("ideas.md", "---\nnote: true\n---\nMaybe a dog?\n"),
+            ],
+        );
+        project.compile.notes = vec!["notes/".to_string()];
+        project.compile.separator = Some("***".to_string());
+        project.compile.heading_format = "Chapter {n}: {title}".to_string();
+
+        let manuscript = compile(&project).unwrap();
+        assert_eq!(manuscript.skipped, ["notes/cast.md", "ideas.md"]);
+        assert_eq!(
+            manuscript.to_markdown(),
+            "# Chapter 1: Arrival\n\nAnn arrives.\n\n***\n\n# Chapter 2: The Storm\n\nRain.\n"
+        );
+
+        project.compile.headings = ChapterHeadings::FileName;
+        project.compile.heading_format = "{title}".to_string();
+        project.compile.separator = None;
+        let manuscript = compile(&project).unwrap();
+        assert_eq!(manuscript.chapters[1].markdown, "# Storm\n\nRain.\n");
+
+        project.compile.headings = ChapterHeadings::None;
+        let manuscript = compile(&project).unwrap();
+        assert_eq!(manuscript.chapters[0].markdown, "Ann arrives.\n");
+    }
+
+    #[test]
+    fn test_compile_missing_document() {
+        let dir = tempdir().unwrap();
+        let mut project = project(dir.path(), &[]);
+        project.add_document("gone.md");
+        assert!(compile(&project).is_err());
+    }
+}
@@ -1,8 +1,9 @@
 use serde::Deserialize;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::theme::Theme;
+use crate::export::{exporters, ExportOptions};
+use crate::theme::{Theme, ThemeError};
 
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Config {
@@ -18,11 +19,15 @@ pub struct Config {
     pub theme: ThemeConfig,
     #[serde(default)]
     pub spelling: SpellingConfig,
+    #[serde(default)]
+    pub lint: LintConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ThemeConfig {
-    /// Name of preset theme to use (dark, light, sepia, solarized)
+    /// Name of theme to use (a preset or a file in ~/.config/hollow/themes/)
     #[serde(default = "default_theme_preset")]
     pub preset: String,
     /// Custom theme (overrides preset if provided)
@@ -44,12 +49,12 @@ impl Default for ThemeConfig {
 }
 
 impl ThemeConfig {
-    /// Get the active theme (custom if set, otherwise preset)
+    /// Get the active theme (custom if set, otherwise named theme)
     pub fn get_theme(&self) -> Theme {
         if let Some(ref custom) = self.custom {
             custom.clone()
         } else {
-            Theme::from_name(&self.preset).unwrap_or_default()
+            Theme::load(&self.preset).unwrap_or_default()
         }
     }
 }
@@ -60,6 +65,8 @@ pub struct EditorConfig {
     pub text_width: usize,
     #[serde(default = "default_tab_width")]
     pub tab_width: usize,
+    #[serde(default = "default_expand_tabs")]
+    pub expand_tabs: bool,
     #[serde(default = "default_auto_save_seconds")]
     pub auto_save_seconds: u64,
 }
@@ -100,8 +107,26 @@ pub struct SpellingConfig {
     pub enabled: bool,
     #[serde(default = "default_language")]
     pub language: String,
+    /// Several languages checked together; overrides `language` when set
+    #[serde(default)]
+    pub languages: Vec<String>,
     #[serde(default)]
     pub custom_dict: Option<String>,
+    /// Directory (or .dic file) searched first for Hunspell dictionaries
+    #[serde(default)]
+    pub dictionary_path: Option<String>,
+    /// Don't flag words in capitals (acronyms)
+    #[serde(default = "default_skip")]
+    pub skip_all_caps: bool,
+    /// Don't flag words containing digits
+    #[serde(default = "default_skip")]
+    pub skip_words_with_digits: bool,
+    /// Don't flag email addresses
+    #[serde(default = "default_skip")]
+    pub skip_emails: bool,
+    /// Don't flag @mentions
+    #[serde(default = "default_skip")]
+    pub skip_mentions: bool,
 }
 
 fn default_spelling_enabled() -> bool {
@@ -112,16 +137,133 @@ fn default_language() -> String {
     "en_US".to_string()
 }
 
+fn default_skip() -> bool {
+    true
+}
+
+impl SpellingConfig {
+    /// Languages to check against by default
+    pub fn active_languages(&self) -> Vec<String> {
+        if self.languages.is_empty() {
+            vec![self.language.clone()]
+        } else {
+            self.languages.clone()
+        }
+    }
+}
+
 impl Default for SpellingConfig {
     fn default() -> Self {
         Self {
             enabled: default_spelling_enabled(),
             language: default_language(),
+            languages: Vec::new(),
             custom_dict: None,
+            dictionary_path: None,
+            skip_all_caps: default_skip(),
+            skip_words_with_digits: default_skip(),
+            skip_emails: default_skip(),
+            skip_mentions: default_skip(),
         }
     }
 }
 
+/// Prose linting. Off by default; each check can be switched off on its own.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_check")]
+    pub repeated_words: bool,
+    #[serde(default = "default_check")]
+    pub weasel_words: bool,
+    #[serde(default = "default_check")]
+    pub passive_voice: bool,
+    #[serde(default = "default_check")]
+    pub adverbs: bool,
+    #[serde(default = "default_check")]
+    pub long_sentences: bool,
+    #[serde(default = "default_check")]
+    pub cliches: bool,
+    /// Words flagged by the weasel word check
+    #[serde(default = "default_weasel_list")]
+    pub weasel_list: Vec<String>,
+    /// Sentences with more words than this are flagged
+    #[serde(default = "default_max_sentence_words")]
+    pub max_sentence_words: usize,
+    /// Adverbs are flagged when they make up more than this share of a sentence
+    #[serde(default = "default_max_adverb_density")]
+    pub max_adverb_density: f64,
+}
+
+fn default_check() -> bool {
+    true
+}
+
+fn default_weasel_list() -> Vec<String> {
+    [
+        "very", "really", "quite", "rather", "somewhat", "fairly", "extremely",
+        "just", "basically", "actually", "literally", "totally", "simply",
+        "clearly", "obviously", "various", "several", "arguably", "virtually",
+    ]
+    .iter()
+    .map(|w| w.to_string())
+    .collect()
+}
+
+fn default_max_sentence_words() -> usize {
+    35
+}
+
+fn default_max_adverb_density() -> f64 {
+    0.1
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            repeated_words: default_check(),
+            weasel_words: default_check(),
+            passive_voice: default_check(),
+            adverbs: default_check(),
+            long_sentences: default_check(),
+            cliches: default_check(),
+            weasel_list: default_weasel_list(),
+            max_sentence_words: default_max_sentence_words(),
+            max_adverb_density: default_max_adverb_density(),
+        }
+    }
+}
+
+/// Defaults for `hollow export`. Templates and stylesheets are paths, or
+/// names of files in ~/.config/hollow/templates/.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportConfig {
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub css: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub page_size: Option<String>,
+    #[serde(default)]
+    pub margins: Option<String>,
+    #[serde(default)]
+    pub ragged_right: bool,
+    #[serde(default)]
+    pub preamble: Option<String>,
+    #[serde(default)]
+    pub text_width: Option<usize>,
+    #[serde(default)]
+    pub toc: bool,
+    #[serde(default)]
+    pub smart_punctuation: bool,
+    #[serde(default)]
+    pub on_save: Option<String>,
+}
+
 fn default_show_progress() -> bool {
     true
 }
@@ -146,6 +288,10 @@ fn default_tab_width() -> usize {
     4
 }
 
+fn default_expand_tabs() -> bool {
+    true
+}
+
 fn default_auto_save_seconds() -> u64 {
     30
 }
@@ -163,6 +309,7 @@ impl Default for EditorConfig {
         Self {
             text_width: default_text_width(),
             tab_width: default_tab_width(),
+            expand_tabs: default_expand_tabs(),
             auto_save_seconds: default_auto_save_seconds(),
         }
     }
@@ -198,31 +345,92 @@ impl Default for VersionConfig {
     }
 }
 
+/// Configuration errors
+#[derive(Debug)]
+pub enum ConfigError {
+    /// IO error
+    Io(String),
+    /// Invalid TOML
+    Parse(String),
+    /// A field with an invalid value (field path, reason)
+    Invalid(String, String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "IO error: {}", e),
+            Self::Parse(e) => write!(f, "Parse error: {}", e),
+            Self::Invalid(field, reason) => write!(f, "invalid value for `{}`: {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 impl Config {
     /// Load configuration from ~/.config/hollow/config.toml
-    /// Returns defaults if file is missing or invalid
+    /// Returns defaults if the file is missing or unusable; the editor loads
+    /// with `try_load` instead, so that a broken config stops it with an error
     pub fn load() -> Self {
-        let config_path = Self::config_path();
+        Self::try_load(None).unwrap_or_default()
+    }
 
-        match config_path {
+    /// Load configuration, reporting why an existing config file is unusable
+    /// Returns defaults if the file is missing. A --theme override is applied
+    /// before checking, so a broken configured theme doesn't stop it.
+    pub fn try_load(theme: Option<String>) -> Result<Self, ConfigError> {
+        match Self::config_path() {
             Some(path) if path.exists() => {
-                match fs::read_to_string(&path) {
-                    Ok(content) => {
-                        match toml::from_str(&content) {
-                            Ok(config) => Self::validate(config),
-                            Err(_) => {
-                                // Invalid TOML, use defaults
-                                Self::default()
-                            }
-                        }
-                    }
-                    Err(_) => Self::default(),
-                }
+                let content = fs::read_to_string(&path)
+                    .map_err(|e| ConfigError::Io(e.to_string()))?;
+                Self::from_toml(&content, theme)
             }
-            _ => Self::default(),
+            _ => Ok(Self::default().with_theme(theme)),
         }
     }
 
+    /// Parse, check and clamp a config from TOML text, with an optional --theme override
+    pub fn from_toml(content: &str, theme: Option<String>) -> Result<Self, ConfigError> {
+        let config: Config = toml::from_str::<Config>(content)
+            .map_err(|e| ConfigError::Parse(e.to_string()))?
+            .with_theme(theme);
+        config.check()?;
+        Ok(Self::validate(config))
+    }
+
+    /// Check values that cannot be clamped into range
+    fn check(&self) -> Result<(), ConfigError> {
+        self.check_with_themes(Theme::themes_dir().as_deref())
+    }
+
+    /// `check`, reading theme files from `themes_dir`
+    fn check_with_themes(&self, themes_dir: Option<&Path>) -> Result<(), ConfigError> {
+        if let Some(ref custom) = self.theme.custom {
+            if let Err(ThemeError::InvalidColor { field, reason }) = custom.validate() {
+                return Err(ConfigError::Invalid(format!("theme.custom.{}", field), reason));
+            }
+        } else if let Some(dir) = themes_dir {
+            // The named theme is loaded at startup; a broken theme file would turn it into dark
+            match Theme::load_from(dir, &self.theme.preset) {
+                Ok(_) => {}
+                Err(ThemeError::InvalidColor { field, reason }) => return Err(ConfigError::Invalid(field, reason)),
+                // Names that match nothing fall back to dark as before
+                Err(ThemeError::NotFound(ref name)) if *name == self.theme.preset => {}
+                Err(e) => return Err(ConfigError::Invalid("theme.preset".to_string(), e.to_string())),
+            }
+        }
+        if let Some(ref format) = self.export.on_save {
+            let options = ExportOptions::default();
+            let names: Vec<&str> = exporters(&options).iter().map(|e| e.name()).collect();
+            if !names.contains(&format.as_str()) {
+                let reason = format!("unknown format \"{}\" (expected one of: {})", format, names.join(", "));
+                return Err(ConfigError::Invalid("export.on_save".to_string(), reason));
+            }
+        }
+        Ok(())
+    }
+
     /// Get the config file path
     fn config_path() -> Option<PathBuf> {
         dirs::config_dir().map(|p| p.join("hollow").join("config.toml"))
@@ -260,6 +468,23 @@ impl Config {
         }
         self
     }
+
+    /// Apply a --theme override (takes precedence over [theme.custom])
+    pub fn with_theme(mut self, theme: Option<String>) -> Self {
+        if let Some(name) = theme {
+            self.theme.preset = name;
+            self.theme.custom = None;
+        }
+        self
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
 }
 
 #[cfg(test)]
@@ -271,6 +496,7 @@ mod tests {
         let config = Config::default();
         assert_eq!(config.editor.text_width, 80);
         assert_eq!(config.editor.tab_width, 4);
+        assert!(config.editor.expand_tabs);
         assert_eq!(config.editor.auto_save_seconds, 30);
         assert!(!config.display.show_status);
         assert_eq!(config.display.status_timeout, 3);
@@ -366,6 +592,94 @@ text_width = 70
         assert_eq!(config.editor.auto_save_seconds, 0);
     }
 
+    #[test]
+    fn test_theme_override() {
+        let toml = r#"
+[theme.custom]
+name = "mine"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.theme.get_theme().name, "mine");
+
+        let config = config.with_theme(Some("light".to_string()));
+        assert!(config.theme.custom.is_none());
+        assert_eq!(config.theme.preset, "light");
+
+        let config = Config::default().with_theme(None);
+        assert_eq!(config.theme.preset, "dark");
+    }
+
+    #[test]
+    fn test_from_toml_rejects_unknown_color() {
+        let toml = r#"
+[theme.custom]
+text = "white"
+cursor = "chartreuse"
+"#;
+        let err = Config::from_toml(toml, None).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid(ref field, _) if field == "theme.custom.cursor"));
+        assert!(err.to_string().contains("chartreuse"));
+    }
+
+    #[test]
+    fn test_check_validates_named_theme_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("oops.toml"), "highlight = \"yelow\"\n").unwrap();
+        let config: Config = toml::from_str("[theme]\npreset = \"oops\"\n").unwrap();
+
+        let err = config.check_with_themes(Some(dir.path())).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid(ref field, _) if field == "highlight"));
+        assert!(err.to_string().contains("oops.toml"));
+
+        let config = config.with_theme(Some("sepia".to_string()));
+        assert!(config.check_with_themes(Some(dir.path())).is_ok());
+    }
+
+    #[test]
+    fn test_check_reports_broken_theme_file_and_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("broken.toml"), "cursor = [1, 2\n").unwrap();
+        fs::write(dir.path().join("orphan.toml"), "inherits = \"neon\"\n").unwrap();
+
+        for (preset, expected) in [("broken", "broken.toml"), ("orphan", "neon"), ("../../x", "path separators")] {
+            let config = Config::default().with_theme(Some(preset.to_string()));
+            let err = config.check_with_themes(Some(dir.path())).unwrap_err();
+            assert!(matches!(err, ConfigError::Invalid(ref field, _) if field == "theme.preset"));
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+
+        // An unknown name still falls back to dark
+        let config = Config::default().with_theme(Some("nothing".to_string()));
+        assert!(config.check_with_themes(Some(dir.path())).is_ok());
+
+        // A --theme override replaces a broken configured theme before the check
+        let toml = "[theme]\npreset = \"../../x\"\n";
+        assert!(Config::from_toml(toml, None).is_err());
+        let config = Config::from_toml(toml, Some("sepia".to_string())).unwrap();
+        assert_eq!(config.theme.preset, "sepia");
+    }
+
+    #[test]
+    fn test_from_toml_accepts_hex_and_ansi() {
+        let toml = r##"
+[theme.custom]
+text = "#eeeeee"
+dim = 245
+cursor = "ansi:208"
+"##;
+        assert!(Config::from_toml(toml, None).is_ok());
+    }
+
+    #[test]
+    fn test_from_toml_checks_export_on_save() {
+        let config = Config::from_toml("[export]\non_save = \"epub\"\n", None).unwrap();
+        assert_eq!(config.export.on_save.as_deref(), Some("epub"));
+
+        let err = Config::from_toml("[export]\non_save = \"pdff\"\n", None).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid(ref field, _) if field == "export.on_save"));
+        assert!(err.to_string().contains("pdff"));
+    }
+
     #[test]
     fn test_load_returns_defaults_when_no_file() {
         // This test relies on the config file not existing
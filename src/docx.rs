@@ -0,0 +1,585 @@
+//! Export markdown documents to DOCX (Office Open XML).
+//!
+//! The WordprocessingML parts are written straight from the pulldown-cmark
+//! event stream. The manuscript preset follows standard manuscript format:
+//! 12pt Times or Courier, double spaced, one-inch margins, a running header
+//! with surname / title / page number, and a title page with the word count.
+
+use chrono::Utc;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::io;
+
+use crate::archive::ZipWriter;
+use crate::export::{self, html_escape, ExportOptions, Exporter, Metadata, SourceDocument};
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const R_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Page layout and typography.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    /// Single spaced, block paragraphs, no title page
+    Standard,
+    /// Standard manuscript format in Times New Roman
+    Manuscript,
+    /// Standard manuscript format in Courier New
+    ManuscriptCourier,
+}
+
+impl Preset {
+    /// Look up a preset by its `--preset` name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "standard" => Some(Preset::Standard),
+            "manuscript" => Some(Preset::Manuscript),
+            "manuscript-courier" => Some(Preset::ManuscriptCourier),
+            _ => None,
+        }
+    }
+
+    fn is_manuscript(&self) -> bool {
+        *self != Preset::Standard
+    }
+
+    fn font(&self) -> &'static str {
+        match self {
+            Preset::ManuscriptCourier => "Courier New",
+            _ => "Times New Roman",
+        }
+    }
+
+    /// Line spacing in 240ths of a line
+    fn line_spacing(&self) -> u32 {
+        if self.is_manuscript() { 480 } else { 240 }
+    }
+}
+
+/// Word documents, laid out by a preset.
+pub struct DocxExporter<'a> {
+    options: &'a ExportOptions,
+}
+
+impl<'a> DocxExporter<'a> {
+    pub fn new(options: &'a ExportOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Exporter for DocxExporter<'_> {
+    fn name(&self) -> &'static str {
+        "docx"
+    }
+
+    fn extension(&self) -> &'static str {
+        "docx"
+    }
+
+    fn export(&self, documents: &[SourceDocument], _metadata: &Metadata) -> io::Result<Vec<u8>> {
+        build_docx(documents, self.options)
+    }
+}
+
+/// Build a DOCX package in memory.
+pub fn build_docx(documents: &[SourceDocument], options: &ExportOptions) -> io::Result<Vec<u8>> {
+    let preset = match options.preset.as_deref() {
+        None => Preset::Standard,
+        Some(name) => Preset::from_name(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown preset: {} (expected standard, manuscript or manuscript-courier)", name),
+            )
+        })?,
+    };
+    let title = options.title.clone()
+        .or_else(|| documents.first().and_then(|doc| export::extract_title(&doc.markdown)))
+        .unwrap_or_else(|| "Untitled".to_string());
+    let author = options.author.as_deref();
+
+    let mut zip = ZipWriter::new();
+    zip.add_deflated("[Content_Types].xml", content_types(preset).as_bytes())?;
+    zip.add_deflated("_rels/.rels", PACKAGE_RELS.as_bytes())?;
+    zip.add_deflated("docProps/core.xml", core_properties(&title, author).as_bytes())?;
+    zip.add_deflated("word/_rels/document.xml.rels", document_rels(preset).as_bytes())?;
+    zip.add_deflated("word/styles.xml", styles_xml(preset).as_bytes())?;
+    if preset.is_manuscript() {
+        zip.add_deflated("word/header1.xml", header_xml(&title, author).as_bytes())?;
+    }
+    zip.add_deflated("word/document.xml", document_xml(documents, &title, author, preset).as_bytes())?;
+    Ok(zip.finish())
+}
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>
+"#;
+
+fn content_types(preset: Preset) -> String {
+    let header = if preset.is_manuscript() {
+        "<Override PartName=\"/word/header1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml\"/>\n"
+    } else {
+        ""
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+{}<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>
+"#,
+        header
+    )
+}
+
+fn document_rels(preset: Preset) -> String {
+    let header = if preset.is_manuscript() {
+        "<Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/header\" Target=\"header1.xml\"/>\n"
+    } else {
+        ""
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+{}</Relationships>
+"#,
+        header
+    )
+}
+
+fn core_properties(title: &str, author: Option<&str>) -> String {
+    let creator = author
+        .map(|a| format!("<dc:creator>{}</dc:creator>", html_escape(a)))
+        .unwrap_or_default();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+<dc:title>{}</dc:title>{}<dcterms:created xsi:type="dcterms:W3CDTF">{}</dcterms:created>
+</cp:coreProperties>
+"#,
+        html_escape(title),
+        creator,
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+    )
+}
+
+fn styles_xml(preset: Preset) -> String {
+    let font = preset.font();
+    let line = preset.line_spacing();
+    let (indent, after) = if preset.is_manuscript() { (720, 0) } else { (0, 160) };
+    // Manuscript chapters start on a new page, centered, in body type
+    let (heading_align, page_break, heading_size) = if preset.is_manuscript() {
+        ("center", "<w:pageBreakBefore/>", 24)
+    } else {
+        ("left", "", 32)
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="{W_NS}">
+<w:docDefaults>
+<w:rPrDefault><w:rPr><w:rFonts w:ascii="{font}" w:hAnsi="{font}" w:cs="{font}" w:eastAsia="{font}"/><w:sz w:val="24"/><w:szCs w:val="24"/><w:lang w:val="en-US"/></w:rPr></w:rPrDefault>
+<w:pPrDefault><w:pPr><w:spacing w:after="{after}" w:line="{line}" w:lineRule="auto"/></w:pPr></w:pPrDefault>
+</w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:firstLine="{indent}"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/>{page_break}<w:spacing w:before="240" w:after="240"/><w:ind w:firstLine="0"/><w:jc w:val="{heading_align}"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="{heading_size}"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240"/><w:ind w:firstLine="0"/><w:jc w:val="{heading_align}"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:ind w:firstLine="0"/><w:jc w:val="{heading_align}"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720" w:right="720" w:firstLine="0"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="SceneBreak"><w:name w:val="Scene Break"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:firstLine="0"/><w:jc w:val="center"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:left="720" w:hanging="360"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="Code"><w:name w:val="Code"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0" w:line="240" w:lineRule="auto"/><w:ind w:firstLine="0"/></w:pPr><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/></w:rPr></w:style>
+</w:styles>
+"#
+    )
+}
+
+/// Running header: Surname / TITLE / page number, right aligned
+fn header_xml(title: &str, author: Option<&str>) -> String {
+    let mut label = String::new();
+    if let Some(surname) = author.and_then(|a| a.split_whitespace().last()) {
+        label.push_str(surname);
+        label.push_str(" / ");
+    }
+    label.push_str(&title.to_uppercase());
+    label.push_str(" / ");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:hdr xmlns:w="{}"><w:p><w:pPr><w:spacing w:line="240" w:lineRule="auto"/><w:ind w:firstLine="0"/><w:jc w:val="right"/></w:pPr>{}<w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText xml:space="preserve"> PAGE </w:instrText></w:r><w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>1</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r></w:p></w:hdr>
+"#,
+        W_NS,
+        text_run(&label, &RunStyle::default())
+    )
+}
+
+/// Character formatting in effect for a run
+#[derive(Default)]
+struct RunStyle {
+    italic: usize,
+    bold: usize,
+    strike: usize,
+    code: bool,
+    superscript: bool,
+}
+
+fn text_run(text: &str, style: &RunStyle) -> String {
+    let mut props = String::new();
+    if style.code {
+        props.push_str("<w:rFonts w:ascii=\"Courier New\" w:hAnsi=\"Courier New\" w:cs=\"Courier New\"/>");
+    }
+    if style.bold > 0 {
+        props.push_str("<w:b/>");
+    }
+    if style.italic > 0 {
+        props.push_str("<w:i/>");
+    }
+    if style.strike > 0 {
+        props.push_str("<w:strike/>");
+    }
+    if style.superscript {
+        props.push_str("<w:vertAlign w:val=\"superscript\"/>");
+    }
+    let props = if props.is_empty() { props } else { format!("<w:rPr>{}</w:rPr>", props) };
+    format!("<w:r>{}<w:t xml:space=\"preserve\">{}</w:t></w:r>", props, html_escape(text))
+}
+
+/// Builds the document body paragraph by paragraph
+struct BodyWriter {
+    body: String,
+    /// Style of the open paragraph
+    paragraph: Option<String>,
+    /// Runs of the open paragraph
+    runs: String,
+    style: RunStyle,
+    quote_depth: usize,
+    /// Numbering of the open lists: `Some(next)` for ordered lists
+    lists: Vec<Option<u64>>,
+    /// Bullet, number or footnote label waiting for the first paragraph of
+    /// a list item or note
+    item_marker: Option<String>,
+    in_code_block: bool,
+    words: usize,
+}
+
+impl BodyWriter {
+    fn new() -> Self {
+        Self {
+            body: String::new(),
+            paragraph: None,
+            runs: String::new(),
+            style: RunStyle::default(),
+            quote_depth: 0,
+            lists: Vec::new(),
+            item_marker: None,
+            in_code_block: false,
+            words: 0,
+        }
+    }
+
+    /// Style for a paragraph in the current container
+    fn container_style(&self) -> &'static str {
+        if !self.lists.is_empty() {
+            "ListParagraph"
+        } else if self.quote_depth > 0 {
+            "Quote"
+        } else {
+            "Normal"
+        }
+    }
+
+    fn open(&mut self, style: &str) {
+        self.close();
+        self.paragraph = Some(style.to_string());
+        if let Some(marker) = self.item_marker.take() {
+            self.runs.push_str(&text_run(&marker, &RunStyle::default()));
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some(style) = self.paragraph.take() {
+            self.body.push_str(&format!(
+                "<w:p><w:pPr><w:pStyle w:val=\"{}\"/></w:pPr>{}</w:p>\n",
+                style,
+                std::mem::take(&mut self.runs)
+            ));
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.paragraph.is_none() {
+            let style = self.container_style();
+            self.open(style);
+        }
+        self.words += text.split_whitespace().count();
+        self.runs.push_str(&text_run(text, &self.style));
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.in_code_block => {
+                for line in text.lines() {
+                    self.open("Code");
+                    self.runs.push_str(&text_run(line, &self.style));
+                }
+                self.close();
+            }
+            Event::Text(text) => self.text(&text),
+            Event::Code(text) => {
+                self.style.code = true;
+                self.text(&text);
+                self.style.code = false;
+            }
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.runs.push_str("<w:r><w:br/></w:r>"),
+            Event::Rule => {
+                // Scene break
+                self.open("SceneBreak");
+                self.runs.push_str(&text_run("#", &RunStyle::default()));
+                self.close();
+            }
+            Event::FootnoteReference(label) => {
+                self.style.superscript = true;
+                self.text(&label);
+                self.style.superscript = false;
+            }
+            Event::TaskListMarker(done) => self.text(if done { "[x] " } else { "[ ] " }),
+            Event::Html(_) | Event::InlineHtml(_) => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                let style = self.container_style();
+                self.open(style);
+            }
+            Tag::Heading { level, .. } => {
+                let style = match level {
+                    HeadingLevel::H1 => "Heading1",
+                    HeadingLevel::H2 => "Heading2",
+                    _ => "Heading3",
+                };
+                self.open(style);
+            }
+            Tag::BlockQuote => {
+                self.close();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.close();
+                self.in_code_block = true;
+            }
+            Tag::List(start) => {
+                self.close();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.close();
+                self.item_marker = Some(match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.\t", *n - 1)
+                    }
+                    _ => "\u{2022}\t".to_string(),
+                });
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.close();
+                self.item_marker = Some(format!("{}. ", label));
+            }
+            Tag::TableRow | Tag::TableHead => self.open("Normal"),
+            Tag::Emphasis => self.style.italic += 1,
+            Tag::Strong => self.style.bold += 1,
+            Tag::Strikethrough => self.style.strike += 1,
+            Tag::Image { .. } => self.text("[Image: "),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::FootnoteDefinition => self.close(),
+            TagEnd::TableRow | TagEnd::TableHead => self.close(),
+            TagEnd::TableCell => self.runs.push_str("<w:r><w:tab/></w:r>"),
+            TagEnd::BlockQuote => {
+                self.close();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => self.in_code_block = false,
+            TagEnd::List(_) => {
+                self.close();
+                self.lists.pop();
+            }
+            TagEnd::Emphasis => self.style.italic = self.style.italic.saturating_sub(1),
+            TagEnd::Strong => self.style.bold = self.style.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.style.strike = self.style.strike.saturating_sub(1),
+            TagEnd::Image => self.text("]"),
+            _ => {}
+        }
+    }
+}
+
+/// Word count for a title page, rounded to the nearest hundred
+fn rounded_word_count(words: usize) -> String {
+    let rounded = ((words + 50) / 100 * 100).max(100);
+    // Thousands separators
+    let digits = rounded.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn document_xml(documents: &[SourceDocument], title: &str, author: Option<&str>, preset: Preset) -> String {
+    let mut writer = BodyWriter::new();
+    for doc in documents {
+        for event in Parser::new_ext(&doc.markdown, Options::all()) {
+            writer.event(event);
+        }
+        writer.close();
+    }
+
+    let mut body = String::new();
+    if preset.is_manuscript() {
+        body.push_str(&title_page(title, author, writer.words));
+        // The story starts on a new page; H1 headings already do
+        if !writer.body.starts_with("<w:p><w:pPr><w:pStyle w:val=\"Heading1\"/>") {
+            body.push_str("<w:p><w:r><w:br w:type=\"page\"/></w:r></w:p>\n");
+        }
+    }
+    body.push_str(&writer.body);
+
+    let header = if preset.is_manuscript() {
+        "<w:headerReference w:type=\"default\" r:id=\"rId2\"/>"
+    } else {
+        ""
+    };
+    let title_page = if preset.is_manuscript() { "<w:titlePg/>" } else { "" };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="{W_NS}" xmlns:r="{R_NS}">
+<w:body>
+{body}<w:sectPr>{header}<w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="720" w:footer="720" w:gutter="0"/>{title_page}</w:sectPr>
+</w:body>
+</w:document>
+"#
+    )
+}
+
+/// Manuscript title page: author and word count at the top, then the
+/// title and byline centered partway down the page
+fn title_page(title: &str, author: Option<&str>, words: usize) -> String {
+    let plain = RunStyle::default();
+    let mut page = format!(
+        "<w:p><w:pPr><w:pStyle w:val=\"Normal\"/><w:tabs><w:tab w:val=\"right\" w:pos=\"9360\"/></w:tabs>\
+         <w:spacing w:line=\"240\" w:lineRule=\"auto\"/><w:ind w:firstLine=\"0\"/></w:pPr>{}<w:r><w:tab/></w:r>{}</w:p>\n",
+        text_run(author.unwrap_or(""), &plain),
+        text_run(&format!("about {} words", rounded_word_count(words)), &plain),
+    );
+    page.push_str(&format!(
+        "<w:p><w:pPr><w:pStyle w:val=\"Normal\"/><w:spacing w:before=\"4320\"/><w:ind w:firstLine=\"0\"/>\
+         <w:jc w:val=\"center\"/></w:pPr>{}</w:p>\n",
+        text_run(title, &plain),
+    ));
+    if let Some(author) = author {
+        page.push_str(&format!(
+            "<w:p><w:pPr><w:pStyle w:val=\"Normal\"/><w:ind w:firstLine=\"0\"/><w:jc w:val=\"center\"/></w:pPr>{}</w:p>\n",
+            text_run(&format!("by {}", author), &plain),
+        ));
+    }
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(markdown: &str) -> Vec<SourceDocument> {
+        vec![SourceDocument::from_markdown("draft", markdown)]
+    }
+
+    #[test]
+    fn test_rounded_word_count() {
+        assert_eq!(rounded_word_count(12), "100");
+        assert_eq!(rounded_word_count(1249), "1,200");
+        assert_eq!(rounded_word_count(1250), "1,300");
+        assert_eq!(rounded_word_count(84_960), "85,000");
+    }
+
+    #[test]
+    fn test_body_mapping() {
+        let xml = document_xml(
+            &doc("# One\n\nShe *ran* and **ran**.\n\n***\n\n> Quoted\n\n- item\n"),
+            "Book",
+            None,
+            Preset::Standard,
+        );
+        assert!(xml.contains("<w:pStyle w:val=\"Heading1\"/></w:pPr><w:r><w:t xml:space=\"preserve\">One</w:t></w:r>"));
+        assert!(xml.contains("<w:r><w:rPr><w:i/></w:rPr><w:t xml:space=\"preserve\">ran</w:t></w:r>"));
+        assert!(xml.contains("<w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">ran</w:t></w:r>"));
+        assert!(xml.contains("<w:pStyle w:val=\"SceneBreak\"/></w:pPr><w:r><w:t xml:space=\"preserve\">#</w:t>"));
+        assert!(xml.contains("<w:pStyle w:val=\"Quote\"/></w:pPr><w:r><w:t xml:space=\"preserve\">Quoted</w:t>"));
+        assert!(xml.contains("<w:pStyle w:val=\"ListParagraph\"/></w:pPr><w:r><w:t xml:space=\"preserve\">\u{2022}\t</w:t>"));
+        // No title page or header outside the manuscript preset
+        assert!(!xml.contains("headerReference"));
+    }
+
+    #[test]
+    fn test_footnote_label_starts_its_note() {
+        let xml = document_xml(&doc("Text.[^1]\n\n[^1]: The note.\n"), "Book", None, Preset::Standard);
+        assert!(xml.contains(
+            "<w:p><w:pPr><w:pStyle w:val=\"Normal\"/></w:pPr><w:r><w:t xml:space=\"preserve\">1. </w:t></w:r>\
+             <w:r><w:t xml:space=\"preserve\">The note.</w:t></w:r></w:p>"
+        ));
+    }
+
+    #[test]
+    fn test_manuscript_title_page_and_header() {
+        let words = "word ".repeat(1260);
+        let xml = document_xml(&doc(&format!("Opening.\n\n{}", words)), "The Long Road", Some("Ann Lee"), Preset::Manuscript);
+        assert!(xml.contains("about 1,300 words"));
+        assert!(xml.contains(">by Ann Lee<"));
+        assert!(xml.contains("<w:br w:type=\"page\"/>"));
+        assert!(xml.contains("<w:headerReference w:type=\"default\" r:id=\"rId2\"/>"));
+        assert!(xml.contains("<w:titlePg/>"));
+
+        let header = header_xml("The Long Road", Some("Ann Lee"));
+        assert!(header.contains("Lee / THE LONG ROAD / "));
+        assert!(header.contains(" PAGE "));
+
+        let styles = styles_xml(Preset::Manuscript);
+        assert!(styles.contains("w:ascii=\"Times New Roman\""));
+        assert!(styles.contains("w:line=\"480\""));
+        assert!(styles_xml(Preset::ManuscriptCourier).contains("w:ascii=\"Courier New\""));
+    }
+
+    #[test]
+    fn test_build_docx_rejects_unknown_preset() {
+        let options = ExportOptions {
+            preset: Some("novella".to_string()),
+            ..Default::default()
+        };
+        assert!(build_docx(&doc("Text"), &options).is_err());
+
+        let options = ExportOptions {
+            preset: Some("manuscript".to_string()),
+            ..Default::default()
+        };
+        let data = build_docx(&doc("Text"), &options).unwrap();
+        let names = String::from_utf8_lossy(&data);
+        for part in ["[Content_Types].xml", "_rels/.rels", "word/document.xml", "word/styles.xml", "word/header1.xml"] {
+            assert!(names.contains(part), "missing {}", part);
+        }
+    }
+}
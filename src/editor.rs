@@ -4,6 +4,8 @@ use std::io::{self, Write};
 use std::path::Path;
 use std::time::Instant;
 
+use crate::{frontmatter, width};
+
 /// Direction for cursor movement
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Direction {
@@ -42,6 +44,7 @@ pub struct Editor {
     undo_stack: Vec<UndoItem>,
     redo_stack: Vec<UndoItem>,
     sticky_col: Option<usize>,
+    tab_width: usize,
     last_edit_time: Option<Instant>,
     backup_created: bool,
     original_content: Option<String>,
@@ -59,6 +62,7 @@ impl Editor {
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
             sticky_col: None,
+            tab_width: 4,
             last_edit_time: None,
             backup_created: false,
             original_content: None,
@@ -164,6 +168,22 @@ impl Editor {
         self.sticky_col = None;
     }
 
+    /// Insert an indent: `tab_width` spaces, or a literal tab when `expand` is false
+    pub fn insert_tab(&mut self, expand: bool) {
+        if expand {
+            for _ in 0..self.tab_width {
+                self.insert_char(' ');
+            }
+        } else {
+            self.insert_char('\t');
+        }
+    }
+
+    /// Set the tab width used for indents and display columns
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width.max(1);
+    }
+
     /// Insert a newline at the cursor position
     pub fn insert_newline(&mut self) {
         self.insert_char('\n');
@@ -297,6 +317,53 @@ impl Editor {
         }
     }
 
+    /// Replace char ranges with new text as a single undo step
+    /// Ranges must be sorted and must not overlap
+    pub fn replace_ranges(&mut self, replacements: &[(usize, usize, String)]) {
+        if replacements.is_empty() {
+            return;
+        }
+
+        // Work from the end so earlier positions stay valid
+        let mut items = Vec::new();
+        for (start, end, text) in replacements.iter().rev() {
+            let removed = self.rope.slice(*start..*end).to_string();
+            self.rope.remove(*start..*end);
+            self.rope.insert(*start, text);
+            items.push(UndoItem::Insert { pos: *start, text: removed });
+            items.push(UndoItem::Delete { pos: *start, text: text.clone() });
+        }
+
+        self.last_edit_time = None;
+        self.push_undo(UndoItem::Group(items));
+        self.last_edit_time = None;
+        self.modified = true;
+
+        let cursor = self.cursor_char_pos();
+        self.set_cursor_from_char_pos(cursor);
+    }
+
+    /// Char range of the paragraph around the cursor, bounded by blank lines
+    pub fn paragraph_range(&self) -> (usize, usize) {
+        if self.is_blank_line(self.cursor_line) {
+            let pos = self.cursor_char_pos();
+            return (pos, pos);
+        }
+
+        let mut first = self.cursor_line;
+        while first > 0 && !self.is_blank_line(first - 1) {
+            first -= 1;
+        }
+        let mut last = self.cursor_line;
+        while last + 1 < self.rope.len_lines() && !self.is_blank_line(last + 1) {
+            last += 1;
+        }
+
+        let start = self.rope.line_to_char(first);
+        let end = self.rope.line_to_char(last) + self.line_text(last).chars().count();
+        (start, end)
+    }
+
     /// Undo the last operation
     pub fn undo(&mut self) {
         if let Some(item) = self.undo_stack.pop() {
@@ -333,11 +400,12 @@ impl Editor {
                 }
             }
             UndoItem::Group(items) => {
+                // Undo runs the items last to first; the inverse group must
+                // run them first to last, so it keeps them in the order applied
                 let mut inverse_items = Vec::new();
                 for item in items.iter().rev() {
                     inverse_items.push(self.apply_undo_item(item));
                 }
-                inverse_items.reverse();
                 UndoItem::Group(inverse_items)
             }
         }
@@ -398,19 +466,9 @@ impl Editor {
 
     fn move_left(&mut self) {
         if self.cursor_col > 0 {
-            // Move back one character
-            let line_start = self.rope.line_to_byte(self.cursor_line);
-            let current_byte = line_start + self.cursor_col;
-            let char_pos = self.rope.byte_to_char(current_byte);
-            if char_pos > 0 {
-                let prev_char = self.rope.char(char_pos - 1);
-                if prev_char == '\n' {
-                    // Don't cross line boundary here
-                    self.cursor_col = 0;
-                } else {
-                    self.cursor_col -= prev_char.len_utf8();
-                }
-            }
+            // Move back one grapheme, keeping combining marks with their base
+            let text = self.line_text(self.cursor_line);
+            self.cursor_col = width::prev_boundary(&text, self.cursor_col);
         } else if self.cursor_line > 0 {
             // Wrap to end of previous line
             self.cursor_line -= 1;
@@ -422,14 +480,9 @@ impl Editor {
     fn move_right(&mut self) {
         let line_len = self.line_len(self.cursor_line);
         if self.cursor_col < line_len {
-            // Move forward one character
-            let line_start = self.rope.line_to_byte(self.cursor_line);
-            let current_byte = line_start + self.cursor_col;
-            let char_pos = self.rope.byte_to_char(current_byte);
-            if char_pos < self.rope.len_chars() {
-                let current_char = self.rope.char(char_pos);
-                self.cursor_col += current_char.len_utf8();
-            }
+            // Move forward one grapheme
+            let text = self.line_text(self.cursor_line);
+            self.cursor_col = width::next_boundary(&text, self.cursor_col);
         } else if self.cursor_line + 1 < self.rope.len_lines() {
             // Wrap to start of next line
             self.cursor_line += 1;
@@ -440,24 +493,28 @@ impl Editor {
 
     fn move_up(&mut self) {
         if self.cursor_line > 0 {
-            let target_col = self.sticky_col.unwrap_or(self.cursor_col);
-            self.cursor_line -= 1;
-            let new_line_len = self.line_len(self.cursor_line);
-            self.cursor_col = target_col.min(new_line_len);
-            self.sticky_col = Some(target_col);
+            self.move_to_line(self.cursor_line - 1);
         }
     }
 
     fn move_down(&mut self) {
         if self.cursor_line + 1 < self.rope.len_lines() {
-            let target_col = self.sticky_col.unwrap_or(self.cursor_col);
-            self.cursor_line += 1;
-            let new_line_len = self.line_len(self.cursor_line);
-            self.cursor_col = target_col.min(new_line_len);
-            self.sticky_col = Some(target_col);
+            self.move_to_line(self.cursor_line + 1);
         }
     }
 
+    /// Move to another line, keeping the cursor's display column where possible
+    fn move_to_line(&mut self, line: usize) {
+        let target_col = self
+            .sticky_col
+            .unwrap_or_else(|| {
+                width::byte_to_col(&self.line_text(self.cursor_line), self.cursor_col, self.tab_width)
+            });
+        self.cursor_line = line;
+        self.cursor_col = width::col_to_byte(&self.line_text(line), target_col, self.tab_width);
+        self.sticky_col = Some(target_col);
+    }
+
     fn move_word_forward(&mut self) {
         let line_start = self.rope.line_to_char(self.cursor_line);
         let mut char_pos = line_start + self.cursor_col_chars();
@@ -591,19 +648,11 @@ impl Editor {
     }
 
     fn move_page_up(&mut self, height: usize) {
-        let target_col = self.sticky_col.unwrap_or(self.cursor_col);
-        self.cursor_line = self.cursor_line.saturating_sub(height);
-        let new_line_len = self.line_len(self.cursor_line);
-        self.cursor_col = target_col.min(new_line_len);
-        self.sticky_col = Some(target_col);
+        self.move_to_line(self.cursor_line.saturating_sub(height));
     }
 
     fn move_page_down(&mut self, height: usize) {
-        let target_col = self.sticky_col.unwrap_or(self.cursor_col);
-        self.cursor_line = (self.cursor_line + height).min(self.rope.len_lines().saturating_sub(1));
-        let new_line_len = self.line_len(self.cursor_line);
-        self.cursor_col = target_col.min(new_line_len);
-        self.sticky_col = Some(target_col);
+        self.move_to_line((self.cursor_line + height).min(self.rope.len_lines().saturating_sub(1)));
     }
 
     /// Get cursor position in chars within the current line
@@ -620,14 +669,20 @@ impl Editor {
         cursor_char - line_start_char
     }
 
+    /// Get cursor position as a char offset in the document
+    pub fn cursor_char_pos(&self) -> usize {
+        self.rope.byte_to_char(self.cursor_byte_offset())
+    }
+
     /// Set cursor position from a char position
-    fn set_cursor_from_char_pos(&mut self, char_pos: usize) {
+    pub fn set_cursor_from_char_pos(&mut self, char_pos: usize) {
         let clamped = char_pos.min(self.rope.len_chars());
         self.cursor_line = self.rope.char_to_line(clamped);
         let line_start_char = self.rope.line_to_char(self.cursor_line);
         let line_start_byte = self.rope.char_to_byte(line_start_char);
         let cursor_byte = self.rope.char_to_byte(clamped);
         self.cursor_col = cursor_byte - line_start_byte;
+        self.sticky_col = None;
     }
 
     /// Get byte offset of cursor position
@@ -640,6 +695,18 @@ impl Editor {
         line_start + self.cursor_col.min(line_len)
     }
 
+    /// Get a line's text without its trailing newline
+    fn line_text(&self, line: usize) -> String {
+        if line >= self.rope.len_lines() {
+            return String::new();
+        }
+        let mut text = self.rope.line(line).to_string();
+        if text.ends_with('\n') {
+            text.pop();
+        }
+        text
+    }
+
     /// Get length of a line in bytes (excluding newline)
     fn line_len(&self, line: usize) -> usize {
         if line >= self.rope.len_lines() {
@@ -670,13 +737,10 @@ impl Editor {
         self.modified
     }
 
-    /// Count words in the document
+    /// Count words in the document, leaving out front matter
     pub fn word_count(&self) -> usize {
-        self.rope
-            .chars()
-            .collect::<String>()
-            .split_whitespace()
-            .count()
+        let text = self.rope.to_string();
+        frontmatter::body(&text).split_whitespace().count()
     }
 
     /// Get number of lines
@@ -785,6 +849,13 @@ mod tests {
         assert_eq!(editor.word_count(), 3);
     }
 
+    #[test]
+    fn test_word_count_skips_front_matter() {
+        let mut editor = Editor::new();
+        editor.set_content("---\ntitle: The Long Road\nstatus: draft\n---\nHello world test");
+        assert_eq!(editor.word_count(), 3);
+    }
+
     #[test]
     fn test_move_left_right() {
         let mut editor = Editor::new();
@@ -925,4 +996,87 @@ mod tests {
         editor.move_cursor(Direction::Up, Unit::Paragraph);
         assert!(editor.cursor_position().0 < before);
     }
+
+    #[test]
+    fn test_move_by_grapheme() {
+        let mut editor = Editor::new();
+        editor.set_content("ae\u{301}b");
+        editor.move_cursor(Direction::Right, Unit::Char);
+        assert_eq!(editor.cursor_position(), (0, 1));
+        // e + combining acute is stepped over as one
+        editor.move_cursor(Direction::Right, Unit::Char);
+        assert_eq!(editor.cursor_position(), (0, 4));
+        editor.move_cursor(Direction::Left, Unit::Char);
+        assert_eq!(editor.cursor_position(), (0, 1));
+    }
+
+    #[test]
+    fn test_move_up_down_keeps_display_column() {
+        let mut editor = Editor::new();
+        editor.set_content("世界abc\nabcdef");
+        editor.move_cursor(Direction::Right, Unit::Char);
+        editor.move_cursor(Direction::Right, Unit::Char);
+        // After two wide chars: byte 6, display column 4
+        assert_eq!(editor.cursor_position(), (0, 6));
+        editor.move_cursor(Direction::Down, Unit::Line);
+        assert_eq!(editor.cursor_position(), (1, 4));
+        editor.move_cursor(Direction::Left, Unit::Char);
+        editor.move_cursor(Direction::Up, Unit::Line);
+        // Column 3 falls inside the second wide char and snaps to its start
+        assert_eq!(editor.cursor_position(), (0, 3));
+    }
+
+    #[test]
+    fn test_insert_tab() {
+        let mut editor = Editor::new();
+        editor.set_tab_width(2);
+        editor.insert_tab(true);
+        assert_eq!(editor.content().to_string(), "  ");
+        editor.insert_tab(false);
+        assert_eq!(editor.content().to_string(), "  \t");
+        assert_eq!(editor.cursor_position(), (0, 3));
+    }
+
+    #[test]
+    fn test_move_down_across_tab() {
+        let mut editor = Editor::new();
+        editor.set_content("\tx\nabcdef");
+        editor.move_cursor(Direction::Right, Unit::Char);
+        // Past the tab is display column 4
+        editor.move_cursor(Direction::Down, Unit::Line);
+        assert_eq!(editor.cursor_position(), (1, 4));
+    }
+
+    #[test]
+    fn test_replace_ranges_single_undo() {
+        let mut editor = Editor::new();
+        editor.set_content("Anna met Anna at noon.");
+        editor.replace_ranges(&[(0, 4, "Hanna".to_string()), (9, 13, "Hanna".to_string())]);
+        assert_eq!(editor.content().to_string(), "Hanna met Hanna at noon.");
+
+        editor.undo();
+        assert_eq!(editor.content().to_string(), "Anna met Anna at noon.");
+        editor.redo();
+        assert_eq!(editor.content().to_string(), "Hanna met Hanna at noon.");
+    }
+
+    #[test]
+    fn test_paragraph_range() {
+        let mut editor = Editor::new();
+        editor.set_content("one\n\ntwo\nthree\n\nfour");
+        editor.move_cursor(Direction::Down, Unit::Line);
+        editor.move_cursor(Direction::Down, Unit::Line);
+        assert_eq!(editor.paragraph_range(), (5, 14));
+    }
+
+    #[test]
+    fn test_redo_grouped_typing() {
+        let mut editor = Editor::new();
+        editor.insert_char('a');
+        editor.insert_char('b');
+        editor.undo();
+        assert_eq!(editor.content().to_string(), "");
+        editor.redo();
+        assert_eq!(editor.content().to_string(), "ab");
+    }
 }
@@ -0,0 +1,529 @@
+//! Export markdown documents to EPUB 3.
+//!
+//! Each document is split into chapters at its H1 headings, and each chapter
+//! becomes one XHTML file. The package also holds a navigation document, the
+//! stylesheet and an optional cover image.
+
+use chrono::Utc;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::archive::ZipWriter;
+use crate::export::{self, html_escape, ExportOptions, Exporter, Metadata, SourceDocument};
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// One XHTML file of the book.
+struct Chapter {
+    title: String,
+    markdown: String,
+    /// Index of the source document, for resolving images
+    document: usize,
+}
+
+/// EPUB books, one chapter per H1 heading.
+pub struct EpubExporter<'a> {
+    options: &'a ExportOptions,
+}
+
+impl<'a> EpubExporter<'a> {
+    pub fn new(options: &'a ExportOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Exporter for EpubExporter<'_> {
+    fn name(&self) -> &'static str {
+        "epub"
+    }
+
+    fn extension(&self) -> &'static str {
+        "epub"
+    }
+
+    fn export(&self, documents: &[SourceDocument], _metadata: &Metadata) -> io::Result<Vec<u8>> {
+        build_epub(documents, self.options)
+    }
+}
+
+/// Build an EPUB package in memory.
+pub fn build_epub(documents: &[SourceDocument], options: &ExportOptions) -> io::Result<Vec<u8>> {
+    let chapters: Vec<Chapter> = documents
+        .iter()
+        .enumerate()
+        .flat_map(|(i, doc)| {
+            let mut chapters = split_chapters(&doc.markdown, &doc.name);
+            chapters.iter_mut().for_each(|c| c.document = i);
+            carry_footnotes(&doc.markdown, &mut chapters);
+            chapters
+        })
+        .collect();
+
+    let title = options.title.clone()
+        .or_else(|| documents.first().and_then(|doc| export::extract_title(&doc.markdown)))
+        .unwrap_or_else(|| "Untitled".to_string());
+    let language = options.language.clone().unwrap_or_else(|| "en".to_string());
+    let css = options.css.as_deref().unwrap_or(export::DEFAULT_CSS);
+    let cover = match &options.cover {
+        Some(path) => Some((image_media_type(path)?, path, fs::read(path)?)),
+        None => None,
+    };
+
+    let mut zip = ZipWriter::new();
+    // The mimetype must come first, uncompressed
+    zip.add_stored("mimetype", b"application/epub+zip");
+    zip.add_deflated("META-INF/container.xml", CONTAINER_XML.as_bytes())?;
+    zip.add_deflated("OEBPS/style.css", css.as_bytes())?;
+
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    manifest.push_str(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    manifest.push_str("    <item id=\"css\" href=\"style.css\" media-type=\"text/css\"/>\n");
+
+    if let Some((media_type, path, data)) = &cover {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("img").to_lowercase();
+        let image = format!("cover.{}", extension);
+        zip.add_stored(&format!("OEBPS/{}", image), data);
+        let body = format!(
+            "<section epub:type=\"cover\" class=\"cover\">\n<img src=\"{}\" alt=\"{}\"/>\n</section>",
+            image,
+            html_escape(&title)
+        );
+        zip.add_deflated("OEBPS/cover.xhtml", xhtml_page(&title, &language, &body).as_bytes())?;
+        manifest.push_str(&format!(
+            "    <item id=\"cover-image\" href=\"{}\" media-type=\"{}\" properties=\"cover-image\"/>\n",
+            image, media_type
+        ));
+        manifest.push_str("    <item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n");
+        spine.push_str("    <itemref idref=\"cover\"/>\n");
+    }
+
+    let mut toc = String::new();
+    // Local images, by source path, and their names in the package
+    let mut images: HashMap<PathBuf, String> = HashMap::new();
+    for (i, chapter) in chapters.iter().enumerate() {
+        let id = format!("chapter-{:03}", i + 1);
+        let mut html = chapter_html(&chapter.markdown);
+
+        let base_dir = documents[chapter.document].base_dir.as_deref().unwrap_or(Path::new("."));
+        for url in local_images(&chapter.markdown) {
+            let source = base_dir.join(&url);
+            let Ok(media_type) = image_media_type(&source) else { continue };
+            if !source.is_file() {
+                continue;
+            }
+            if !images.contains_key(&source) {
+                let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+                let name = format!("images/image-{:03}.{}", images.len() + 1, extension);
+                zip.add_stored(&format!("OEBPS/{}", name), &fs::read(&source)?);
+                manifest.push_str(&format!(
+                    "    <item id=\"image-{:03}\" href=\"{}\" media-type=\"{}\"/>\n",
+                    images.len() + 1,
+                    name,
+                    media_type
+                ));
+                images.insert(source.clone(), name);
+            }
+            html = html.replace(&format!("src=\"{}\"", url), &format!("src=\"{}\"", images[&source]));
+        }
+
+        let body = format!("<section epub:type=\"chapter\">\n{}</section>", html);
+        let page = xhtml_page(&chapter.title, &language, &body);
+        zip.add_deflated(&format!("OEBPS/{}.xhtml", id), page.as_bytes())?;
+        manifest.push_str(&format!(
+            "    <item id=\"{}\" href=\"{}.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+            id, id
+        ));
+        spine.push_str(&format!("    <itemref idref=\"{}\"/>\n", id));
+        toc.push_str(&format!(
+            "<li><a href=\"{}.xhtml\">{}</a></li>\n",
+            id,
+            html_escape(&chapter.title)
+        ));
+    }
+
+    let nav = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n{}</ol>\n</nav>",
+        toc
+    );
+    zip.add_deflated("OEBPS/nav.xhtml", xhtml_page(&title, &language, &nav).as_bytes())?;
+
+    let mut metadata = format!(
+        "    <dc:identifier id=\"book-id\">urn:uuid:{}</dc:identifier>\n\
+         \x20   <dc:title>{}</dc:title>\n\
+         \x20   <dc:language>{}</dc:language>\n\
+         \x20   <dc:date>{}</dc:date>\n\
+         \x20   <meta property=\"dcterms:modified\">{}</meta>\n",
+        book_id(&title, &chapters),
+        html_escape(&title),
+        html_escape(&language),
+        html_escape(&options.date.clone().unwrap_or_else(export::today)),
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+    );
+    if let Some(author) = &options.author {
+        metadata.push_str(&format!("    <dc:creator>{}</dc:creator>\n", html_escape(author)));
+    }
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{}\">\n\
+         \x20 <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{}  </metadata>\n\
+         \x20 <manifest>\n{}  </manifest>\n\
+         \x20 <spine>\n{}  </spine>\n\
+         </package>\n",
+        html_escape(&language),
+        metadata,
+        manifest,
+        spine
+    );
+    zip.add_deflated("OEBPS/content.opf", opf.as_bytes())?;
+
+    Ok(zip.finish())
+}
+
+/// Split a document at its H1 headings. Text before the first heading
+/// becomes a chapter of its own, named after the document.
+fn split_chapters(markdown: &str, name: &str) -> Vec<Chapter> {
+    let mut starts: Vec<(usize, String)> = Vec::new();
+    let mut heading: Option<(usize, String)> = None;
+    for (event, range) in Parser::new_ext(markdown, Options::all()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level: HeadingLevel::H1, .. }) => {
+                heading = Some((range.start, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = heading.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) => starts.extend(heading.take()),
+            _ => {}
+        }
+    }
+
+    let mut chapters = Vec::new();
+    let first = starts.first().map_or(markdown.len(), |(start, _)| *start);
+    if !markdown[..first].trim().is_empty() || starts.is_empty() {
+        chapters.push(Chapter {
+            title: name.to_string(),
+            markdown: markdown[..first].to_string(),
+            document: 0,
+        });
+    }
+    for (i, (start, title)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(markdown.len(), |(next, _)| *next);
+        chapters.push(Chapter {
+            title: title.trim().to_string(),
+            markdown: markdown[*start..end].to_string(),
+            document: 0,
+        });
+    }
+    chapters
+}
+
+/// Footnotes are usually defined at the end of a document, which after
+/// splitting is a different file from the reference. Give each chapter a
+/// copy of the definitions it refers to.
+fn carry_footnotes(markdown: &str, chapters: &mut [Chapter]) {
+    let mut definitions: HashMap<String, &str> = HashMap::new();
+    let mut open: Option<(String, usize)> = None;
+    for (event, range) in Parser::new_ext(markdown, Options::all()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => open = Some((label.to_string(), range.start)),
+            Event::End(TagEnd::FootnoteDefinition) => {
+                if let Some((label, start)) = open.take() {
+                    definitions.insert(label, markdown[start..range.end].trim_end());
+                }
+            }
+            _ => {}
+        }
+    }
+    if definitions.is_empty() {
+        return;
+    }
+
+    for chapter in chapters.iter_mut() {
+        let mut referenced = Vec::new();
+        let mut defined = HashSet::new();
+        for event in Parser::new_ext(&chapter.markdown, Options::all()) {
+            match event {
+                Event::FootnoteReference(label) => referenced.push(label.to_string()),
+                Event::Start(Tag::FootnoteDefinition(label)) => {
+                    defined.insert(label.to_string());
+                }
+                _ => {}
+            }
+        }
+        for label in referenced {
+            if defined.insert(label.clone()) {
+                if let Some(definition) = definitions.get(&label) {
+                    chapter.markdown.push_str("\n\n");
+                    chapter.markdown.push_str(definition);
+                    chapter.markdown.push('\n');
+                }
+            }
+        }
+    }
+}
+
+/// Render a chapter's markdown as XHTML. Raw HTML is dropped, since it
+/// needn't be well-formed XML; a `<br>` becomes a line break. Footnote
+/// labels are slugged into ids, as labels may be numbers or contain spaces.
+fn chapter_html(markdown: &str) -> String {
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut used = HashSet::new();
+    let mut id = |label: &str| -> String {
+        ids.entry(label.to_string())
+            .or_insert_with(|| format!("fn-{}", export::unique_slug(label, &mut used)))
+            .clone()
+    };
+    let events = Parser::new_ext(markdown, Options::all()).filter_map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => {
+            let tag = html.trim().trim_end_matches('>').trim_end_matches('/').trim_end();
+            tag.eq_ignore_ascii_case("<br").then_some(Event::HardBreak)
+        }
+        Event::FootnoteReference(label) => Some(Event::FootnoteReference(id(&label).into())),
+        Event::Start(Tag::FootnoteDefinition(label)) => Some(Event::Start(Tag::FootnoteDefinition(id(&label).into()))),
+        event => Some(event),
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+/// Relative image paths in a chapter that can be packaged. Only plain paths
+/// are taken, since those appear unchanged in the rendered `src` attribute.
+fn local_images(markdown: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    for event in Parser::new_ext(markdown, Options::all()) {
+        if let Event::Start(Tag::Image { dest_url, .. }) = event {
+            let plain = dest_url
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._/-".contains(c));
+            if plain && !dest_url.starts_with('/') && !urls.contains(&dest_url.to_string()) {
+                urls.push(dest_url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/// Wrap body markup in an XHTML document.
+fn xhtml_page(title: &str, language: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head>
+<meta charset="UTF-8"/>
+<title>{title}</title>
+<link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        lang = html_escape(language),
+        title = html_escape(title),
+        body = body
+    )
+}
+
+/// Media type of an image, from its extension.
+fn image_media_type(path: &Path) -> io::Result<&'static str> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => Ok("image/jpeg"),
+        "png" => Ok("image/png"),
+        "gif" => Ok("image/gif"),
+        "svg" => Ok("image/svg+xml"),
+        "webp" => Ok("image/webp"),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported image type: {}", path.display()),
+        )),
+    }
+}
+
+/// A stable identifier for the book, derived from its title and text, so
+/// re-exporting the same book keeps its identity in readers' libraries.
+fn book_id(title: &str, chapters: &[Chapter]) -> String {
+    // 0xff never occurs in UTF-8, so it separates the texts unambiguously
+    let text = || {
+        title.bytes().chain([0xff]).chain(chapters.iter().flat_map(|c| c.markdown.bytes().chain([0xff])))
+    };
+    let halves = [fnv1a([0].into_iter().chain(text())), fnv1a([1].into_iter().chain(text()))];
+    // RFC 9562 version 8 (custom) UUID
+    let bits = (u128::from(halves[0]) << 64 | u128::from(halves[1]))
+        & !(0xF << 76)
+        & !(0x3 << 62)
+        | (0x8 << 76)
+        | (0x2 << 62);
+    let hex = format!("{:032x}", bits);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// 64-bit FNV-1a. Unlike the standard library's hasher, its output is fixed,
+/// so a book keeps its id across builds of hollow.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes
+        .into_iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    /// Read every file out of a ZIP archive built by `ZipWriter`
+    fn unzip(data: &[u8]) -> Vec<(String, String)> {
+        let u16_at = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+        let u32_at = |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]) as usize;
+
+        let mut files = Vec::new();
+        let mut at = 0;
+        while u32_at(at) == 0x0403_4b50 {
+            let method = u16_at(at + 8);
+            let size = u32_at(at + 18);
+            let name_len = u16_at(at + 26);
+            let name = String::from_utf8_lossy(&data[at + 30..at + 30 + name_len]).into_owned();
+            let raw = &data[at + 30 + name_len..at + 30 + name_len + size];
+            let mut text = String::new();
+            if method == 8 {
+                DeflateDecoder::new(raw).read_to_string(&mut text).unwrap();
+            } else {
+                text = String::from_utf8_lossy(raw).into_owned();
+            }
+            files.push((name, text));
+            at += 30 + name_len + size;
+        }
+        files
+    }
+
+    fn file<'a>(files: &'a [(String, String)], name: &str) -> &'a str {
+        &files.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    #[test]
+    fn test_split_chapters() {
+        let chapters = split_chapters("Preface.\n\n# One\n\nText.\n\n```\n# not a heading\n```\n\nTwo\n===\n", "book");
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["book", "One", "Two"]);
+        assert!(chapters[1].markdown.contains("# not a heading"));
+
+        let chapters = split_chapters("Just text.", "notes");
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].title, "notes");
+    }
+
+    #[test]
+    fn test_epub_package() {
+        let documents = vec![
+            SourceDocument::from_markdown("one", "# Start & End\n\nOnce."),
+            SourceDocument::from_markdown("two", "No heading here."),
+        ];
+        let options = ExportOptions {
+            author: Some("Ann Lee".to_string()),
+            language: Some("en-GB".to_string()),
+            ..Default::default()
+        };
+        let files = unzip(&build_epub(&documents, &options).unwrap());
+
+        assert_eq!(files[0], ("mimetype".to_string(), "application/epub+zip".to_string()));
+        assert!(file(&files, "META-INF/container.xml").contains("OEBPS/content.opf"));
+
+        let opf = file(&files, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>Start &amp; End</dc:title>"));
+        assert!(opf.contains("<dc:creator>Ann Lee</dc:creator>"));
+        assert!(opf.contains("<dc:language>en-GB</dc:language>"));
+        assert!(opf.contains("<itemref idref=\"chapter-002\"/>"));
+
+        let nav = file(&files, "OEBPS/nav.xhtml");
+        assert!(nav.contains("<a href=\"chapter-001.xhtml\">Start &amp; End</a>"));
+        assert!(nav.contains("<a href=\"chapter-002.xhtml\">two</a>"));
+
+        let chapter = file(&files, "OEBPS/chapter-001.xhtml");
+        assert!(chapter.starts_with("<?xml"));
+        assert!(chapter.contains("<p>Once.</p>"));
+    }
+
+    #[test]
+    fn test_footnotes_and_images_are_packaged() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("img")).unwrap();
+        fs::write(dir.path().join("img/map.png"), b"png").unwrap();
+        let documents = vec![SourceDocument {
+            base_dir: Some(dir.path().to_path_buf()),
+            ..SourceDocument::from_markdown("book", "# One\n\nSee[^1] ![Map](img/map.png)\n\n# Notes\n\n[^1]: The note.\n")
+        }];
+        let files = unzip(&build_epub(&documents, &ExportOptions::default()).unwrap());
+
+        let chapter = file(&files, "OEBPS/chapter-001.xhtml");
+        assert!(chapter.contains("<a href=\"#fn-1\">"));
+        assert!(chapter.contains("id=\"fn-1\""));
+        assert!(chapter.contains("src=\"images/image-001.png\""));
+        assert_eq!(file(&files, "OEBPS/images/image-001.png"), "png");
+        assert!(file(&files, "OEBPS/content.opf").contains("href=\"images/image-001.png\" media-type=\"image/png\""));
+    }
+
+    #[test]
+    fn test_chapter_html_basic() {
+        let md = "# Hello\n\nThis is a paragraph.";
+        let html = chapter_html(md);
+        
+        assert!(html.contains("<h1>Hello</h1>"));
+        assert!(html.contains("<p>This is a paragraph.</p>"));
+    }
+
+    #[test]
+    fn test_chapter_html_code() {
+        let md = "Here is `inline code` and:\n\n```\ncode block\n```";
+        let html = chapter_html(md);
+        
+        assert!(html.contains("<code>inline code</code>"));
+        assert!(html.contains("<pre><code>"));
+    }
+
+    #[test]
+    fn test_chapter_html_links() {
+        let md = "Visit [example](https://example.com).";
+        let html = chapter_html(md);
+        
+        assert!(html.contains("<a href=\"https://example.com\">example</a>"));
+    }
+
+    #[test]
+    fn test_chapters_are_well_formed_xhtml() {
+        let html = chapter_html("Line<br>two <img src=\"x.png\">\n\n<div>\n\nSee[^my note].\n\n[^my note]: Spaced.\n");
+        assert!(html.contains("<p>Line<br />\ntwo </p>"));
+        assert!(!html.contains("<img") && !html.contains("<div>"));
+        assert!(html.contains("<a href=\"#fn-my-note\">"));
+        assert!(html.contains("id=\"fn-my-note\""));
+    }
+
+    #[test]
+    fn test_book_id_is_stable_uuid() {
+        let chapters = vec![Chapter { title: "A".to_string(), markdown: "Text".to_string(), document: 0 }];
+        let id = book_id("Book", &chapters);
+        assert_eq!(id, book_id("Book", &chapters));
+        assert_ne!(id, book_id("Other", &chapters));
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "8");
+        // Pinned, so a change to the hash shows up here
+        assert_eq!(id, "b16d4494-2f43-8055-bf01-677249417b0a");
+        assert_eq!(fnv1a(*b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -1,12 +1,28 @@
-//! Export markdown documents to HTML format.
+//! Export markdown documents.
+//!
+//! Every output format is an [`Exporter`] in the registry that
+//! `--format` picks from. HTML lives here; LaTeX, plain text and the
+//! packaged formats (EPUB, DOCX, PDF) have their own modules.
 
-use pulldown_cmark::{html, Options, Parser};
+use chrono::Local;
+use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+use serde_yaml::Mapping;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::config::expand_home;
+use crate::docx::DocxExporter;
+use crate::epub::EpubExporter;
+use crate::frontmatter;
+use crate::latex::LatexExporter;
+use crate::pdf::PdfExporter;
+use crate::plaintext::TextExporter;
+use crate::project::Project;
 
 /// Default CSS for exported HTML documents.
-const DEFAULT_CSS: &str = r#"
+pub const DEFAULT_CSS: &str = r#"
 body {
     max-width: 700px;
     margin: 40px auto;
@@ -47,6 +63,14 @@ blockquote {
     padding-left: 1em;
     color: #666;
 }
+.footnotes {
+    border-top: 1px solid #ddd;
+    margin-top: 3em;
+    font-size: 0.9em;
+}
+.footnote-backref {
+    text-decoration: none;
+}
 a {
     color: #0366d6;
 }
@@ -60,6 +84,21 @@ hr {
 }
 "#;
 
+/// Default HTML skeleton for exported documents.
+const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{{title}}</title>
+    <style>{{css}}</style>
+</head>
+<body>
+{{body}}
+</body>
+</html>
+"#;
+
 /// Export options.
 #[derive(Default)]
 pub struct ExportOptions {
@@ -67,59 +106,561 @@ pub struct ExportOptions {
     pub css: Option<String>,
     /// Document title for HTML head.
     pub title: Option<String>,
+    /// Custom HTML template to use instead of the default skeleton.
+    ///
+    /// Placeholders: `{{title}}`, `{{author}}`, `{{date}}`, `{{word_count}}`,
+    /// `{{toc}}`, `{{body}}` and `{{css}}`.
+    pub template: Option<String>,
+    /// Author for the `{{author}}` placeholder.
+    pub author: Option<String>,
+    /// Date for the `{{date}}` placeholder and packaged metadata; today if unset.
+    pub date: Option<String>,
+    /// Language tag for packaged formats (e.g. "en-US").
+    pub language: Option<String>,
+    /// Cover image for packaged formats.
+    pub cover: Option<PathBuf>,
+    /// Layout preset for DOCX ("standard", "manuscript").
+    pub preset: Option<String>,
+    /// Page size for PDF ("letter", "a4", "6x9", "150mmx220mm", ...).
+    pub page_size: Option<String>,
+    /// Page margins for PDF, one to four lengths in CSS order.
+    pub margins: Option<String>,
+    /// Set PDF text ragged right instead of justified.
+    pub ragged_right: bool,
+    /// LaTeX preamble to use instead of the default.
+    pub preamble: Option<String>,
+    /// Column to reflow plain text to; 0 leaves paragraphs unwrapped.
+    pub text_width: Option<usize>,
+    /// Put a table of contents at the top of HTML output.
+    pub toc: bool,
+    /// Turn straight quotes, `--` and `...` into typographic punctuation.
+    pub smart_punctuation: bool,
+}
+
+/// A markdown document to export, named for chapter titles.
+pub struct SourceDocument {
+    pub name: String,
+    /// The document body, without front matter
+    pub markdown: String,
+    /// The document's front matter, if it has any
+    pub front_matter: Mapping,
+    /// Directory that relative links and images are resolved against
+    pub base_dir: Option<PathBuf>,
 }
 
+impl SourceDocument {
+    /// Read a document, separating its front matter from the body
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Ok(Self {
+            name: document_name(path),
+            markdown: frontmatter::body(&text).to_string(),
+            front_matter: frontmatter::parse(&text).unwrap_or_default(),
+            base_dir: path.parent().map(PathBuf::from),
+        })
+    }
+
+    /// A scalar front matter field as text
+    pub fn field(&self, key: &str) -> Option<String> {
+        frontmatter::field(&self.front_matter, key)
+    }
+
+    /// A document with no front matter or base directory
+    #[cfg(test)]
+    pub fn from_markdown(name: &str, markdown: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            markdown: markdown.to_string(),
+            front_matter: Mapping::new(),
+            base_dir: None,
+        }
+    }
+}
 
-/// Export a markdown file to HTML.
-pub fn export_to_html<P: AsRef<Path>>(
-    input: P,
-    output: P,
+/// Load what to export: a single markdown file, or every document of a
+/// project when given a `.hollow-project` file or its directory.
+/// Returns the documents and the project name, if any.
+pub fn load_sources(input: &Path) -> io::Result<(Vec<SourceDocument>, Option<String>)> {
+    if let Some(project) = load_project(input)? {
+        let mut documents = Vec::new();
+        for path in document_paths(&project) {
+            documents.push(SourceDocument::read(&path)?);
+        }
+        return Ok((documents, Some(project.name)));
+    }
+
+    Ok((vec![SourceDocument::read(input)?], None))
+}
+
+/// The files an export of `input` reads: the file itself, or a project file
+/// and its documents.
+pub fn source_paths(input: &Path) -> io::Result<Vec<PathBuf>> {
+    match load_project(input)? {
+        Some(project) => {
+            let mut paths = document_paths(&project);
+            paths.extend(project.path);
+            Ok(paths)
+        }
+        None => Ok(vec![input.to_path_buf()]),
+    }
+}
+
+/// The project `input` names, if it is a `.hollow-project` file or its directory
+fn load_project(input: &Path) -> io::Result<Option<Project>> {
+    let project_file = if input.is_dir() {
+        input.join(".hollow-project")
+    } else {
+        input.to_path_buf()
+    };
+    if project_file.file_name().is_none_or(|n| n != ".hollow-project") {
+        return Ok(None);
+    }
+    Project::load(&project_file)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Paths of a project's documents, in project order
+fn document_paths(project: &Project) -> Vec<PathBuf> {
+    project
+        .documents
+        .iter()
+        .map(|doc| project.resolve_document(doc).unwrap_or_else(|| PathBuf::from(doc)))
+        .collect()
+}
+
+/// A document's file name without extension
+fn document_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Untitled".to_string())
+}
+
+/// A heading, as listed in the table of contents.
+struct TocEntry {
+    level: u8,
+    id: String,
+    text: String,
+}
+
+/// Title, author and other details of the document being exported.
+pub struct Metadata {
+    pub title: String,
+    pub author: Option<String>,
+    pub date: String,
+    pub word_count: usize,
+}
+
+impl Metadata {
+    /// Metadata from the options, falling back to the first H1 heading for the title.
+    pub fn new(documents: &[SourceDocument], options: &ExportOptions) -> Self {
+        let title = options.title.clone()
+            .or_else(|| documents.first().and_then(|doc| extract_title(&doc.markdown)))
+            .unwrap_or_else(|| "Untitled".to_string());
+        Self {
+            title,
+            author: options.author.clone(),
+            date: options.date.clone().unwrap_or_else(today),
+            word_count: documents.iter().map(|doc| prose_word_count(&doc.markdown)).sum(),
+        }
+    }
+}
+
+/// Words of prose in a markdown document, leaving out markup, code blocks,
+/// image descriptions and HTML.
+fn prose_word_count(markdown: &str) -> usize {
+    let mut text = String::new();
+    // Depth of code blocks, images and metadata blocks around the event
+    let mut hidden = 0;
+    for event in Parser::new_ext(markdown, Options::all()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::Image { .. } | Tag::MetadataBlock(_)) => hidden += 1,
+            Event::End(TagEnd::CodeBlock | TagEnd::Image | TagEnd::MetadataBlock(_)) => hidden -= 1,
+            Event::Text(part) | Event::Code(part) if hidden == 0 => text.push_str(&part),
+            // Inline markup can sit inside a word; anything else ends one
+            Event::End(TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link) => {}
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().count()
+}
+
+/// Today's date for document metadata
+pub fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// An output format for `hollow export --format`.
+pub trait Exporter {
+    /// Name given to `--format`
+    fn name(&self) -> &'static str;
+
+    /// Extension of the output file
+    fn extension(&self) -> &'static str;
+
+    /// Render the documents as the contents of the output file.
+    fn export(&self, documents: &[SourceDocument], metadata: &Metadata) -> io::Result<Vec<u8>>;
+}
+
+/// The registered exporters, configured from the export options.
+pub fn exporters(options: &ExportOptions) -> Vec<Box<dyn Exporter + '_>> {
+    vec![
+        Box::new(HtmlExporter::new(options)),
+        Box::new(LatexExporter::new(options)),
+        Box::new(TextExporter::new(options)),
+        Box::new(EpubExporter::new(options)),
+        Box::new(DocxExporter::new(options)),
+        Box::new(PdfExporter::new(options)),
+    ]
+}
+
+/// The registered exporter for a format name, if there is one.
+pub fn find_exporter<'a>(format: &str, options: &'a ExportOptions) -> Option<Box<dyn Exporter + 'a>> {
+    exporters(options).into_iter().find(|e| e.name() == format)
+}
+
+/// Markdown extensions for the text exporters; smart punctuation only when asked for.
+pub fn parser_options(options: &ExportOptions) -> Options {
+    let mut parser_options = Options::all();
+    parser_options.set(Options::ENABLE_SMART_PUNCTUATION, options.smart_punctuation);
+    parser_options
+}
+
+/// Parse documents into a single event stream with the given extensions.
+/// Footnote labels are prefixed with the number of their document, so a
+/// reference finds the definition in its own document even when chapters
+/// reuse labels; `footnote_label` gives back the label as written.
+pub fn parse_documents(documents: &[SourceDocument], parser_options: Options) -> Vec<Event<'_>> {
+    documents
+        .iter()
+        .enumerate()
+        .flat_map(|(i, doc)| {
+            Parser::new_ext(&doc.markdown, parser_options).map(move |event| match event {
+                Event::FootnoteReference(label) => Event::FootnoteReference(format!("{}:{}", i, label).into()),
+                Event::Start(Tag::FootnoteDefinition(label)) => {
+                    Event::Start(Tag::FootnoteDefinition(format!("{}:{}", i, label).into()))
+                }
+                event => event,
+            })
+        })
+        .collect()
+}
+
+/// A footnote label from `parse_documents` as it was written in its document
+pub fn footnote_label(label: &str) -> &str {
+    label.split_once(':').map_or(label, |(_, written)| written)
+}
+
+/// Export documents to a file with the given exporter.
+pub fn export_to_file(
+    exporter: &dyn Exporter,
+    documents: &[SourceDocument],
+    output: &Path,
     options: &ExportOptions,
 ) -> io::Result<()> {
-    let markdown = fs::read_to_string(&input)?;
-    let html_content = markdown_to_html(&markdown);
-    
-    let title = options.title.clone()
-        .or_else(|| extract_title(&markdown))
-        .unwrap_or_else(|| "Untitled".to_string());
-    
-    let css = options.css.as_deref().unwrap_or(DEFAULT_CSS);
-    
-    let full_html = format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>{}</title>
-    <style>{}</style>
-</head>
-<body>
-{}
-</body>
-</html>
-"#,
-        html_escape(&title),
-        css,
-        html_content
-    );
-    
-    fs::write(output, full_html)?;
-    Ok(())
-}
-
-/// Convert markdown to HTML string.
-pub fn markdown_to_html(markdown: &str) -> String {
-    let options = Options::all();
-    let parser = Parser::new_ext(markdown, options);
-    
+    let metadata = Metadata::new(documents, options);
+    fs::write(output, exporter.export(documents, &metadata)?)
+}
+
+/// Full HTML pages, filled into a template.
+pub struct HtmlExporter {
+    template: String,
+    css: String,
+    toc: bool,
+    parser_options: Options,
+}
+
+impl HtmlExporter {
+    pub fn new(options: &ExportOptions) -> Self {
+        Self {
+            template: options.template.clone().unwrap_or_else(|| DEFAULT_TEMPLATE.to_string()),
+            css: options.css.clone().unwrap_or_else(|| DEFAULT_CSS.to_string()),
+            toc: options.toc,
+            parser_options: parser_options(options),
+        }
+    }
+
+    /// Render documents as a full HTML page.
+    pub fn render(&self, documents: &[SourceDocument], metadata: &Metadata) -> String {
+        let (mut body, headings) = render_body(parse_documents(documents, self.parser_options));
+        let toc = toc_html(&headings);
+        // Templates with a {{toc}} placeholder decide where the contents go
+        if self.toc && !toc.is_empty() && !self.template.contains("{{toc}}") {
+            body = format!("{}\n{}", toc, body);
+        }
+
+        fill_template(&self.template, &[
+            ("title", &html_escape(&metadata.title)),
+            ("author", &html_escape(metadata.author.as_deref().unwrap_or(""))),
+            ("date", &metadata.date),
+            ("word_count", &metadata.word_count.to_string()),
+            ("toc", &toc),
+            ("body", &body),
+            ("css", &self.css),
+        ])
+    }
+}
+
+impl Exporter for HtmlExporter {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn export(&self, documents: &[SourceDocument], metadata: &Metadata) -> io::Result<Vec<u8>> {
+        Ok(self.render(documents, metadata).into_bytes())
+    }
+}
+
+/// Convert markdown events to HTML, giving every heading a slug id and
+/// collecting footnotes at the end. Returns the HTML and the headings found.
+fn render_body(mut events: Vec<Event<'_>>) -> (String, Vec<TocEntry>) {
+    let mut headings = Vec::new();
+    let mut used: HashSet<String> = HashSet::new();
+    let mut open: Option<(usize, String)> = None;
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { .. }) => open = Some((i, String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, heading_text)) = open.as_mut() {
+                    heading_text.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((start, text)) = open.take() else { continue };
+                let Event::Start(Tag::Heading { level, id, .. }) = &mut events[start] else { continue };
+                let id = match id {
+                    Some(id) => id.to_string(),
+                    None => {
+                        let slug = unique_slug(&text, &mut used);
+                        *id = Some(slug.clone().into());
+                        slug
+                    }
+                };
+                used.insert(id.clone());
+                headings.push(TocEntry { level: *level as u8, id, text });
+            }
+            _ => {}
+        }
+    }
+
     let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
-    html_output
+    html::push_html(&mut html_output, place_footnotes(events).into_iter());
+    (html_output, headings)
+}
+
+/// Number footnotes in order of first reference and move their definitions
+/// to a list at the end, each linking back to the places that cite it.
+fn place_footnotes(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut definitions: Vec<(String, Vec<Event>)> = Vec::new();
+    let mut body = Vec::new();
+    // Body index and label of each reference
+    let mut references: Vec<(usize, String)> = Vec::new();
+    let mut open: Option<(String, Vec<Event>)> = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => open = Some((label.to_string(), Vec::new())),
+            Event::End(TagEnd::FootnoteDefinition) => definitions.extend(open.take()),
+            event => match &mut open {
+                Some((_, events)) => events.push(event),
+                None => {
+                    if let Event::FootnoteReference(label) = &event {
+                        references.push((body.len(), label.to_string()));
+                    }
+                    body.push(event);
+                }
+            },
+        }
+    }
+    if definitions.is_empty() {
+        return body;
+    }
+
+    // As in CommonMark, the first definition of a label is the one used
+    let mut by_label: HashMap<&str, usize> = HashMap::new();
+    for (i, (label, _)) in definitions.iter().enumerate() {
+        by_label.entry(label).or_insert(i);
+    }
+    let mut numbers: Vec<Option<usize>> = vec![None; definitions.len()];
+    let mut backrefs: Vec<Vec<String>> = vec![Vec::new(); definitions.len()];
+    let mut next = 1;
+    for (at, label) in references {
+        let Some(&def) = by_label.get(label.as_str()) else { continue };
+        let number = *numbers[def].get_or_insert_with(|| {
+            next += 1;
+            next - 1
+        });
+        let id = match backrefs[def].len() {
+            0 => format!("fnref-{}", number),
+            n => format!("fnref-{}-{}", number, n + 1),
+        };
+        body[at] = Event::InlineHtml(
+            format!("<sup class=\"footnote-ref\"><a href=\"#fn-{}\" id=\"{}\">{}</a></sup>", number, id, number).into(),
+        );
+        backrefs[def].push(id);
+    }
+    // Notes nothing refers to still get listed, after the others
+    for number in numbers.iter_mut().filter(|n| n.is_none()) {
+        *number = Some(next);
+        next += 1;
+    }
+
+    let mut notes: Vec<(usize, Vec<Event>, Vec<String>)> = definitions
+        .into_iter()
+        .zip(numbers)
+        .zip(backrefs)
+        .map(|(((_, events), number), backrefs)| (number.unwrap_or_default(), events, backrefs))
+        .collect();
+    notes.sort_by_key(|(number, _, _)| *number);
+
+    body.push(Event::Html("<section class=\"footnotes\">\n<ol>\n".into()));
+    for (number, mut note, backrefs) in notes {
+        let links: String = backrefs
+            .iter()
+            .enumerate()
+            .map(|(k, id)| {
+                let mark = if k == 0 { String::new() } else { format!("<sup>{}</sup>", k + 1) };
+                format!(" <a href=\"#{}\" class=\"footnote-backref\" aria-label=\"Back to text\">\u{21a9}{}</a>", id, mark)
+            })
+            .collect();
+        // Links back go at the end of the note's last paragraph
+        if matches!(note.last(), Some(Event::End(TagEnd::Paragraph))) {
+            note.insert(note.len() - 1, Event::InlineHtml(links.into()));
+        } else if !links.is_empty() {
+            note.push(Event::Html(links.into()));
+        }
+        body.push(Event::Html(format!("<li id=\"fn-{}\">\n", number).into()));
+        body.extend(note);
+        body.push(Event::Html("</li>\n".into()));
+    }
+    body.push(Event::Html("</ol>\n</section>\n".into()));
+    body
+}
+
+/// Turn heading text into an id: lowercase words joined by hyphens.
+/// Repeats get a numeric suffix.
+pub fn unique_slug(text: &str, used: &mut HashSet<String>) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = match slug.trim_end_matches('-') {
+        "" => "section".to_string(),
+        s => s.to_string(),
+    };
+
+    let mut candidate = slug.clone();
+    let mut n = 1;
+    while used.contains(&candidate) {
+        candidate = format!("{}-{}", slug, n);
+        n += 1;
+    }
+    used.insert(candidate.clone());
+    candidate
+}
+
+/// Build a nested list of links to the document's headings.
+fn toc_html(headings: &[TocEntry]) -> String {
+    if headings.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("<nav class=\"toc\">\n");
+    // Level of the last heading in each open list
+    let mut levels: Vec<u8> = Vec::new();
+    for heading in headings {
+        match levels.last() {
+            None => {
+                out.push_str("<ul>\n");
+                levels.push(heading.level);
+            }
+            Some(&level) if heading.level > level => {
+                out.push_str("\n<ul>\n");
+                levels.push(heading.level);
+            }
+            Some(_) => {
+                out.push_str("</li>\n");
+                // Close lists until the heading is deeper than the entry the list hangs from
+                while levels.len() > 1 && heading.level <= levels[levels.len() - 2] {
+                    levels.pop();
+                    out.push_str("</ul>\n</li>\n");
+                }
+                // The list now ends with this heading; deeper ones nest under it
+                if let Some(level) = levels.last_mut() {
+                    *level = heading.level;
+                }
+            }
+        }
+        out.push_str(&format!("<li><a href=\"#{}\">{}</a>", heading.id, html_escape(&heading.text)));
+    }
+    out.push_str("</li>\n");
+    while levels.pop().is_some() {
+        out.push_str("</ul>\n");
+        if !levels.is_empty() {
+            out.push_str("</li>\n");
+        }
+    }
+    out.push_str("</nav>");
+    out
+}
+
+/// Replace `{{name}}` placeholders in one pass, so values are never rescanned.
+/// Unknown placeholders are left as they are.
+fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            rest = &rest[open..];
+            break;
+        };
+        let name = after[..close].trim();
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[open..open + close + 4]),
+        }
+        rest = &after[close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Directory for user templates and stylesheets (~/.config/hollow/templates)
+pub fn templates_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("hollow").join("templates"))
+}
+
+/// Find a template or stylesheet: a path as given, or a file in the
+/// templates directory, with `extension` added if the name has none.
+pub fn find_template_file(name: &str, extension: &str) -> Option<PathBuf> {
+    find_in(name, extension, templates_dir().as_deref())
+}
+
+fn find_in(name: &str, extension: &str, dir: Option<&Path>) -> Option<PathBuf> {
+    let path = expand_home(name);
+    if path.is_file() {
+        return Some(path);
+    }
+    let dir = dir?;
+    let mut candidates = vec![dir.join(name)];
+    if Path::new(name).extension().is_none() {
+        candidates.push(dir.join(format!("{}.{}", name, extension)));
+    }
+    candidates.into_iter().find(|p| p.is_file())
 }
 
 /// Extract title from first H1 heading in markdown.
-fn extract_title(markdown: &str) -> Option<String> {
+pub fn extract_title(markdown: &str) -> Option<String> {
     for line in markdown.lines() {
         let trimmed = line.trim();
         if let Some(title) = trimmed.strip_prefix("# ") {
@@ -130,7 +671,7 @@ fn extract_title(markdown: &str) -> Option<String> {
 }
 
 /// Escape HTML special characters.
-fn html_escape(s: &str) -> String {
+pub fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -142,30 +683,14 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
-    #[test]
-    fn test_markdown_to_html_basic() {
-        let md = "# Hello\n\nThis is a paragraph.";
-        let html = markdown_to_html(md);
-        
-        assert!(html.contains("<h1>Hello</h1>"));
-        assert!(html.contains("<p>This is a paragraph.</p>"));
+    fn render_html(markdown: &str, options: &ExportOptions) -> String {
+        let documents = [SourceDocument::from_markdown("test", markdown)];
+        HtmlExporter::new(options).render(&documents, &Metadata::new(&documents, options))
     }
 
-    #[test]
-    fn test_markdown_to_html_code() {
-        let md = "Here is `inline code` and:\n\n```\ncode block\n```";
-        let html = markdown_to_html(md);
-        
-        assert!(html.contains("<code>inline code</code>"));
-        assert!(html.contains("<pre><code>"));
-    }
-
-    #[test]
-    fn test_markdown_to_html_links() {
-        let md = "Visit [example](https://example.com).";
-        let html = markdown_to_html(md);
-        
-        assert!(html.contains("<a href=\"https://example.com\">example</a>"));
+    fn export_to_html(input: &Path, output: &Path, options: &ExportOptions) -> io::Result<()> {
+        let (documents, _) = load_sources(input)?;
+        export_to_file(&HtmlExporter::new(options), &documents, output, options)
     }
 
     #[test]
@@ -194,10 +719,34 @@ mod tests {
         let html = fs::read_to_string(&output).unwrap();
         assert!(html.contains("<!DOCTYPE html>"));
         assert!(html.contains("<title>Test</title>"));
-        assert!(html.contains("<h1>Test</h1>"));
+        assert!(html.contains("<h1 id=\"test\">Test</h1>"));
         assert!(html.contains("<p>Hello world.</p>"));
     }
 
+    #[test]
+    fn test_front_matter_feeds_metadata() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("storm.md");
+        fs::write(&input, "---\ntitle: The Storm\nauthor: Ann Lee\ndate: 2024-03-01\n---\nRain fell.\n").unwrap();
+
+        let (documents, _) = load_sources(&input).unwrap();
+        assert_eq!(documents[0].markdown, "Rain fell.\n");
+        assert_eq!(documents[0].field("author").as_deref(), Some("Ann Lee"));
+
+        let options = ExportOptions {
+            date: documents[0].field("date"),
+            ..Default::default()
+        };
+        let metadata = Metadata::new(&documents, &options);
+        assert_eq!(metadata.date, "2024-03-01");
+        assert_eq!(metadata.word_count, 2);
+
+        // The YAML is not rendered as a rule and a paragraph
+        let html = HtmlExporter::new(&options).render(&documents, &metadata);
+        assert!(!html.contains("<hr"));
+        assert!(!html.contains("Ann Lee"));
+    }
+
     #[test]
     fn test_export_with_custom_title() {
         let dir = tempdir().unwrap();
@@ -208,7 +757,7 @@ mod tests {
         
         let options = ExportOptions {
             title: Some("Custom Title".to_string()),
-            css: None,
+            ..Default::default()
         };
         
         export_to_html(&input, &output, &options).unwrap();
@@ -231,4 +780,127 @@ mod tests {
         assert!(html.contains("max-width: 700px"));
         assert!(html.contains("font-family: Georgia"));
     }
+
+    #[test]
+    fn test_template_placeholders() {
+        let options = ExportOptions {
+            template: Some("<h1>{{ title }}</h1> by {{author}} ({{word_count}} words) {{unknown}}\n{{body}}".to_string()),
+            author: Some("Ann & Co".to_string()),
+            ..Default::default()
+        };
+        let html = render_html("# Tea\n\nBody mentions {{title}}.", &options);
+        assert!(html.starts_with("<h1>Tea</h1> by Ann &amp; Co (4 words) {{unknown}}\n"));
+        // Placeholders inside the document are left alone
+        assert!(html.contains("<p>Body mentions {{title}}.</p>"));
+    }
+
+    #[test]
+    fn test_prose_word_count() {
+        assert_eq!(prose_word_count("# Tea\n\n- one *two*three\n- `four`\n\n> five"), 5);
+        assert_eq!(prose_word_count("Six ![a map](map.png)\n\n```\nlet x = 1;\n```\n<br>\n"), 1);
+    }
+
+    #[test]
+    fn test_toc_links_headings() {
+        let options = ExportOptions {
+            template: Some("{{toc}}\n{{body}}".to_string()),
+            ..Default::default()
+        };
+        let html = render_html("# Intro\n## Part `one`\n## Part one\n# End", &options);
+        assert!(html.contains("<h2 id=\"part-one-1\">Part one</h2>"));
+        assert!(html.starts_with(
+            "<nav class=\"toc\">\n<ul>\n<li><a href=\"#intro\">Intro</a>\n<ul>\n\
+             <li><a href=\"#part-one\">Part one</a></li>\n\
+             <li><a href=\"#part-one-1\">Part one</a></li>\n</ul>\n</li>\n\
+             <li><a href=\"#end\">End</a></li>\n</ul>\n</nav>"
+        ));
+
+        // A heading that skips a level still nests under the one before it
+        let entries: Vec<TocEntry> = [(1, "a"), (3, "b"), (2, "c"), (3, "d"), (1, "e")]
+            .into_iter()
+            .map(|(level, id)| TocEntry { level, id: id.to_string(), text: id.to_uppercase() })
+            .collect();
+        assert_eq!(
+            toc_html(&entries),
+            "<nav class=\"toc\">\n<ul>\n<li><a href=\"#a\">A</a>\n<ul>\n\
+             <li><a href=\"#b\">B</a></li>\n\
+             <li><a href=\"#c\">C</a>\n<ul>\n<li><a href=\"#d\">D</a></li>\n</ul>\n</li>\n</ul>\n</li>\n\
+             <li><a href=\"#e\">E</a></li>\n</ul>\n</nav>"
+        );
+
+        // Headings get ids without a toc too, so they can be linked to
+        let html = render_html("# Intro", &ExportOptions::default());
+        assert!(html.contains("<h1 id=\"intro\">Intro</h1>"));
+        assert!(!html.contains("<nav"));
+
+        // --toc puts the contents above the body of templates without {{toc}}
+        let options = ExportOptions {
+            template: Some("<main>{{body}}</main>".to_string()),
+            toc: true,
+            ..Default::default()
+        };
+        let html = render_html("# Intro\n\nText.", &options);
+        assert!(html.starts_with("<main><nav class=\"toc\">"));
+        assert!(html.contains("</nav>\n<h1 id=\"intro\">"));
+    }
+
+    #[test]
+    fn test_footnotes_link_back() {
+        let options = ExportOptions {
+            template: Some("{{body}}".to_string()),
+            ..Default::default()
+        };
+        let html = render_html("One.[^b] Two.[^a] Again.[^b]\n\n[^a]: First *note*.\n\n[^b]: Second.\n", &options);
+        // Numbered in order of first reference, not definition
+        assert!(html.contains("One.<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\">1</a></sup>"));
+        assert!(html.contains("Two.<sup class=\"footnote-ref\"><a href=\"#fn-2\" id=\"fnref-2\">2</a></sup>"));
+        assert!(html.contains("<a href=\"#fn-1\" id=\"fnref-1-2\">1</a>"));
+        assert!(html.contains(
+            "<section class=\"footnotes\">\n<ol>\n<li id=\"fn-1\">\n<p>Second. \
+             <a href=\"#fnref-1\" class=\"footnote-backref\" aria-label=\"Back to text\">\u{21a9}</a> \
+             <a href=\"#fnref-1-2\" class=\"footnote-backref\" aria-label=\"Back to text\">\u{21a9}<sup>2</sup></a></p>\n</li>\n"
+        ));
+        assert!(html.contains("<li id=\"fn-2\">\n<p>First <em>note</em>. <a href=\"#fnref-2\""));
+    }
+
+    #[test]
+    fn test_footnote_labels_reused_across_documents() {
+        // The second document defines its note before citing it
+        let documents: Vec<SourceDocument> = ["A.[^1]\n\n[^1]: Note A.\n", "[^1]: Note B.\n\nB.[^1]\n"]
+            .iter()
+            .map(|markdown| SourceDocument::from_markdown("chapter", markdown))
+            .collect();
+        let (html, _) = render_body(parse_documents(&documents, Options::all()));
+        assert!(html.contains("A.<sup class=\"footnote-ref\"><a href=\"#fn-1\""));
+        assert!(html.contains("<li id=\"fn-1\">\n<p>Note A."));
+        assert!(html.contains("B.<sup class=\"footnote-ref\"><a href=\"#fn-2\""));
+        assert!(html.contains("<li id=\"fn-2\">\n<p>Note B."));
+    }
+
+    #[test]
+    fn test_smart_punctuation_is_optional() {
+        let options = ExportOptions {
+            template: Some("{{body}}".to_string()),
+            ..Default::default()
+        };
+        let markdown = "\"Wait -- it's late...\"";
+        assert!(render_html(markdown, &options).contains("<p>\"Wait -- it's late...\"</p>"));
+
+        let options = ExportOptions { smart_punctuation: true, ..options };
+        assert!(render_html(markdown, &options).contains("\u{201c}Wait \u{2013} it\u{2019}s late\u{2026}\u{201d}"));
+    }
+
+    #[test]
+    fn test_find_template_file() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("blog.html"), "{{body}}").unwrap();
+        fs::write(dir.path().join("site.css"), "body {}").unwrap();
+
+        assert_eq!(find_in("blog", "html", Some(dir.path())), Some(dir.path().join("blog.html")));
+        assert_eq!(find_in("site.css", "css", Some(dir.path())), Some(dir.path().join("site.css")));
+        assert_eq!(find_in("missing", "html", Some(dir.path())), None);
+
+        let direct = dir.path().join("site.css");
+        assert_eq!(find_in(direct.to_str().unwrap(), "css", None), Some(direct));
+    }
 }
@@ -0,0 +1,100 @@
+//! YAML front matter at the top of a document
+//!
+//! Front matter is a block fenced by `---` lines at the very start of the
+//! file. Anything that fails to parse as a YAML mapping is treated as body text.
+
+use serde_yaml::{Mapping, Value};
+
+/// Split front matter from a document.
+/// Returns the YAML source and the byte offset where the body starts.
+pub fn split(text: &str) -> Option<(&str, usize)> {
+    let rest = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))?;
+    let yaml_start = text.len() - rest.len();
+
+    let mut offset = yaml_start;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some((&text[yaml_start..offset], offset + line.len()));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Parse the front matter of a document into a mapping
+pub fn parse(text: &str) -> Option<Mapping> {
+    let (yaml, _) = split(text)?;
+    match serde_yaml::from_str(yaml) {
+        Ok(Value::Mapping(map)) => Some(map),
+        _ => None,
+    }
+}
+
+/// Look up a front matter key
+pub fn get(text: &str, key: &str) -> Option<Value> {
+    parse(text)?.get(key).cloned()
+}
+
+/// Byte offset where the body starts: after valid front matter, or 0
+pub fn body_start(text: &str) -> usize {
+    match split(text) {
+        Some((_, start)) if parse(text).is_some() => start,
+        _ => 0,
+    }
+}
+
+/// The document without its front matter
+pub fn body(text: &str) -> &str {
+    &text[body_start(text)..]
+}
+
+/// A scalar front matter field as text, e.g. `title` or `date`
+pub fn field(map: &Mapping, key: &str) -> Option<String> {
+    match map.get(key)? {
+        Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let text = "---\ntitle: Draft\nlang: de_DE\n---\nBody text.\n";
+        let (yaml, body) = split(text).unwrap();
+        assert_eq!(yaml, "title: Draft\nlang: de_DE\n");
+        assert_eq!(&text[body..], "Body text.\n");
+
+        assert!(split("No front matter\n---\n").is_none());
+        assert!(split("---\nnever closed\n").is_none());
+    }
+
+    #[test]
+    fn test_get() {
+        let text = "---\ntitle: Draft\n---\nBody";
+        assert_eq!(get(text, "title"), Some(Value::String("Draft".to_string())));
+        assert_eq!(get(text, "lang"), None);
+        // A thematic break that isn't YAML
+        assert!(parse("---\n* * *\n---\n").is_none());
+    }
+
+    #[test]
+    fn test_body_and_field() {
+        let text = "---\ntitle: Draft\nyear: 2024\ntags: [a]\n---\nBody";
+        assert_eq!(body(text), "Body");
+        // Not YAML, so it stays part of the body
+        assert_eq!(body("---\n* * *\n---\nBody"), "---\n* * *\n---\nBody");
+
+        let map = parse(text).unwrap();
+        assert_eq!(field(&map, "title").as_deref(), Some("Draft"));
+        assert_eq!(field(&map, "year").as_deref(), Some("2024"));
+        assert_eq!(field(&map, "tags"), None);
+    }
+}
@@ -0,0 +1,1441 @@
+//! Import drafts from other tools as Markdown
+//!
+//! DOCX is read straight from the XML parts of its zip archive, HTML with a
+//! forgiving tag scanner, and plain text is unwrapped into paragraphs. All
+//! three become the same list of blocks, which is written out as Markdown:
+//! ATX headings, `*` and `**` emphasis, `-` and `1.` lists, `>` quotes,
+//! `* * *` scene breaks and `[^n]` footnotes.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::archive;
+
+/// What to do with quotation marks
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum QuoteStyle {
+    /// Leave quotes as they are in the source
+    #[default]
+    Keep,
+    /// Curly quotes become `"` and `'`
+    Straight,
+    /// Straight quotes become curly ones, apostrophes included
+    Curly,
+}
+
+impl QuoteStyle {
+    /// Parse a `--quotes` value
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(QuoteStyle::Keep),
+            "straight" => Some(QuoteStyle::Straight),
+            "curly" | "smart" => Some(QuoteStyle::Curly),
+            _ => None,
+        }
+    }
+}
+
+/// Import errors
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file type isn't one that can be imported
+    Unsupported(String),
+    /// The file couldn't be read
+    Io(String),
+    /// The file is damaged or not what its extension says
+    Malformed(String),
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Unsupported(ext) => {
+                write!(f, "can't import .{} files (expected .docx, .html or .txt)", ext)
+            }
+            ImportError::Io(msg) => write!(f, "{}", msg),
+            ImportError::Malformed(msg) => write!(f, "malformed document: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Convert a DOCX, HTML or plain text file to Markdown, by its extension.
+pub fn import_file(path: &Path, quotes: QuoteStyle) -> Result<String, ImportError> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let io_error = |e: std::io::Error| ImportError::Io(format!("{}: {}", path.display(), e));
+    match extension.as_str() {
+        "docx" => from_docx(&fs::read(path).map_err(io_error)?, quotes),
+        "html" | "htm" | "xhtml" => Ok(from_html(&fs::read_to_string(path).map_err(io_error)?, quotes)),
+        "txt" | "text" | "" => Ok(from_text(&fs::read_to_string(path).map_err(io_error)?, quotes)),
+        _ => Err(ImportError::Unsupported(extension)),
+    }
+}
+
+/// A block of an imported document. Text is already Markdown.
+#[derive(Debug, PartialEq)]
+enum Block {
+    Paragraph(String),
+    Heading(u8, String),
+    /// A paragraph inside this many levels of block quote
+    Quote(usize, String),
+    /// A list item at a nesting level; later paragraphs of the item follow
+    /// a blank line in its text
+    Item { ordered: bool, level: usize, text: String },
+    Code(String),
+    Rule,
+}
+
+/// Blocks and footnotes, numbered from 1
+#[derive(Default)]
+struct Document {
+    blocks: Vec<Block>,
+    footnotes: Vec<String>,
+}
+
+impl Document {
+    /// Add a block, dropping paragraphs with no text
+    fn push(&mut self, block: Block) {
+        let empty = match &block {
+            Block::Paragraph(text) | Block::Heading(_, text) | Block::Quote(_, text) => text.trim().is_empty(),
+            _ => false,
+        };
+        if !empty {
+            self.blocks.push(block);
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        // Kind and item count of the open lists, by level
+        let mut lists: Vec<(bool, u64)> = Vec::new();
+        let mut previous: Option<&Block> = None;
+        for block in &self.blocks {
+            match (previous, block) {
+                (None, _) => {}
+                // A bulleted list right after a numbered one, or the other way
+                // round, needs a blank line to start a list of its own
+                (Some(Block::Item { .. }), Block::Item { ordered, level: 0, .. })
+                    if lists.first().is_some_and(|(kind, _)| kind != ordered) =>
+                {
+                    out.push('\n');
+                    lists.clear();
+                }
+                (Some(Block::Item { .. }), Block::Item { .. }) => {}
+                (Some(Block::Quote(a, _)), Block::Quote(b, _)) => {
+                    out.push_str(&format!("{}\n", "> ".repeat(*a.min(b)).trim_end()));
+                }
+                _ => out.push('\n'),
+            }
+            if !matches!(block, Block::Item { .. }) {
+                lists.clear();
+            }
+
+            match block {
+                Block::Paragraph(text) => push_lines(&mut out, text, "", ""),
+                Block::Heading(level, text) => {
+                    out.push_str(&format!("{} {}\n", "#".repeat(*level as usize), text.replace('\n', " ")));
+                }
+                Block::Quote(depth, text) => {
+                    let prefix = "> ".repeat(*depth);
+                    push_lines(&mut out, text, &prefix, &prefix);
+                }
+                Block::Item { ordered, level, text } => {
+                    lists.truncate(level + 1);
+                    lists.resize(level + 1, (*ordered, 0));
+                    if lists[*level].0 != *ordered {
+                        lists[*level] = (*ordered, 0);
+                    }
+                    lists[*level].1 += 1;
+                    let indent = "    ".repeat(*level);
+                    let marker = if *ordered { format!("{}.", lists[*level].1) } else { "-".to_string() };
+                    let first = format!("{}{} ", indent, marker);
+                    let rest = " ".repeat(first.len());
+                    push_lines(&mut out, text, &first, &rest);
+                }
+                Block::Code(code) => out.push_str(&format!("```\n{}\n```\n", code.trim_end_matches('\n'))),
+                Block::Rule => out.push_str("* * *\n"),
+            }
+            previous = Some(block);
+        }
+
+        for (i, note) in self.footnotes.iter().enumerate() {
+            out.push_str(&format!("\n[^{}]: {}\n", i + 1, note.replace('\n', " ")));
+        }
+        out
+    }
+}
+
+/// Write the lines of a block, escaping what would read as block syntax
+fn push_lines(out: &mut String, text: &str, first_prefix: &str, prefix: &str) {
+    for (i, line) in text.lines().enumerate() {
+        if line.is_empty() {
+            out.push_str(prefix.trim_end());
+        } else {
+            out.push_str(if i == 0 { first_prefix } else { prefix });
+            out.push_str(&escape_line_start(line));
+        }
+        out.push('\n');
+    }
+}
+
+/// Escape a line start that Markdown would take for a heading, quote or list
+fn escape_line_start(line: &str) -> String {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") ")) {
+        return format!("{}\\{}", &line[..digits], &line[digits..]);
+    }
+    if line.starts_with('#') || line.starts_with('>') || line.starts_with("- ") || line.starts_with("+ ") {
+        return format!("\\{}", line);
+    }
+    line.to_string()
+}
+
+/// Escape characters that Markdown would take for inline markup
+fn escape_inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '`' | '<') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Whether a line is a scene break such as `* * *`, `***` or `#`
+fn is_scene_break(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && line.len() <= 9 && line.chars().all(|c| matches!(c, '*' | '#' | '~' | ' '))
+}
+
+/// Builds the Markdown text of a paragraph from styled pieces
+struct Inline {
+    out: String,
+    bold: bool,
+    italic: bool,
+    quotes: QuoteStyle,
+    /// Last character of source text, for telling opening quotes from closing ones
+    last: Option<char>,
+}
+
+impl Inline {
+    fn new(quotes: QuoteStyle) -> Self {
+        Self {
+            out: String::new(),
+            bold: false,
+            italic: false,
+            quotes,
+            last: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.out.trim().is_empty()
+    }
+
+    /// Add text in a style. Runs of whitespace collapse to one space.
+    fn text(&mut self, text: &str, bold: bool, italic: bool) {
+        let mut text = collapse_whitespace(text);
+        if text.starts_with(' ') && (self.out.is_empty() || self.out.ends_with([' ', '\n'])) {
+            text.remove(0);
+        }
+        if text.is_empty() {
+            return;
+        }
+        let text = normalize_quotes(&text, self.last, self.quotes);
+        self.last = text.chars().last();
+        let escaped = escape_inline(&text);
+
+        // Whitespace alone never changes the style, so spaces don't split emphasis
+        if text.trim().is_empty() || (bold == self.bold && italic == self.italic) {
+            self.out.push_str(&escaped);
+            return;
+        }
+        self.close();
+        let content = escaped.trim_start();
+        self.out.push_str(&escaped[..escaped.len() - content.len()]);
+        if bold {
+            self.out.push_str("**");
+        }
+        if italic {
+            self.out.push('*');
+        }
+        self.bold = bold;
+        self.italic = italic;
+        self.out.push_str(content);
+    }
+
+    /// Add Markdown as it is, outside any emphasis
+    fn raw(&mut self, markdown: &str) {
+        self.close();
+        self.out.push_str(markdown);
+    }
+
+    /// Add a code span
+    fn code(&mut self, code: &str) {
+        let fence = if code.contains('`') { "``" } else { "`" };
+        self.raw(&format!("{} {} {}", fence, code, fence).replace("` ", "`").replace(" `", "`"));
+    }
+
+    fn line_break(&mut self) {
+        self.close();
+        let trimmed = self.out.trim_end().len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() {
+            self.out.push_str("\\\n");
+        }
+        self.last = None;
+    }
+
+    /// End the open emphasis, keeping trailing spaces outside it
+    fn close(&mut self) {
+        if !self.bold && !self.italic {
+            return;
+        }
+        let content = self.out.trim_end().len();
+        let spaces = self.out.split_off(content);
+        if self.italic {
+            self.out.push('*');
+        }
+        if self.bold {
+            self.out.push_str("**");
+        }
+        self.out.push_str(&spaces);
+        self.bold = false;
+        self.italic = false;
+    }
+
+    fn finish(mut self) -> String {
+        self.close();
+        let text = self.out.trim();
+        text.strip_suffix('\\').unwrap_or(text).trim_end().to_string()
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !out.ends_with(' ') {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Convert quotation marks to the chosen style. `before` is the character
+/// that precedes the text.
+fn normalize_quotes(text: &str, before: Option<char>, style: QuoteStyle) -> String {
+    match style {
+        QuoteStyle::Keep => text.to_string(),
+        QuoteStyle::Straight => text
+            .chars()
+            .map(|c| match c {
+                '\u{201c}' | '\u{201d}' | '\u{201e}' => '"',
+                '\u{2018}' | '\u{2019}' | '\u{201a}' => '\'',
+                c => c,
+            })
+            .collect(),
+        QuoteStyle::Curly => {
+            let mut out = String::with_capacity(text.len());
+            let mut previous = before;
+            for c in text.chars() {
+                // A quote opens after a space, an opening bracket or a dash
+                let opens = previous.is_none_or(|p| p.is_whitespace() || "([{\u{2014}\u{2013}-/".contains(p));
+                out.push(match c {
+                    '"' if opens => '\u{201c}',
+                    '"' => '\u{201d}',
+                    '\'' if opens => '\u{2018}',
+                    '\'' => '\u{2019}',
+                    c => c,
+                });
+                previous = Some(c);
+            }
+            out
+        }
+    }
+}
+
+// Plain text
+
+/// Convert plain text to Markdown. Hard-wrapped lines are joined into
+/// paragraphs, which end at a blank line or an indented first line.
+pub fn from_text(text: &str, quotes: QuoteStyle) -> String {
+    let mut doc = Document::default();
+    // Lines of the open paragraph, and the list it belongs to, if any
+    let mut lines: Vec<&str> = Vec::new();
+    let mut item: Option<bool> = None;
+
+    let flush = |doc: &mut Document, lines: &mut Vec<&str>, item: &mut Option<bool>| {
+        if lines.is_empty() {
+            return;
+        }
+        let mut inline = Inline::new(quotes);
+        inline.text(&lines.join(" "), false, false);
+        let text = inline.finish();
+        doc.push(match item.take() {
+            Some(ordered) => Block::Item { ordered, level: 0, text },
+            None => Block::Paragraph(text),
+        });
+        lines.clear();
+    };
+
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let trimmed = line.trim();
+        let indented = line.starts_with('\t') || line.starts_with("  ");
+        if trimmed.is_empty() {
+            flush(&mut doc, &mut lines, &mut item);
+        } else if is_scene_break(trimmed) {
+            flush(&mut doc, &mut lines, &mut item);
+            doc.push(Block::Rule);
+        } else if let Some((ordered, rest)) = list_marker(trimmed) {
+            flush(&mut doc, &mut lines, &mut item);
+            item = Some(ordered);
+            lines.push(rest);
+        } else {
+            // Indented lines continue a list item, but start a new paragraph
+            if indented && item.is_none() {
+                flush(&mut doc, &mut lines, &mut item);
+            }
+            lines.push(trimmed);
+        }
+    }
+    flush(&mut doc, &mut lines, &mut item);
+    doc.to_markdown()
+}
+
+/// A list marker at the start of a line: whether the list is ordered, and
+/// the text after the marker
+fn list_marker(line: &str) -> Option<(bool, &str)> {
+    for bullet in ["- ", "* ", "+ ", "\u{2022} ", "\u{2013} "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return Some((false, rest.trim_start()));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let rest = &line[digits..];
+    if (1..=3).contains(&digits) && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        return Some((true, rest[2..].trim_start()));
+    }
+    None
+}
+
+// Markup scanning, shared by HTML and the XML parts of DOCX
+
+/// A piece of an HTML or XML document
+#[derive(Debug, PartialEq)]
+enum Token {
+    Start { name: String, attrs: Vec<(String, String)>, empty: bool },
+    End(String),
+    Text(String),
+}
+
+impl Token {
+    fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            Token::Start { attrs, .. } => attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Split markup into tags and text. Comments, processing instructions and
+/// doctypes are dropped, and the contents of `script` and `style` elements
+/// are skipped. Text and attribute values have entities decoded.
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while !rest.is_empty() {
+        let Some(open) = rest.find('<') else {
+            tokens.push(Token::Text(decode_entities(rest)));
+            break;
+        };
+        if open > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..open])));
+        }
+        rest = &rest[open..];
+
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            tokens.push(Token::Text(cdata[..end].to_string()));
+            rest = cdata.get(end + 3..).unwrap_or("");
+            continue;
+        }
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let Some(close) = tag_end(rest) else {
+            // A stray '<' is text
+            tokens.push(Token::Text("<".to_string()));
+            rest = &rest[1..];
+            continue;
+        };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::End(name.trim().to_string()));
+            continue;
+        }
+        let empty = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+        let name = tag[..name_end].to_string();
+        if name.is_empty() || !name.starts_with(|c: char| c.is_alphabetic()) {
+            tokens.push(Token::Text(decode_entities(&format!("<{}>", tag))));
+            continue;
+        }
+        let attrs = parse_attributes(&tag[name_end..]);
+
+        let raw = name.eq_ignore_ascii_case("script") || name.eq_ignore_ascii_case("style");
+        tokens.push(Token::Start { name: name.clone(), attrs, empty });
+        if raw && !empty {
+            let lower = rest.to_ascii_lowercase();
+            let end = lower.find(&format!("</{}", name.to_ascii_lowercase())).unwrap_or(rest.len());
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+/// Index of the `>` that ends the tag starting at `text[0]`, skipping quoted values
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (i, c) in text.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attributes(text: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(rest.len());
+        let name = rest[..name_end].to_string();
+        rest = rest[name_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let end = after[1..].find(q).map_or(after.len(), |e| e + 1);
+                    (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining.trim_start();
+        }
+        if name.is_empty() {
+            // Skip a character that can't start a name, such as a stray quote
+            rest = rest.get(1..).unwrap_or("");
+        } else {
+            attrs.push((name, value));
+        }
+    }
+    attrs
+}
+
+/// Decode character references and the common named entities
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let name = &rest[1..end];
+            let c = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                "ndash" => Some('\u{2013}'),
+                "mdash" => Some('\u{2014}'),
+                "hellip" => Some('\u{2026}'),
+                "lsquo" => Some('\u{2018}'),
+                "rsquo" => Some('\u{2019}'),
+                "ldquo" => Some('\u{201c}'),
+                "rdquo" => Some('\u{201d}'),
+                "copy" => Some('\u{a9}'),
+                _ => {
+                    let number = name.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code)
+                }
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// HTML
+
+/// Convert an HTML page to Markdown. Navigation, scripts and styles are
+/// left out; footnotes linked from superscripts are collected at the end.
+pub fn from_html(html: &str, quotes: QuoteStyle) -> String {
+    let tokens = tokenize(html.trim_start_matches('\u{feff}'));
+    let mut import = HtmlImport::new(quotes, footnote_targets(&tokens));
+    for token in &tokens {
+        import.token(token);
+    }
+    import.finish()
+}
+
+/// Ids that footnote references point to: links inside superscripts, or
+/// marked as note references
+fn footnote_targets(tokens: &[Token]) -> HashSet<String> {
+    let mut targets = HashSet::new();
+    let mut sup_depth = 0usize;
+    for token in tokens {
+        match token {
+            Token::Start { name, .. } if name.eq_ignore_ascii_case("sup") => sup_depth += 1,
+            Token::End(name) if name.eq_ignore_ascii_case("sup") => sup_depth = sup_depth.saturating_sub(1),
+            Token::Start { name, .. } if name.eq_ignore_ascii_case("a") && is_note_reference(token, sup_depth > 0) => {
+                if let Some(target) = token.attr("href").and_then(|h| h.strip_prefix('#')) {
+                    targets.insert(target.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    targets
+}
+
+fn is_note_reference(link: &Token, in_sup: bool) -> bool {
+    let local = link.attr("href").is_some_and(|h| h.starts_with('#') && h.len() > 1);
+    let class = link.attr("class").unwrap_or("");
+    let marked =
+        class.contains("footnote-ref") || class.contains("noteref") || link.attr("epub:type") == Some("noteref");
+    local && (in_sup || marked)
+}
+
+/// Elements whose contents are never imported
+const SKIPPED: [&str; 6] = ["head", "script", "style", "nav", "template", "noscript"];
+/// Elements that end the paragraph before them
+const BLOCKS: [&str; 21] = [
+    "p", "div", "section", "article", "main", "header", "footer", "aside", "figure", "figcaption", "li", "ul",
+    "ol", "blockquote", "pre", "h1", "h2", "h3", "h4", "h5", "h6",
+];
+
+struct HtmlImport {
+    doc: Document,
+    quotes: QuoteStyle,
+    inline: Inline,
+    /// Element whose contents are skipped, and how many elements of that
+    /// name are open inside it, itself included
+    skipping: Option<(String, usize)>,
+    bold: usize,
+    italic: usize,
+    heading: Option<u8>,
+    quote_depth: usize,
+    /// Whether each open list is ordered
+    lists: Vec<bool>,
+    /// The open list item has had its first paragraph written
+    item_started: bool,
+    sup_depth: usize,
+    /// Text of the open `pre` element
+    pre: Option<String>,
+    in_code: bool,
+    /// Destinations of the open links; `None` for links not written as links
+    links: Vec<Option<String>>,
+    /// Ids that footnote references point to
+    targets: HashSet<String>,
+    /// Footnote numbers by target id
+    numbers: HashMap<String, usize>,
+    /// The footnote being read: its id, element name and nesting depth
+    note: Option<(String, String, usize)>,
+    notes: HashMap<String, String>,
+}
+
+impl HtmlImport {
+    fn new(quotes: QuoteStyle, targets: HashSet<String>) -> Self {
+        Self {
+            doc: Document::default(),
+            quotes,
+            inline: Inline::new(quotes),
+            skipping: None,
+            bold: 0,
+            italic: 0,
+            heading: None,
+            quote_depth: 0,
+            lists: Vec::new(),
+            item_started: false,
+            sup_depth: 0,
+            pre: None,
+            in_code: false,
+            links: Vec::new(),
+            targets,
+            numbers: HashMap::new(),
+            note: None,
+            notes: HashMap::new(),
+        }
+    }
+
+    fn token(&mut self, token: &Token) {
+        match token {
+            Token::Start { name, empty, .. } => {
+                let name = name.to_ascii_lowercase();
+                let opens = !empty && !is_void(&name);
+                // Only tags of the skipped element's own name are counted, since
+                // others (<li>, <p>) may leave out their end tags
+                if let Some((skipped, depth)) = &mut self.skipping {
+                    if opens && name == *skipped {
+                        *depth += 1;
+                    }
+                    return;
+                }
+                if SKIPPED.contains(&name.as_str()) {
+                    if opens {
+                        self.skipping = Some((name, 1));
+                    }
+                    return;
+                }
+                self.start(&name, token);
+                if *empty && !is_void(&name) {
+                    self.end(&name);
+                }
+            }
+            Token::End(name) => {
+                let name = name.to_ascii_lowercase();
+                if let Some((skipped, depth)) = &mut self.skipping {
+                    if name == *skipped {
+                        *depth -= 1;
+                        if *depth == 0 {
+                            self.skipping = None;
+                        }
+                    }
+                    return;
+                }
+                self.end(&name);
+            }
+            Token::Text(text) => {
+                if self.skipping.is_some() {
+                    return;
+                }
+                if let Some(pre) = &mut self.pre {
+                    pre.push_str(text);
+                } else if self.in_code {
+                    self.inline.code(text);
+                } else {
+                    self.inline.text(text, self.bold > 0, self.italic > 0);
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, name: &str, token: &Token) {
+        // Footnote definitions are read into their own text
+        if let Some((_, note_name, depth)) = &mut self.note {
+            if name == note_name {
+                // An unclosed <li> ends at the next one
+                if name == "li" {
+                    self.end_note();
+                } else {
+                    *depth += 1;
+                }
+            }
+        }
+        if self.note.is_none() {
+            if let Some(id) = token.attr("id").filter(|id| self.targets.contains(*id)) {
+                self.flush();
+                self.note = Some((id.to_string(), name.to_string(), 1));
+                return;
+            }
+        }
+
+        if BLOCKS.contains(&name) {
+            self.flush();
+        }
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.heading = name[1..].parse().ok(),
+            "blockquote" => self.quote_depth += 1,
+            "ul" | "ol" => self.lists.push(name == "ol"),
+            "li" => self.item_started = false,
+            "pre" => self.pre = Some(String::new()),
+            "code" => self.in_code = true,
+            "b" | "strong" => self.bold += 1,
+            "i" | "em" | "cite" => self.italic += 1,
+            "sup" => self.sup_depth += 1,
+            "br" => self.inline.line_break(),
+            "hr" => {
+                self.flush();
+                if self.note.is_none() {
+                    self.doc.push(Block::Rule);
+                }
+            }
+            "img" => {
+                let alt = escape_inline(token.attr("alt").unwrap_or(""));
+                let src = token.attr("src").unwrap_or("");
+                self.inline.raw(&format!("![{}]({})", alt, src));
+            }
+            "a" => self.start_link(token),
+            "td" | "th" => self.inline.text(" ", false, false),
+            "tr" => self.flush(),
+            _ => {}
+        }
+    }
+
+    fn start_link(&mut self, token: &Token) {
+        let href = token.attr("href").unwrap_or("");
+        // The text of a skipped link is left out; an empty link has none
+        let skip_text = match token {
+            Token::Start { empty: false, .. } => Some(("a".to_string(), 1)),
+            _ => None,
+        };
+        if self.note.is_some() && href.starts_with('#') {
+            // Links back to the text from a footnote are dropped, number and all
+            self.skipping = skip_text;
+        } else if is_note_reference(token, self.sup_depth > 0) && self.targets.contains(&href[1..]) {
+            let next = self.numbers.len() + 1;
+            let number = *self.numbers.entry(href[1..].to_string()).or_insert(next);
+            self.inline.raw(&format!("[^{}]", number));
+            self.skipping = skip_text;
+        } else if href.is_empty() || href.starts_with('#') {
+            self.links.push(None);
+        } else {
+            self.inline.raw("[");
+            self.links.push(Some(href.to_string()));
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        if let Some((_, note_name, depth)) = &mut self.note {
+            if name == note_name {
+                *depth -= 1;
+                if *depth == 0 {
+                    self.end_note();
+                    return;
+                }
+            }
+        }
+        if BLOCKS.contains(&name) {
+            self.flush();
+        }
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.heading = None,
+            "blockquote" => self.quote_depth = self.quote_depth.saturating_sub(1),
+            "ul" | "ol" => {
+                self.lists.pop();
+                self.item_started = true;
+            }
+            "pre" => {
+                if let Some(code) = self.pre.take() {
+                    if self.note.is_none() {
+                        self.doc.push(Block::Code(code.trim_matches('\n').to_string()));
+                    }
+                }
+            }
+            "code" => self.in_code = false,
+            "b" | "strong" => self.bold = self.bold.saturating_sub(1),
+            "i" | "em" | "cite" => self.italic = self.italic.saturating_sub(1),
+            "sup" => self.sup_depth = self.sup_depth.saturating_sub(1),
+            "a" => {
+                if let Some(Some(href)) = self.links.pop() {
+                    self.inline.raw(&format!("]({})", href));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Write the open paragraph as the block its context calls for
+    fn flush(&mut self) {
+        if self.inline.is_empty() {
+            self.inline = Inline::new(self.quotes);
+            return;
+        }
+        let text = std::mem::replace(&mut self.inline, Inline::new(self.quotes)).finish();
+        if let Some((id, _, _)) = &self.note {
+            let note = self.notes.entry(id.clone()).or_default();
+            if !note.is_empty() {
+                note.push(' ');
+            }
+            note.push_str(&text);
+            return;
+        }
+
+        let block = if let Some(level) = self.heading {
+            Block::Heading(level, text.replace("\\\n", " "))
+        } else if let Some(&ordered) = self.lists.last() {
+            if self.item_started {
+                // A later paragraph of the same item
+                if let Some(Block::Item { text: item, .. }) = self.doc.blocks.last_mut() {
+                    item.push_str("\n\n");
+                    item.push_str(&text);
+                    return;
+                }
+            }
+            self.item_started = true;
+            Block::Item { ordered, level: self.lists.len() - 1, text }
+        } else if self.quote_depth > 0 {
+            Block::Quote(self.quote_depth, text)
+        } else if is_scene_break(&text.replace('\\', "")) {
+            Block::Rule
+        } else {
+            Block::Paragraph(text)
+        };
+        self.doc.push(block);
+    }
+
+    fn end_note(&mut self) {
+        self.flush();
+        self.note = None;
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        let mut numbered: Vec<(usize, String)> = self.numbers.into_iter().map(|(id, n)| (n, id)).collect();
+        numbered.sort();
+        self.doc.footnotes = numbered
+            .into_iter()
+            .map(|(_, id)| self.notes.remove(&id).unwrap_or_default())
+            .collect();
+        self.doc.to_markdown()
+    }
+}
+
+/// Elements that never have contents or an end tag
+fn is_void(name: &str) -> bool {
+    matches!(name, "br" | "hr" | "img" | "meta" | "link" | "input" | "col" | "area" | "base" | "wbr")
+}
+
+// DOCX
+
+/// Convert a Word document to Markdown, reading `word/document.xml` and
+/// the styles, numbering, footnotes and links it refers to.
+pub fn from_docx(data: &[u8], quotes: QuoteStyle) -> Result<String, ImportError> {
+    let part = |name: &str| -> Result<Option<String>, ImportError> {
+        match archive::read_entry(data, name) {
+            Ok(Some(bytes)) => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+            Ok(None) => Ok(None),
+            Err(e) => Err(ImportError::Malformed(e.to_string())),
+        }
+    };
+    let document = part("word/document.xml")?
+        .ok_or_else(|| ImportError::Malformed("no word/document.xml; is this a Word document?".to_string()))?;
+    let styles = part("word/styles.xml")?.map(|xml| docx_styles(&xml)).unwrap_or_default();
+    let numbering = part("word/numbering.xml")?.map(|xml| docx_numbering(&xml)).unwrap_or_default();
+    let links = part("word/_rels/document.xml.rels")?.map(|xml| docx_links(&xml)).unwrap_or_default();
+
+    let mut notes: HashMap<String, Vec<Paragraph>> = HashMap::new();
+    for (kind, name) in [("footnote", "word/footnotes.xml"), ("endnote", "word/endnotes.xml")] {
+        if let Some(xml) = part(name)? {
+            for paragraph in docx_paragraphs(&xml, &links) {
+                if let Some(id) = &paragraph.note {
+                    notes.entry(format!("{}:{}", kind, id)).or_default().push(paragraph);
+                }
+            }
+        }
+    }
+
+    let mut doc = Document::default();
+    // Footnote numbers by note key, in order of first reference
+    let mut numbers: Vec<String> = Vec::new();
+    for paragraph in docx_paragraphs(&document, &links) {
+        let (block, text) = paragraph.into_block(&styles, &numbering, quotes, &mut numbers);
+        match block {
+            Some(Block::Code(_)) => match doc.blocks.last_mut() {
+                // Code paragraphs are one line each; gather them into one block
+                Some(Block::Code(code)) => {
+                    code.push('\n');
+                    code.push_str(&text);
+                }
+                _ => doc.push(Block::Code(text)),
+            },
+            Some(block) => doc.push(block),
+            None => {}
+        }
+    }
+
+    // Notes can refer to further notes, which are numbered as they're found
+    let mut i = 0;
+    while i < numbers.len() {
+        let paragraphs = notes.remove(&numbers[i]).unwrap_or_default();
+        let texts: Vec<String> = paragraphs
+            .iter()
+            .map(|p| p.inline(quotes, &mut numbers))
+            .filter(|text| !text.is_empty())
+            .collect();
+        doc.footnotes.push(texts.join(" "));
+        i += 1;
+    }
+    Ok(doc.to_markdown())
+}
+
+/// What a paragraph style means for import
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StyleKind {
+    Heading(u8),
+    Quote,
+    Code,
+    SceneBreak,
+    List,
+}
+
+/// Paragraph style ids that mean something on import, by their names
+fn docx_styles(xml: &str) -> HashMap<String, StyleKind> {
+    let mut styles = HashMap::new();
+    let mut id: Option<String> = None;
+    for token in tokenize(xml) {
+        match &token {
+            Token::Start { name, .. } if name == "w:style" => id = token.attr("w:styleId").map(str::to_string),
+            Token::Start { name, .. } if name == "w:name" => {
+                let (Some(id), Some(style_name)) = (&id, token.attr("w:val")) else { continue };
+                if let Some(kind) = style_kind(style_name) {
+                    styles.insert(id.clone(), kind);
+                }
+            }
+            _ => {}
+        }
+    }
+    styles
+}
+
+fn style_kind(name: &str) -> Option<StyleKind> {
+    let name = name.to_lowercase();
+    if name == "title" {
+        return Some(StyleKind::Heading(1));
+    }
+    if let Some(level) = name.strip_prefix("heading ").and_then(|n| n.trim().parse::<u8>().ok()) {
+        return Some(StyleKind::Heading(level.clamp(1, 6)));
+    }
+    match name.as_str() {
+        "quote" | "intense quote" | "block text" => Some(StyleKind::Quote),
+        "code" | "html preformatted" | "plain text" | "source code" => Some(StyleKind::Code),
+        "scene break" => Some(StyleKind::SceneBreak),
+        n if n.starts_with("list") => Some(StyleKind::List),
+        _ => None,
+    }
+}
+
+/// Whether each list level is ordered, by numbering id and level
+fn docx_numbering(xml: &str) -> HashMap<(String, usize), bool> {
+    // Abstract definitions hold the formats; numbering ids refer to them
+    let mut formats: HashMap<(String, usize), bool> = HashMap::new();
+    let mut instances: Vec<(String, String)> = Vec::new();
+    let mut abstract_id: Option<String> = None;
+    let mut num_id: Option<String> = None;
+    let mut level = 0;
+    for token in tokenize(xml) {
+        let Token::Start { name, .. } = &token else { continue };
+        match name.as_str() {
+            "w:abstractNum" => abstract_id = token.attr("w:abstractNumId").map(str::to_string),
+            "w:lvl" => level = token.attr("w:ilvl").and_then(|l| l.parse().ok()).unwrap_or(0),
+            "w:numFmt" => {
+                if let Some(id) = &abstract_id {
+                    let ordered = !matches!(token.attr("w:val"), Some("bullet") | Some("none"));
+                    formats.insert((id.clone(), level), ordered);
+                }
+            }
+            "w:num" => {
+                abstract_id = None;
+                num_id = token.attr("w:numId").map(str::to_string);
+            }
+            "w:abstractNumId" => {
+                if let (Some(num), Some(abstract_num)) = (&num_id, token.attr("w:val")) {
+                    instances.push((num.clone(), abstract_num.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut lists = HashMap::new();
+    for (num, abstract_num) in instances {
+        for ((id, level), ordered) in &formats {
+            if *id == abstract_num {
+                lists.insert((num.clone(), *level), *ordered);
+            }
+        }
+    }
+    lists
+}
+
+/// Hyperlink targets by relationship id
+fn docx_links(xml: &str) -> HashMap<String, String> {
+    tokenize(xml)
+        .iter()
+        .filter(|t| t.attr("TargetMode") == Some("External"))
+        .filter_map(|t| Some((t.attr("Id")?.to_string(), t.attr("Target")?.to_string())))
+        .collect()
+}
+
+/// A piece of a Word paragraph
+#[derive(Debug)]
+enum Piece {
+    Text { text: String, bold: bool, italic: bool },
+    Break,
+    /// Reference to a footnote, as "footnote:ID" or "endnote:ID"
+    Note(String),
+    /// Start of a hyperlink; `None` for links within the document
+    LinkStart(Option<String>),
+    LinkEnd,
+}
+
+#[derive(Debug, Default)]
+struct Paragraph {
+    style: Option<String>,
+    /// Numbering id and level of a list paragraph
+    numbering: Option<(String, usize)>,
+    centered: bool,
+    pieces: Vec<Piece>,
+    /// Id of the footnote or endnote the paragraph belongs to
+    note: Option<String>,
+}
+
+impl Paragraph {
+    fn plain_text(&self) -> String {
+        self.pieces
+            .iter()
+            .filter_map(|p| match p {
+                Piece::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The block this paragraph becomes, and its text
+    fn into_block(
+        mut self,
+        styles: &HashMap<String, StyleKind>,
+        numbering: &HashMap<(String, usize), bool>,
+        quotes: QuoteStyle,
+        numbers: &mut Vec<String>,
+    ) -> (Option<Block>, String) {
+        let kind = self.style.as_ref().and_then(|s| styles.get(s)).copied();
+        if kind == Some(StyleKind::Code) {
+            return (Some(Block::Code(String::new())), self.plain_text());
+        }
+        let plain = self.plain_text();
+        if kind == Some(StyleKind::SceneBreak) || (is_scene_break(&plain) && (self.centered || plain.trim().len() > 1)) {
+            return (Some(Block::Rule), String::new());
+        }
+
+        // Lists are numbered by Word, or typed out as "•\t" or "1.\t"
+        let mut list = self
+            .numbering
+            .as_ref()
+            .map(|(id, level)| (numbering.get(&(id.clone(), *level)).copied().unwrap_or(false), *level));
+        if list.is_none() && kind == Some(StyleKind::List) {
+            if let Some(Piece::Text { text, .. }) = self.pieces.first_mut() {
+                let trimmed = text.trim_start();
+                if let Some((ordered, rest)) = list_marker(&trimmed.replacen('\t', " ", 1)) {
+                    *text = rest.to_string();
+                    list = Some((ordered, 0));
+                }
+            }
+        }
+
+        let text = self.inline(quotes, numbers);
+        let block = match (list, kind) {
+            (Some((ordered, level)), _) => Block::Item { ordered, level, text: text.clone() },
+            (None, Some(StyleKind::Heading(level))) => Block::Heading(level, text.replace("\\\n", " ")),
+            (None, Some(StyleKind::Quote)) => Block::Quote(1, text.clone()),
+            _ => Block::Paragraph(text.clone()),
+        };
+        (Some(block), text)
+    }
+
+    /// The paragraph's text as Markdown, numbering the notes it refers to
+    fn inline(&self, quotes: QuoteStyle, numbers: &mut Vec<String>) -> String {
+        let mut inline = Inline::new(quotes);
+        // Destinations of the open hyperlinks; `None` for internal ones
+        let mut links: Vec<Option<&str>> = Vec::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text { text, bold, italic } => inline.text(text, *bold, *italic),
+                Piece::Break => inline.line_break(),
+                Piece::Note(key) => {
+                    let number = match numbers.iter().position(|n| n == key) {
+                        Some(i) => i + 1,
+                        None => {
+                            numbers.push(key.clone());
+                            numbers.len()
+                        }
+                    };
+                    inline.raw(&format!("[^{}]", number));
+                }
+                Piece::LinkStart(url) => {
+                    if url.is_some() {
+                        inline.raw("[");
+                    }
+                    links.push(url.as_deref());
+                }
+                Piece::LinkEnd => {
+                    if let Some(Some(url)) = links.pop() {
+                        inline.raw(&format!("]({})", url));
+                    }
+                }
+            }
+        }
+        inline.finish()
+    }
+}
+
+/// The paragraphs of a document, footnotes or endnotes part
+fn docx_paragraphs(xml: &str, links: &HashMap<String, String>) -> Vec<Paragraph> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph: Option<Paragraph> = None;
+    let mut note: Option<String> = None;
+    let (mut bold, mut italic) = (false, false);
+    let mut in_paragraph_props = false;
+    let mut in_text = false;
+    let mut numbering_id: Option<String> = None;
+    let mut numbering_level = 0;
+
+    for token in tokenize(xml) {
+        match &token {
+            Token::Start { name, empty, .. } => match name.as_str() {
+                "w:footnote" | "w:endnote" => {
+                    // Separators are Word's own, not notes
+                    note = match token.attr("w:type") {
+                        Some("separator") | Some("continuationSeparator") | Some("continuationNotice") => None,
+                        _ => token.attr("w:id").map(str::to_string),
+                    };
+                }
+                "w:p" => {
+                    paragraph = Some(Paragraph { note: note.clone(), ..Default::default() });
+                    numbering_id = None;
+                    numbering_level = 0;
+                    if *empty {
+                        paragraphs.extend(paragraph.take());
+                    }
+                }
+                "w:pPr" if !empty => in_paragraph_props = true,
+                "w:pStyle" => {
+                    if let Some(p) = &mut paragraph {
+                        p.style = token.attr("w:val").map(str::to_string);
+                    }
+                }
+                "w:numId" => numbering_id = token.attr("w:val").map(str::to_string),
+                "w:ilvl" => numbering_level = token.attr("w:val").and_then(|l| l.parse().ok()).unwrap_or(0),
+                "w:jc" => {
+                    if let Some(p) = &mut paragraph {
+                        p.centered = token.attr("w:val") == Some("center");
+                    }
+                }
+                "w:r" => (bold, italic) = (false, false),
+                "w:b" if !in_paragraph_props => bold = is_on(&token),
+                "w:i" if !in_paragraph_props => italic = is_on(&token),
+                "w:t" if !empty => in_text = true,
+                "w:tab" if !in_paragraph_props => {
+                    push_piece(&mut paragraph, Piece::Text { text: " ".to_string(), bold, italic })
+                }
+                // Page and column breaks have no place in Markdown
+                "w:br" | "w:cr" if !matches!(token.attr("w:type"), Some("page") | Some("column")) => {
+                    push_piece(&mut paragraph, Piece::Break)
+                }
+                "w:noBreakHyphen" => push_piece(&mut paragraph, Piece::Text { text: "-".to_string(), bold, italic }),
+                "w:footnoteReference" | "w:endnoteReference" => {
+                    if let Some(id) = token.attr("w:id") {
+                        let kind = if name == "w:footnoteReference" { "footnote" } else { "endnote" };
+                        push_piece(&mut paragraph, Piece::Note(format!("{}:{}", kind, id)));
+                    }
+                }
+                "w:hyperlink" if !empty => {
+                    let url = token.attr("r:id").and_then(|id| links.get(id)).cloned();
+                    push_piece(&mut paragraph, Piece::LinkStart(url));
+                }
+                _ => {}
+            },
+            Token::End(name) => match name.as_str() {
+                "w:p" => {
+                    if let Some(mut p) = paragraph.take() {
+                        if let Some(id) = numbering_id.take().filter(|id| id != "0") {
+                            p.numbering = Some((id, numbering_level));
+                        }
+                        paragraphs.push(p);
+                    }
+                }
+                "w:pPr" => in_paragraph_props = false,
+                "w:t" => in_text = false,
+                "w:hyperlink" => push_piece(&mut paragraph, Piece::LinkEnd),
+                "w:footnote" | "w:endnote" => note = None,
+                _ => {}
+            },
+            Token::Text(text) if in_text => push_piece(&mut paragraph, Piece::Text { text: text.clone(), bold, italic }),
+            Token::Text(_) => {}
+        }
+    }
+    paragraphs
+}
+
+fn push_piece(paragraph: &mut Option<Paragraph>, piece: Piece) {
+    if let Some(p) = paragraph {
+        p.pieces.push(piece);
+    }
+}
+
+/// Whether a toggle property such as `<w:b/>` is on; `w:val="0"` turns it off
+fn is_on(token: &Token) -> bool {
+    !matches!(token.attr("w:val"), Some("0") | Some("false") | Some("none"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docx;
+    use crate::export::{ExportOptions, SourceDocument};
+    use tempfile::tempdir;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/import");
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(FIXTURES).join(name)
+    }
+
+    #[test]
+    fn test_docx_fixture() {
+        let markdown = import_file(&fixture("draft.docx"), QuoteStyle::Keep).unwrap();
+        assert_eq!(
+            markdown,
+            "# The Lighthouse\n\n# Chapter One\n\n\
+             It was *not* the **first** storm of the year, but it was the worst.[^1]\n\n\
+             She said, \u{201c}Keep the lamp lit.\u{201d} Tom\u{2019}s reply was lost in the wind.\n\n\
+             * * *\n\nThings to carry:\n\n\
+             - Matches\n- Oil\n    - Spare wick\n\n1. Check the glass\n2. Wind the clock\n\n\
+             > The sea is a hungry thing.[^2]\n\n\
+             [The keeper's log](https://example.com/log) is online.\n\n\
+             Line one\\\nline two\n\n\
+             [^1]: The storm of *1893*.\n\n[^2]: From the keeper's log.\n"
+        );
+
+        let straight = import_file(&fixture("draft.docx"), QuoteStyle::Straight).unwrap();
+        assert!(straight.contains("She said, \"Keep the lamp lit.\" Tom's reply"));
+    }
+
+    #[test]
+    fn test_html_fixture() {
+        let markdown = import_file(&fixture("draft.html"), QuoteStyle::Curly).unwrap();
+        assert!(markdown.starts_with("# Chapter One\n\nIt was *not* the **first** storm"));
+        assert!(markdown.contains("the worst.[^1]\n\nShe said, \u{201c}Keep the lamp lit.\u{201d} Tom\u{2019}s reply"));
+        assert!(markdown.contains("- Matches\n- Oil\n    - Spare wick\n\n1. Check the glass\n2. Wind the clock\n"));
+        assert!(markdown.contains("> The sea is a hungry thing.[^2]\n"));
+        assert!(markdown.contains("[The keeper\u{2019}s log](https://example.com/log) is online.\\\nLine two & `lamp_oil`."));
+        assert!(markdown.ends_with("[^1]: The storm of *1893*.\n\n[^2]: From the keeper\u{2019}s log.\n"));
+        // Navigation, scripts and the page title aren't prose
+        assert!(!markdown.contains("Home"));
+        assert!(!markdown.contains("console"));
+        assert!(!markdown.contains("The Lighthouse"));
+    }
+
+    #[test]
+    fn test_text_fixture() {
+        let markdown = import_file(&fixture("draft.txt"), QuoteStyle::Keep).unwrap();
+        assert_eq!(
+            markdown,
+            "Chapter One\n\n\
+             It was not the first storm of the year, but it was the worst. The wind came off the water \
+             in long grey sheets, and the keeper's boy watched it from the gallery.\n\n\
+             She said, \"Keep the lamp lit.\" Tom's reply was lost in the wind.\n\n\
+             He tried again, louder.\n\n* * *\n\nThings to carry:\n\n\
+             - Matches\n- Oil, enough for three nights\n- Spare wick\n\n1. Check the glass\n2. Wind the clock\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_through_docx_export() {
+        // Footnotes are left out: the exporter writes them as plain paragraphs
+        let source = "# Chapter One\n\nShe *ran* and **ran**.\n\n***\n\n> Quoted\n\n- one\n- two\n\n1. first\n2. second\n";
+        let documents = vec![SourceDocument::from_markdown("draft", source)];
+        let data = docx::build_docx(&documents, &ExportOptions::default()).unwrap();
+        let markdown = from_docx(&data, QuoteStyle::Keep).unwrap();
+        assert_eq!(
+            markdown,
+            "# Chapter One\n\nShe *ran* and **ran**.\n\n* * *\n\n> Quoted\n\n- one\n- two\n\n1. first\n2. second\n"
+        );
+    }
+
+    #[test]
+    fn test_markup_is_escaped() {
+        let markdown = from_html("<p>2. Not a list, *not* emphasis, a_b</p><p># Not a heading</p>", QuoteStyle::Keep);
+        assert_eq!(markdown, "2\\. Not a list, \\*not\\* emphasis, a\\_b\n\n\\# Not a heading\n");
+    }
+
+    #[test]
+    fn test_skipped_elements_with_unclosed_tags() {
+        let markdown = from_html("<nav><ul><li>Home<li>About</ul></nav><p>Story text.</p>", QuoteStyle::Keep);
+        assert_eq!(markdown, "Story text.\n");
+        let markdown = from_html("<nav><p>Menu<nav>Inner</nav><p>More</nav><p>After.", QuoteStyle::Keep);
+        assert_eq!(markdown, "After.\n");
+    }
+
+    #[test]
+    fn test_empty_note_reference() {
+        let markdown = from_html(
+            "<p>Text.<a href=\"#n1\" class=\"noteref\"/> More.</p><aside id=\"n1\"><p>The note.</p></aside>",
+            QuoteStyle::Keep,
+        );
+        assert_eq!(markdown, "Text.[^1] More.\n\n[^1]: The note.\n");
+    }
+
+    #[test]
+    fn test_quote_styles() {
+        assert_eq!(
+            normalize_quotes("\"It's 'fine'\" - (\"no\")", None, QuoteStyle::Curly),
+            "\u{201c}It\u{2019}s \u{2018}fine\u{2019}\u{201d} - (\u{201c}no\u{201d})"
+        );
+        assert_eq!(normalize_quotes("\u{201c}It\u{2019}s\u{201d}", None, QuoteStyle::Straight), "\"It's\"");
+        // The character before the text decides whether a quote opens
+        assert_eq!(normalize_quotes("'", Some('s'), QuoteStyle::Curly), "\u{2019}");
+        assert_eq!(QuoteStyle::parse("smart"), Some(QuoteStyle::Curly));
+        assert_eq!(QuoteStyle::parse("fancy"), None);
+    }
+
+    #[test]
+    fn test_unsupported_and_malformed() {
+        let dir = tempdir().unwrap();
+        let odt = dir.path().join("draft.odt");
+        fs::write(&odt, "zip").unwrap();
+        assert!(matches!(import_file(&odt, QuoteStyle::Keep), Err(ImportError::Unsupported(_))));
+
+        let fake = dir.path().join("draft.docx");
+        fs::write(&fake, "not a zip").unwrap();
+        assert!(matches!(import_file(&fake, QuoteStyle::Keep), Err(ImportError::Malformed(_))));
+    }
+}
@@ -8,6 +8,7 @@ pub enum Mode {
     Write,
     Navigate,
     Search,
+    Replace,
 }
 
 /// Actions that can be performed
@@ -18,6 +19,7 @@ pub enum Action {
     Save,
     // Text input
     InsertChar(char),
+    InsertTab,
     InsertNewline,
     DeleteChar,
     DeleteCharForward,
@@ -38,10 +40,16 @@ pub enum Action {
     ToggleStatus,
     ToggleSpellCheck,
     ShowSpellSuggestions,
+    NextMisspelling,
+    PrevMisspelling,
+    StartSpellReview,
+    CycleSpellLanguage,
+    ToggleLint,
     ShowHelp,
     ShowStats,
     ShowVersions,
     ShowProjectDocs,
+    ShowProjectSearch,
     HideOverlay,
     // Search
     StartSearch,
@@ -51,6 +59,19 @@ pub enum Action {
     SearchPrev,
     SearchInput(char),
     SearchBackspace,
+    SearchHistoryPrev,
+    SearchHistoryNext,
+    ToggleSearchRegex,
+    ToggleSearchWholeWord,
+    CycleSearchCase,
+    // Replace
+    StartReplace,
+    SubmitReplace,
+    CancelReplace,
+    ReplaceInput(char),
+    ReplaceBackspace,
+    ReplaceSwitchField,
+    ToggleReplaceScope,
 }
 
 /// State for multi-key sequences
@@ -59,6 +80,8 @@ pub struct InputState {
     pub pending_g: bool,
     pub pending_d: bool,
     pub pending_y: bool,
+    pub pending_rbracket: bool,
+    pub pending_lbracket: bool,
 }
 
 impl InputState {
@@ -66,13 +89,15 @@ impl InputState {
         self.pending_g = false;
         self.pending_d = false;
         self.pending_y = false;
+        self.pending_rbracket = false;
+        self.pending_lbracket = false;
     }
 }
 
 /// Handle a key event and return the corresponding action
 pub fn handle_key(key: KeyEvent, mode: Mode, state: &mut InputState) -> Action {
-    // Universal bindings (work in all modes except search)
-    if mode != Mode::Search {
+    // Universal bindings (work in all modes except the search and replace prompts)
+    if !matches!(mode, Mode::Search | Mode::Replace) {
         if let Some(action) = handle_universal(key) {
             state.clear();
             return action;
@@ -83,6 +108,7 @@ pub fn handle_key(key: KeyEvent, mode: Mode, state: &mut InputState) -> Action {
         Mode::Write => handle_write_mode(key, state),
         Mode::Navigate => handle_navigate_mode(key, state),
         Mode::Search => handle_search_mode(key, state),
+        Mode::Replace => handle_replace_mode(key, state),
     }
 }
 
@@ -95,6 +121,7 @@ fn handle_universal(key: KeyEvent) -> Option<Action> {
         (KeyCode::Char('y'), KeyModifiers::CONTROL) => Some(Action::Redo),
         (KeyCode::Char(';'), KeyModifiers::CONTROL) => Some(Action::ToggleSpellCheck),
         (KeyCode::Char('.'), KeyModifiers::CONTROL) => Some(Action::ShowSpellSuggestions),
+        (KeyCode::Char('l'), KeyModifiers::CONTROL) => Some(Action::ToggleLint),
         _ => None,
     }
 }
@@ -108,6 +135,7 @@ fn handle_write_mode(key: KeyEvent, state: &mut InputState) -> Action {
             Action::InsertChar(c)
         }
         KeyCode::Enter => Action::InsertNewline,
+        KeyCode::Tab => Action::InsertTab,
         KeyCode::Backspace => Action::DeleteChar,
         KeyCode::Delete => Action::DeleteCharForward,
         // Arrow keys
@@ -173,6 +201,22 @@ fn handle_navigate_mode(key: KeyEvent, state: &mut InputState) -> Action {
         // Invalid sequence, fall through
     }
 
+    if state.pending_rbracket {
+        state.pending_rbracket = false;
+        if key.code == KeyCode::Char('s') {
+            return Action::NextMisspelling;
+        }
+        // Invalid sequence, fall through
+    }
+
+    if state.pending_lbracket {
+        state.pending_lbracket = false;
+        if key.code == KeyCode::Char('s') {
+            return Action::PrevMisspelling;
+        }
+        // Invalid sequence, fall through
+    }
+
     match key.code {
         // Mode changes
         KeyCode::Char('i') => Action::EnterWriteMode,
@@ -222,12 +266,26 @@ fn handle_navigate_mode(key: KeyEvent, state: &mut InputState) -> Action {
         KeyCode::Char('/') => Action::StartSearch,
         KeyCode::Char('n') => Action::SearchNext,
         KeyCode::Char('N') => Action::SearchPrev,
+        KeyCode::Char('R') => Action::StartReplace,
 
         // Help, Stats, Versions, and Projects
         KeyCode::Char('?') => Action::ShowHelp,
         KeyCode::Char('s') => Action::ShowStats,
         KeyCode::Char('v') => Action::ShowVersions,
         KeyCode::Char('P') => Action::ShowProjectDocs,
+        KeyCode::Char('F') => Action::ShowProjectSearch,
+
+        // Spelling
+        KeyCode::Char(']') => {
+            state.pending_rbracket = true;
+            Action::None
+        }
+        KeyCode::Char('[') => {
+            state.pending_lbracket = true;
+            Action::None
+        }
+        KeyCode::Char('S') => Action::StartSpellReview,
+        KeyCode::Char('L') => Action::CycleSpellLanguage,
 
         // Arrow keys (also work in navigate mode)
         KeyCode::Left => Action::MoveCursor(Direction::Left, Unit::Char),
@@ -255,6 +313,11 @@ fn handle_search_mode(key: KeyEvent, state: &mut InputState) -> Action {
         KeyCode::Esc => Action::CancelSearch,
         KeyCode::Enter => Action::SubmitSearch,
         KeyCode::Backspace => Action::SearchBackspace,
+        KeyCode::Up => Action::SearchHistoryPrev,
+        KeyCode::Down => Action::SearchHistoryNext,
+        KeyCode::Char('r') if key.modifiers == KeyModifiers::ALT => Action::ToggleSearchRegex,
+        KeyCode::Char('w') if key.modifiers == KeyModifiers::ALT => Action::ToggleSearchWholeWord,
+        KeyCode::Char('c') if key.modifiers == KeyModifiers::ALT => Action::CycleSearchCase,
         KeyCode::Char(c) if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT => {
             Action::SearchInput(c)
         }
@@ -262,6 +325,25 @@ fn handle_search_mode(key: KeyEvent, state: &mut InputState) -> Action {
     }
 }
 
+fn handle_replace_mode(key: KeyEvent, state: &mut InputState) -> Action {
+    state.clear();
+
+    match key.code {
+        KeyCode::Esc => Action::CancelReplace,
+        KeyCode::Enter => Action::SubmitReplace,
+        KeyCode::Tab => Action::ReplaceSwitchField,
+        KeyCode::Backspace => Action::ReplaceBackspace,
+        KeyCode::Char('r') if key.modifiers == KeyModifiers::ALT => Action::ToggleSearchRegex,
+        KeyCode::Char('w') if key.modifiers == KeyModifiers::ALT => Action::ToggleSearchWholeWord,
+        KeyCode::Char('c') if key.modifiers == KeyModifiers::ALT => Action::CycleSearchCase,
+        KeyCode::Char('p') if key.modifiers == KeyModifiers::ALT => Action::ToggleReplaceScope,
+        KeyCode::Char(c) if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT => {
+            Action::ReplaceInput(c)
+        }
+        _ => Action::None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,6 +373,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ctrl_l_toggles_lint() {
+        let mut state = InputState::default();
+        assert_eq!(
+            handle_key(key_ctrl('l'), Mode::Write, &mut state),
+            Action::ToggleLint
+        );
+    }
+
     #[test]
     fn test_ctrl_q_quits() {
         let mut state = InputState::default();
@@ -411,6 +502,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bracket_s_jumps_between_misspellings() {
+        let mut state = InputState::default();
+
+        assert_eq!(
+            handle_key(key_char(']'), Mode::Navigate, &mut state),
+            Action::None
+        );
+        assert!(state.pending_rbracket);
+        assert_eq!(
+            handle_key(key_char('s'), Mode::Navigate, &mut state),
+            Action::NextMisspelling
+        );
+
+        handle_key(key_char('['), Mode::Navigate, &mut state);
+        assert_eq!(
+            handle_key(key_char('s'), Mode::Navigate, &mut state),
+            Action::PrevMisspelling
+        );
+    }
+
     #[test]
     fn test_search_mode() {
         let mut state = InputState::default();
@@ -438,6 +550,39 @@ mod tests {
             handle_key(key(KeyCode::Esc), Mode::Search, &mut state),
             Action::CancelSearch
         );
+
+        // Alt toggles search flags
+        let alt = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT);
+        assert_eq!(handle_key(alt('r'), Mode::Search, &mut state), Action::ToggleSearchRegex);
+        assert_eq!(handle_key(alt('w'), Mode::Search, &mut state), Action::ToggleSearchWholeWord);
+        assert_eq!(handle_key(alt('c'), Mode::Search, &mut state), Action::CycleSearchCase);
+
+        // Up/Down browse history
+        assert_eq!(handle_key(key(KeyCode::Up), Mode::Search, &mut state), Action::SearchHistoryPrev);
+        assert_eq!(handle_key(key(KeyCode::Down), Mode::Search, &mut state), Action::SearchHistoryNext);
+    }
+
+    #[test]
+    fn test_replace_mode() {
+        let mut state = InputState::default();
+        assert_eq!(
+            handle_key(key_char('R'), Mode::Navigate, &mut state),
+            Action::StartReplace
+        );
+        assert_eq!(
+            handle_key(key_char('q'), Mode::Replace, &mut state),
+            Action::ReplaceInput('q')
+        );
+        assert_eq!(
+            handle_key(key(KeyCode::Tab), Mode::Replace, &mut state),
+            Action::ReplaceSwitchField
+        );
+        // Ctrl+S is text entry here, not save
+        assert_eq!(handle_key(key_ctrl('s'), Mode::Replace, &mut state), Action::None);
+        assert_eq!(
+            handle_key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::ALT), Mode::Replace, &mut state),
+            Action::ToggleReplaceScope
+        );
     }
 
     #[test]
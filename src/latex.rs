@@ -0,0 +1,321 @@
+//! Export markdown to LaTeX.
+//!
+//! The body is written from the markdown events; the preamble (everything
+//! before `\begin{document}`) can be replaced with `--preamble`. Headings
+//! are unnumbered, and footnotes are placed inline as `\footnote{}`.
+
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Options, Tag, TagEnd};
+use std::collections::HashMap;
+use std::io;
+
+use crate::export::{self, ExportOptions, Exporter, Metadata, SourceDocument};
+
+/// Preamble used when none is configured
+pub const DEFAULT_PREAMBLE: &str = r"\documentclass[11pt]{article}
+\usepackage[T1]{fontenc}
+\usepackage[utf8]{inputenc}
+\usepackage{lmodern}
+\usepackage{microtype}
+\usepackage{graphicx}
+\usepackage[normalem]{ulem}
+\usepackage{hyperref}
+";
+
+/// LaTeX documents with a configurable preamble.
+pub struct LatexExporter {
+    preamble: String,
+    parser_options: Options,
+}
+
+impl LatexExporter {
+    pub fn new(options: &ExportOptions) -> Self {
+        Self {
+            preamble: options.preamble.clone().unwrap_or_else(|| DEFAULT_PREAMBLE.to_string()),
+            parser_options: export::parser_options(options),
+        }
+    }
+}
+
+impl Exporter for LatexExporter {
+    fn name(&self) -> &'static str {
+        "latex"
+    }
+
+    fn extension(&self) -> &'static str {
+        "tex"
+    }
+
+    fn export(&self, documents: &[SourceDocument], metadata: &Metadata) -> io::Result<Vec<u8>> {
+        let mut out = self.preamble.clone();
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&format!("\n\\title{{{}}}\n", escape(&metadata.title)));
+        if let Some(author) = &metadata.author {
+            out.push_str(&format!("\\author{{{}}}\n", escape(author)));
+        }
+        out.push_str(&format!("\\date{{{}}}\n\n\\begin{{document}}\n\\maketitle\n\n", escape(&metadata.date)));
+        out.push_str(&render_body(export::parse_documents(documents, self.parser_options)));
+        out.push_str("\\end{document}\n");
+        Ok(out.into_bytes())
+    }
+}
+
+/// Convert markdown events to the LaTeX of a document body.
+fn render_body(events: Vec<Event<'_>>) -> String {
+    // Footnote definitions are rendered first so references can inline them
+    let mut footnotes = HashMap::new();
+    let no_footnotes = HashMap::new();
+    let mut body = Vec::new();
+    let mut definition: Option<(String, Vec<Event>)> = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => definition = Some((label.to_string(), Vec::new())),
+            Event::End(TagEnd::FootnoteDefinition) => {
+                if let Some((label, events)) = definition.take() {
+                    let mut writer = LatexWriter::new(&no_footnotes);
+                    for event in events {
+                        writer.event(event);
+                    }
+                    footnotes.insert(label, writer.out.trim().to_string());
+                }
+            }
+            event => match &mut definition {
+                Some((_, events)) => events.push(event),
+                None => body.push(event),
+            },
+        }
+    }
+
+    let mut writer = LatexWriter::new(&footnotes);
+    for event in body {
+        writer.event(event);
+    }
+    writer.out
+}
+
+/// Writes LaTeX for a stream of markdown events
+struct LatexWriter<'a> {
+    out: String,
+    footnotes: &'a HashMap<String, String>,
+    in_code_block: bool,
+    /// Inside an image, whose alt text is dropped
+    in_image: bool,
+    /// Columns of the open table row written so far
+    cells: usize,
+}
+
+impl<'a> LatexWriter<'a> {
+    fn new(footnotes: &'a HashMap<String, String>) -> Self {
+        Self {
+            out: String::new(),
+            footnotes,
+            in_code_block: false,
+            in_image: false,
+            cells: 0,
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.in_code_block => self.out.push_str(&text),
+            Event::Text(_) if self.in_image => {}
+            Event::Text(text) => self.out.push_str(&escape(&text)),
+            Event::Code(text) => self.out.push_str(&format!("\\texttt{{{}}}", escape(&text))),
+            Event::SoftBreak => self.out.push('\n'),
+            Event::HardBreak => self.out.push_str("\\\\\n"),
+            Event::Rule => self.out.push_str("\\begin{center}\n*\\quad*\\quad*\n\\end{center}\n\n"),
+            Event::FootnoteReference(label) => match self.footnotes.get(label.as_ref()) {
+                Some(note) => self.out.push_str(&format!("\\footnote{{{}}}", note)),
+                None => self.out.push_str(&format!("\\textsuperscript{{{}}}", escape(export::footnote_label(&label)))),
+            },
+            Event::TaskListMarker(done) => self.out.push_str(if done { "{[x]} " } else { "{[ ]} " }),
+            Event::Html(_) | Event::InlineHtml(_) => {}
+        }
+    }
+
+    /// Start a new line unless at the start of one
+    fn line(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        if matches!(tag, Tag::BlockQuote | Tag::CodeBlock(_) | Tag::List(_)) {
+            self.line();
+        }
+        match tag {
+            Tag::Paragraph => {}
+            Tag::Heading { level, .. } => {
+                let command = match level {
+                    HeadingLevel::H1 => "section",
+                    HeadingLevel::H2 => "subsection",
+                    HeadingLevel::H3 => "subsubsection",
+                    _ => "paragraph",
+                };
+                self.out.push_str(&format!("\\{}*{{", command));
+            }
+            Tag::BlockQuote => self.out.push_str("\\begin{quote}\n"),
+            Tag::CodeBlock(_) => {
+                self.in_code_block = true;
+                self.out.push_str("\\begin{verbatim}\n");
+            }
+            Tag::List(None) => self.out.push_str("\\begin{itemize}\n"),
+            Tag::List(Some(start)) => {
+                self.out.push_str("\\begin{enumerate}\n");
+                if start != 1 {
+                    self.out.push_str(&format!("\\setcounter{{enumi}}{{{}}}\n", start.saturating_sub(1)));
+                }
+            }
+            Tag::Item => self.out.push_str("\\item "),
+            Tag::Emphasis => self.out.push_str("\\emph{"),
+            Tag::Strong => self.out.push_str("\\textbf{"),
+            Tag::Strikethrough => self.out.push_str("\\sout{"),
+            Tag::Link { dest_url, .. } => {
+                self.out.push_str(&format!("\\href{{{}}}{{", escape_url(&dest_url)));
+            }
+            Tag::Image { dest_url, .. } => {
+                self.in_image = true;
+                self.out.push_str(&format!(
+                    "\\begin{{center}}\n\\includegraphics[width=\\linewidth]{{{}}}\n\\end{{center}}\n",
+                    dest_url
+                ));
+            }
+            Tag::Table(alignments) => {
+                let columns: String = alignments
+                    .iter()
+                    .map(|alignment| match alignment {
+                        Alignment::Center => 'c',
+                        Alignment::Right => 'r',
+                        _ => 'l',
+                    })
+                    .collect();
+                self.out.push_str(&format!("\\begin{{center}}\n\\begin{{tabular}}{{{}}}\n", columns));
+            }
+            Tag::TableHead | Tag::TableRow => self.cells = 0,
+            Tag::TableCell => {
+                if self.cells > 0 {
+                    self.out.push_str(" & ");
+                }
+                self.cells += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.out.push_str("\n\n"),
+            TagEnd::Heading(_) => self.out.push_str("}\n\n"),
+            TagEnd::BlockQuote => self.out.push_str("\\end{quote}\n\n"),
+            TagEnd::CodeBlock => {
+                self.in_code_block = false;
+                self.line();
+                self.out.push_str("\\end{verbatim}\n\n");
+            }
+            TagEnd::List(ordered) => {
+                self.out.push_str(if ordered { "\\end{enumerate}\n\n" } else { "\\end{itemize}\n\n" });
+            }
+            TagEnd::Item => self.line(),
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => self.out.push('}'),
+            TagEnd::Image => self.in_image = false,
+            TagEnd::TableHead => self.out.push_str(" \\\\\n\\hline\n"),
+            TagEnd::TableRow => self.out.push_str(" \\\\\n"),
+            TagEnd::Table => self.out.push_str("\\end{tabular}\n\\end{center}\n\n"),
+            _ => {}
+        }
+    }
+}
+
+/// Escape LaTeX special characters in text.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape the characters `\href` cannot take literally in a URL.
+fn escape_url(url: &str) -> String {
+    url.replace('\\', "/").replace('%', "\\%").replace('#', "\\#")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::Options;
+
+    fn latex(markdown: &str) -> String {
+        latex_of(&[markdown])
+    }
+
+    fn latex_of(chapters: &[&str]) -> String {
+        let documents: Vec<SourceDocument> = chapters
+            .iter()
+            .map(|markdown| SourceDocument::from_markdown("chapter", markdown))
+            .collect();
+        render_body(export::parse_documents(&documents, Options::all()))
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("50% of $5 & #1_a"), "50\\% of \\$5 \\& \\#1\\_a");
+        assert_eq!(escape("a\\b~c^{d}"), "a\\textbackslash{}b\\textasciitilde{}c\\textasciicircum{}\\{d\\}");
+    }
+
+    #[test]
+    fn test_body_mapping() {
+        let tex = latex("# One\n\nShe *ran* and **ran**.\n\n***\n\n> Quoted\n\n1. first\n2. second\n\n```\nx_1 = {}\n```\n");
+        assert!(tex.contains("\\section*{One}\n\n"));
+        assert!(tex.contains("She \\emph{ran} and \\textbf{ran}.\n\n"));
+        assert!(tex.contains("\\begin{center}\n*\\quad*\\quad*\n\\end{center}"));
+        assert!(tex.contains("\\begin{quote}\nQuoted\n\n\\end{quote}"));
+        assert!(tex.contains("\\begin{enumerate}\n\\item first\n\\item second\n\\end{enumerate}"));
+        // Code is verbatim, unescaped
+        assert!(tex.contains("\\begin{verbatim}\nx_1 = {}\n\\end{verbatim}"));
+    }
+
+    #[test]
+    fn test_footnotes_are_inlined() {
+        let tex = latex("Text.[^1] More.\n\n[^1]: A *note*.\n");
+        assert!(tex.contains("Text.\\footnote{A \\emph{note}.} More."));
+        assert!(!tex.contains("[^1]"));
+
+        // Chapters that reuse a label keep their own notes
+        let tex = latex_of(&["A.[^1]\n\n[^1]: Note A.\n", "B.[^1]\n\n[^1]: Note B.\n"]);
+        assert!(tex.contains("A.\\footnote{Note A.}"));
+        assert!(tex.contains("B.\\footnote{Note B.}"));
+        assert!(latex("Lost.[^gone]").contains("\\textsuperscript{gone}"));
+    }
+
+    #[test]
+    fn test_custom_preamble_and_metadata() {
+        let options = ExportOptions {
+            preamble: Some("\\documentclass{book}".to_string()),
+            ..Default::default()
+        };
+        let metadata = Metadata {
+            title: "Fish & Chips".to_string(),
+            author: Some("Ann Lee".to_string()),
+            date: "2024-01-01".to_string(),
+            word_count: 2,
+        };
+        let documents = [SourceDocument::from_markdown("hello", "Hello.")];
+        let tex = String::from_utf8(LatexExporter::new(&options).export(&documents, &metadata).unwrap()).unwrap();
+        assert!(tex.starts_with("\\documentclass{book}\n\n\\title{Fish \\& Chips}\n\\author{Ann Lee}\n"));
+        assert!(tex.contains("\\begin{document}\n\\maketitle\n\nHello.\n\n\\end{document}\n"));
+    }
+}
@@ -0,0 +1,363 @@
+//! Offline prose linting
+//!
+//! Flags repeated words, weasel words, likely passive voice, adverb-heavy and
+//! overlong sentences, and clichés. Only prose is checked, the same text the
+//! spell checker sees, and problems are positioned the same way as misspellings.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use crate::config::LintConfig;
+use crate::markdown;
+
+/// Bundled cliché phrases, one per line
+const CLICHES: &str = include_str!("../dict/cliches.txt");
+
+/// Forms of "to be" that can start a passive construction
+const TO_BE: &[&str] = &[
+    "am", "is", "are", "was", "were", "be", "been", "being", "isn't", "aren't", "wasn't", "weren't",
+];
+
+/// Past participles that don't end in -ed
+const IRREGULAR_PARTICIPLES: &[&str] = &[
+    "beaten", "become", "begun", "bitten", "blown", "born", "borne", "bought", "bound", "broken",
+    "brought", "built", "caught", "chosen", "cut", "dealt", "done", "drawn", "driven", "eaten",
+    "fallen", "fed", "felt", "forbidden", "forgiven", "forgotten", "fought", "found", "frozen",
+    "given", "grown", "heard", "held", "hidden", "hit", "hung", "hurt", "kept", "known", "laid",
+    "led", "left", "lent", "lost", "made", "meant", "met", "paid", "put", "read", "ridden", "rung",
+    "said", "seen", "sent", "set", "shaken", "shot", "shown", "shut", "sold", "sought", "spent",
+    "spoken", "spun", "stolen", "struck", "stuck", "sung", "swept", "sworn", "taken", "taught",
+    "thought", "thrown", "told", "torn", "understood", "woken", "won", "worn", "woven", "written",
+];
+
+/// Words ending in -ly that aren't adverbs (or aren't worth counting)
+const NOT_ADVERBS: &[&str] = &[
+    "ally", "anomaly", "apply", "assembly", "belly", "bully", "butterfly", "comply", "costly",
+    "curly", "daily", "deadly", "elderly", "early", "family", "friendly", "holy", "hourly",
+    "imply", "italy", "jelly", "july", "likely", "lily", "lively", "lonely", "lovely", "monthly",
+    "multiply", "only", "orderly", "rally", "reply", "rely", "silly", "supply", "ugly", "weekly",
+    "yearly",
+];
+
+/// Kind of style problem
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LintKind {
+    RepeatedWord,
+    WeaselWord,
+    PassiveVoice,
+    Adverb,
+    LongSentence,
+    Cliche,
+}
+
+impl LintKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LintKind::RepeatedWord => "repeated word",
+            LintKind::WeaselWord => "weasel word",
+            LintKind::PassiveVoice => "passive voice",
+            LintKind::Adverb => "adverb",
+            LintKind::LongSentence => "long sentence",
+            LintKind::Cliche => "cliche",
+        }
+    }
+}
+
+/// A style problem with its position, indexed like `Misspelling`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub kind: LintKind,
+    pub message: String,
+    /// Byte offsets within the line
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    /// Char column of `start`
+    pub col: usize,
+}
+
+/// A word of prose with its byte range in the document
+struct Word {
+    range: Range<usize>,
+    lower: String,
+}
+
+/// Prose linter
+pub struct Linter {
+    config: LintConfig,
+    weasels: HashSet<String>,
+    /// Cliché phrases as word lists, keyed by their first word
+    cliches: HashMap<String, Vec<Vec<String>>>,
+    enabled: bool,
+}
+
+impl Linter {
+    pub fn new(config: &LintConfig) -> Self {
+        let mut cliches: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+        for line in CLICHES.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+            cliches.entry(words[0].clone()).or_default().push(words);
+        }
+
+        Self {
+            weasels: config.weasel_list.iter().map(|w| w.to_lowercase()).collect(),
+            cliches,
+            enabled: config.enabled,
+            config: config.clone(),
+        }
+    }
+
+    /// Check if linting is on
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Toggle linting on/off
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Lint a Markdown document, whether or not linting is switched on
+    pub fn lint(&self, text: &str) -> Vec<LintIssue> {
+        let mut found: Vec<(LintKind, Range<usize>, String)> = Vec::new();
+
+        for block in markdown::prose_blocks(text) {
+            let words = split_words(text, &block);
+            if words.is_empty() {
+                continue;
+            }
+            self.check_words(text, &words, &mut found);
+            for sentence in split_sentences(text, &words) {
+                self.check_sentence(&words[sentence], &mut found);
+            }
+        }
+
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let mut issues: Vec<LintIssue> = found
+            .into_iter()
+            .map(|(kind, range, detail)| to_issue(text, &line_starts, kind, range, detail))
+            .collect();
+        issues.sort_by_key(|i| (i.line, i.col));
+        issues
+    }
+
+    /// Checks on the run of words in a block: repeats, weasels, passives, clichés
+    fn check_words(&self, text: &str, words: &[Word], found: &mut Vec<(LintKind, Range<usize>, String)>) {
+        for (i, word) in words.iter().enumerate() {
+            let next = words.get(i + 1);
+
+            if self.config.repeated_words {
+                if let Some(next) = next {
+                    let gap = &text[word.range.end..next.range.start];
+                    if next.lower == word.lower && gap.chars().all(char::is_whitespace) {
+                        found.push((
+                            LintKind::RepeatedWord,
+                            word.range.start..next.range.end,
+                            format!("'{}'", &text[next.range.clone()]),
+                        ));
+                    }
+                }
+            }
+
+            if self.config.weasel_words && self.weasels.contains(&word.lower) {
+                found.push((
+                    LintKind::WeaselWord,
+                    word.range.clone(),
+                    format!("'{}'", &text[word.range.clone()]),
+                ));
+            }
+
+            if self.config.passive_voice && TO_BE.contains(&word.lower.as_str()) {
+                // "was taken", "is often overlooked", "was very clearly decided"
+                let mut j = i + 1;
+                while j < i + 3
+                    && words.get(j).is_some_and(|w| is_adverb(&w.lower) || self.weasels.contains(&w.lower))
+                {
+                    j += 1;
+                }
+                if let Some(participle) = words.get(j).filter(|w| is_participle(&w.lower)) {
+                    let range = word.range.start..participle.range.end;
+                    let phrase = text[range.clone()].split_whitespace().collect::<Vec<_>>().join(" ");
+                    found.push((LintKind::PassiveVoice, range, format!("'{}'", phrase)));
+                }
+            }
+
+            if self.config.cliches {
+                for phrase in self.cliches.get(&word.lower).into_iter().flatten() {
+                    let matches = phrase.len() <= words.len() - i
+                        && phrase.iter().zip(&words[i..]).all(|(p, w)| *p == w.lower);
+                    if matches {
+                        let last = &words[i + phrase.len() - 1];
+                        found.push((
+                            LintKind::Cliche,
+                            word.range.start..last.range.end,
+                            format!("'{}'", phrase.join(" ")),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks on a whole sentence: length and adverb density
+    fn check_sentence(&self, sentence: &[Word], found: &mut Vec<(LintKind, Range<usize>, String)>) {
+        let count = sentence.len();
+
+        if self.config.long_sentences && count > self.config.max_sentence_words {
+            let range = sentence[0].range.start..sentence[count - 1].range.end;
+            found.push((
+                LintKind::LongSentence,
+                range,
+                format!("{} words (max {})", count, self.config.max_sentence_words),
+            ));
+        }
+
+        if self.config.adverbs {
+            let adverbs: Vec<&Word> = sentence.iter().filter(|w| is_adverb(&w.lower)).collect();
+            let density = adverbs.len() as f64 / count as f64;
+            if adverbs.len() >= 2 && density > self.config.max_adverb_density {
+                for adverb in adverbs.iter() {
+                    found.push((
+                        LintKind::Adverb,
+                        adverb.range.clone(),
+                        format!("'{}' ({} in a {}-word sentence)", adverb.lower, adverbs.len(), count),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Words in the prose ranges of one block: runs of letters and apostrophes
+fn split_words(text: &str, block: &[Range<usize>]) -> Vec<Word> {
+    let mut words = Vec::new();
+    for range in block {
+        let mut start = None;
+        let segment = &text[range.clone()];
+        for (i, c) in segment.char_indices().chain(std::iter::once((segment.len(), ' '))) {
+            if c.is_alphabetic() || (c == '\'' && start.is_some()) {
+                start.get_or_insert(i);
+            } else if let Some(s) = start.take() {
+                let word = segment[s..i].trim_end_matches('\'');
+                words.push(Word {
+                    range: range.start + s..range.start + s + word.len(),
+                    lower: word.to_lowercase(),
+                });
+            }
+        }
+    }
+    words
+}
+
+/// Split a block's words into sentences at `.`, `!` and `?`
+fn split_sentences(text: &str, words: &[Word]) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for i in 1..words.len() {
+        let gap = &text[words[i - 1].range.end..words[i].range.start];
+        if gap.contains(['.', '!', '?']) {
+            sentences.push(start..i);
+            start = i;
+        }
+    }
+    sentences.push(start..words.len());
+    sentences
+}
+
+fn is_adverb(word: &str) -> bool {
+    word.len() >= 5 && word.ends_with("ly") && !NOT_ADVERBS.contains(&word)
+}
+
+fn is_participle(word: &str) -> bool {
+    (word.len() > 4 && word.ends_with("ed")) || IRREGULAR_PARTICIPLES.contains(&word)
+}
+
+/// Position a document byte range as a line issue. Ranges that run onto
+/// later lines are cut at the end of their first line.
+fn to_issue(
+    text: &str,
+    line_starts: &[usize],
+    kind: LintKind,
+    range: Range<usize>,
+    detail: String,
+) -> LintIssue {
+    let line = line_starts.partition_point(|s| *s <= range.start) - 1;
+    let line_start = line_starts[line];
+    let line_end = line_starts.get(line + 1).map_or(text.len(), |next| next - 1);
+    let line_text = text[line_start..line_end].trim_end_matches('\r');
+    let start = range.start - line_start;
+    let end = (range.end - line_start).min(line_text.len());
+
+    LintIssue {
+        kind,
+        message: format!("{}: {}", kind.label(), detail),
+        start,
+        end,
+        line,
+        col: line_text[..start].chars().count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(text: &str) -> Vec<LintIssue> {
+        Linter::new(&LintConfig::default()).lint(text)
+    }
+
+    fn kinds(issues: &[LintIssue]) -> Vec<LintKind> {
+        issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_repeated_word() {
+        let issues = lint("She walked to the the door.");
+        assert_eq!(kinds(&issues), vec![LintKind::RepeatedWord]);
+        assert_eq!((issues[0].start, issues[0].end), (14, 21));
+
+        // Across a sentence break is fine
+        assert!(lint("It was over. Over and done.").iter().all(|i| i.kind != LintKind::RepeatedWord));
+    }
+
+    #[test]
+    fn test_weasel_and_passive() {
+        let issues = lint("The letter was quickly written by a very tired clerk.");
+        assert_eq!(kinds(&issues), vec![LintKind::PassiveVoice, LintKind::WeaselWord]);
+        assert_eq!(issues[0].message, "passive voice: 'was quickly written'");
+        assert_eq!(issues[1].col, 36);
+    }
+
+    #[test]
+    fn test_adverb_density_and_long_sentence() {
+        let issues = lint("He slowly and quietly left.");
+        assert_eq!(kinds(&issues), vec![LintKind::Adverb, LintKind::Adverb]);
+
+        let words: Vec<&str> = ["one", "more", "word"].iter().cycle().take(40).copied().collect();
+        let long = format!("{}.", words.join(" "));
+        let issues = lint(&long);
+        assert_eq!(kinds(&issues), vec![LintKind::LongSentence]);
+        assert_eq!(issues[0].message, "long sentence: 40 words (max 35)");
+    }
+
+    #[test]
+    fn test_cliche_across_lines() {
+        let issues = lint("And at the end\nof the day, we left.");
+        assert_eq!(kinds(&issues), vec![LintKind::Cliche]);
+        // Cut at the end of the first line
+        assert_eq!((issues[0].line, issues[0].start, issues[0].end), (0, 4, 14));
+    }
+
+    #[test]
+    fn test_skips_code_and_respects_switches() {
+        assert!(lint("```\nthe the very\n```\n").is_empty());
+
+        let config = LintConfig { weasel_words: false, ..LintConfig::default() };
+        assert!(Linter::new(&config).lint("It was very good.").is_empty());
+    }
+}
@@ -1,20 +1,37 @@
 mod app;
+mod archive;
+mod compile;
 mod config;
+mod docx;
 mod editor;
+mod epub;
 mod export;
 mod focus;
+mod frontmatter;
+mod import;
 mod input;
+mod latex;
+mod lint;
+mod markdown;
+mod pdf;
+mod plaintext;
+mod preview;
 mod project;
+mod project_search;
+mod replace;
 mod search;
 mod session;
 mod spell;
 mod stats;
 mod theme;
+mod ttf;
 mod ui;
 mod versions;
+mod watch;
+mod width;
 
 use std::io::{self, stdout};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use crossterm::{
@@ -45,7 +62,12 @@ fn run() -> io::Result<()> {
     if args.len() >= 2 {
         match args[1].as_str() {
             "export" => return run_export(&args[2..]),
+            "preview" => return run_preview(&args[2..]),
+            "import" => return run_import(&args[2..]),
             "project" => return run_project(&args[2..]),
+            "themes" => return run_themes(&args[2..]),
+            "doctor" => return run_doctor(&args[2..]),
+            "lint" => return run_lint(&args[2..]),
             _ => {}
         }
     }
@@ -54,6 +76,7 @@ fn run() -> io::Result<()> {
     let mut file_path: Option<PathBuf> = None;
     let mut width_override: Option<usize> = None;
     let mut no_autosave = false;
+    let mut theme_override: Option<String> = None;
     let mut i = 1;
 
     while i < args.len() {
@@ -75,6 +98,12 @@ fn run() -> io::Result<()> {
             "--no-autosave" => {
                 no_autosave = true;
             }
+            "--theme" => {
+                i += 1;
+                if i < args.len() {
+                    theme_override = Some(args[i].clone());
+                }
+            }
             arg if !arg.starts_with('-') => {
                 file_path = Some(PathBuf::from(arg));
             }
@@ -96,8 +125,25 @@ fn run() -> io::Result<()> {
         }
     };
 
+    // Unknown theme names are an error on the command line; the override
+    // replaces the configured theme, so a broken one there is not checked
+    if let Some(ref name) = theme_override {
+        if let Err(e) = theme::Theme::load(name) {
+            eprintln!("{}", e);
+            eprintln!("Run 'hollow themes list' to see available themes.");
+            process::exit(1);
+        }
+    }
+
     // Load config with overrides
-    let config = Config::load().with_overrides(width_override, no_autosave);
+    let config = match Config::try_load(theme_override) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Config error: {}", e);
+            process::exit(1);
+        }
+    };
+    let config = config.with_overrides(width_override, no_autosave);
 
     // Setup panic hook to restore terminal
     let original_hook = std::panic::take_hook();
@@ -136,10 +182,73 @@ fn run() -> io::Result<()> {
     result
 }
 
+/// `hollow export` settings given as flags; unset ones fall back to the
+/// [export] config
+#[derive(Default, Clone)]
+struct ExportFlags {
+    title: Option<String>,
+    css: Option<String>,
+    template: Option<String>,
+    author: Option<String>,
+    date: Option<String>,
+    cover: Option<PathBuf>,
+    language: Option<String>,
+    preset: Option<String>,
+    page_size: Option<String>,
+    margins: Option<String>,
+    ragged_right: bool,
+    preamble: Option<String>,
+    text_width: Option<usize>,
+    toc: bool,
+    smart_punctuation: bool,
+}
+
+/// Parse an export option at `args[*i]` into `flags`, moving `i` past its
+/// value. Returns false for arguments that aren't export options.
+fn parse_export_flag(args: &[String], i: &mut usize, flags: &mut ExportFlags) -> bool {
+    let arg = args[*i].as_str();
+    match arg {
+        "--ragged" => flags.ragged_right = true,
+        "--toc" => flags.toc = true,
+        "--smart" => flags.smart_punctuation = true,
+        "--width" => {
+            *i += 1;
+            match args.get(*i).map(|w| w.parse::<usize>()) {
+                Some(Ok(width)) => flags.text_width = Some(width),
+                _ => {
+                    eprintln!("--width needs a number of columns (0 to not wrap)");
+                    process::exit(1);
+                }
+            }
+        }
+        "--title" | "-t" | "--author" | "--css" | "--template" | "--cover" | "--lang" | "--preset"
+        | "--page-size" | "--margins" | "--preamble" => {
+            *i += 1;
+            let Some(value) = args.get(*i).cloned() else { return true };
+            match arg {
+                "--title" | "-t" => flags.title = Some(value),
+                "--author" => flags.author = Some(value),
+                "--css" => flags.css = Some(value),
+                "--template" => flags.template = Some(value),
+                "--cover" => flags.cover = Some(PathBuf::from(value)),
+                "--lang" => flags.language = Some(value),
+                "--preset" => flags.preset = Some(value),
+                "--page-size" => flags.page_size = Some(value),
+                "--margins" => flags.margins = Some(value),
+                _ => flags.preamble = Some(value),
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
 fn run_export(args: &[String]) -> io::Result<()> {
     let mut input_path: Option<PathBuf> = None;
     let mut output_path: Option<PathBuf> = None;
-    let mut title: Option<String> = None;
+    let mut format = "html".to_string();
+    let mut flags = ExportFlags::default();
+    let mut watch = false;
     let mut i = 0;
 
     while i < args.len() {
@@ -148,18 +257,20 @@ fn run_export(args: &[String]) -> io::Result<()> {
                 print_export_help();
                 return Ok(());
             }
-            "--output" | "-o" => {
+            "--format" | "-f" => {
                 i += 1;
                 if i < args.len() {
-                    output_path = Some(PathBuf::from(&args[i]));
+                    format = args[i].to_lowercase();
                 }
             }
-            "--title" | "-t" => {
+            "--output" | "-o" => {
                 i += 1;
                 if i < args.len() {
-                    title = Some(args[i].clone());
+                    output_path = Some(PathBuf::from(&args[i]));
                 }
             }
+            "--watch" | "-w" => watch = true,
+            _ if parse_export_flag(args, &mut i, &mut flags) => {}
             arg if !arg.starts_with('-') => {
                 input_path = Some(PathBuf::from(arg));
             }
@@ -180,21 +291,265 @@ fn run_export(args: &[String]) -> io::Result<()> {
         }
     };
 
-    // Default output: same name with .html extension
-    let output = output_path.unwrap_or_else(|| input.with_extension("html"));
+    if !watch {
+        return match export_input(&input, &format, flags, output_path.as_deref()) {
+            Ok(output) => {
+                println!("Exported to {}", output.display());
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("Export failed: {}", e);
+                process::exit(1);
+            }
+        };
+    }
+
+    // Watching keeps going after a failed export; the next save may fix it
+    let mut watcher = watch::Watcher::new(&input);
+    loop {
+        let time = chrono::Local::now().format("%H:%M:%S");
+        match export_input(&input, &format, flags.clone(), output_path.as_deref()) {
+            Ok(output) => println!("[{}] Exported to {}", time, output.display()),
+            Err(e) => eprintln!("[{}] Export failed: {}", time, e),
+        }
+        watcher.wait();
+    }
+}
 
-    let options = export::ExportOptions {
-        title,
-        css: None,
+/// Load the documents to export and the options for them. A project file or
+/// directory exports every document in the project.
+fn load_export(input: &Path, mut flags: ExportFlags) -> io::Result<(Vec<export::SourceDocument>, Option<String>, export::ExportOptions)> {
+    let (documents, project_name) = export::load_sources(input)?;
+    flags.title = flags.title.or(project_name.clone());
+    apply_front_matter(&mut flags, &documents);
+    let options = export_options(flags, Config::load())?;
+    Ok((documents, project_name, options))
+}
+
+/// Export `input` in a format; returns the file written
+fn export_input(input: &Path, format: &str, flags: ExportFlags, output: Option<&Path>) -> io::Result<PathBuf> {
+    let (documents, project_name, options) = load_export(input, flags)?;
+    let extension = format_extension(format, &options);
+
+    // Default output: same name with the format's extension; projects are
+    // named after the project, next to the project file
+    let output = output.map(PathBuf::from).unwrap_or_else(|| match &project_name {
+        Some(name) if input.is_dir() => input.join(format!("{}.{}", name, extension)),
+        Some(name) => input.with_file_name(format!("{}.{}", name, extension)),
+        None => input.with_extension(&extension),
+    });
+    if let Some(source) = overwritten_source(&output, &export::source_paths(input)?) {
+        let message = format!(
+            "{} would overwrite the source {}; choose another file with --output",
+            output.display(),
+            source.display()
+        );
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+    export_documents(format, &documents, &output, &options)?;
+    Ok(output)
+}
+
+/// The source that writing `output` would overwrite, if any
+fn overwritten_source<'a>(output: &Path, sources: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    // A file that doesn't exist yet can't be a source
+    let output = output.canonicalize().ok()?;
+    sources.iter().find(|source| source.canonicalize().is_ok_and(|s| s == output))
+}
+
+/// `hollow preview`: serve the HTML export and reload it on every save
+fn run_preview(args: &[String]) -> io::Result<()> {
+    let mut input_path: Option<PathBuf> = None;
+    let mut port = preview::DEFAULT_PORT;
+    let mut flags = ExportFlags::default();
+    let mut i = 0;
+
+    while i < args.len() {
+        match args[i].as_str() {
+            "--help" | "-h" => {
+                print_preview_help();
+                return Ok(());
+            }
+            "--port" | "-p" => {
+                i += 1;
+                match args.get(i).map(|p| p.parse::<u16>()) {
+                    Some(Ok(p)) => port = p,
+                    _ => {
+                        eprintln!("--port needs a port number");
+                        process::exit(1);
+                    }
+                }
+            }
+            _ if parse_export_flag(args, &mut i, &mut flags) => {}
+            arg if !arg.starts_with('-') => input_path = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("Unknown option: {}", args[i]);
+                process::exit(1);
+            }
+        }
+        i += 1;
+    }
+
+    let Some(input) = input_path else {
+        eprintln!("Usage: hollow preview <file> [OPTIONS]");
+        eprintln!("Run 'hollow preview --help' for more information.");
+        process::exit(1);
     };
+    if !input.exists() {
+        eprintln!("Preview failed: {} not found", input.display());
+        process::exit(1);
+    }
 
-    match export::export_to_html(&input, &output, &options) {
-        Ok(()) => {
-            println!("Exported to {}", output.display());
-            Ok(())
+    let listener = match std::net::TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Preview failed: cannot listen on port {}: {}", port, e);
+            process::exit(1);
         }
+    };
+    println!("Previewing {} at http://127.0.0.1:{}/ (Ctrl-C to stop)", input.display(), port);
+
+    let source = input.clone();
+    preview::serve(listener, &input, move || {
+        let (documents, _, options) = load_export(&source, flags.clone())?;
+        let metadata = export::Metadata::new(&documents, &options);
+        Ok(export::HtmlExporter::new(&options).render(&documents, &metadata))
+    })
+}
+
+/// `hollow import`: convert a DOCX, HTML or plain text draft to Markdown
+fn run_import(args: &[String]) -> io::Result<()> {
+    let mut input_path: Option<PathBuf> = None;
+    let mut output: Option<String> = None;
+    let mut quotes = import::QuoteStyle::default();
+    let mut force = false;
+    let mut i = 0;
+
+    while i < args.len() {
+        match args[i].as_str() {
+            "--help" | "-h" => {
+                print_import_help();
+                return Ok(());
+            }
+            "--output" | "-o" => {
+                i += 1;
+                output = args.get(i).cloned();
+            }
+            "--quotes" | "-q" => {
+                i += 1;
+                match args.get(i).and_then(|q| import::QuoteStyle::parse(q)) {
+                    Some(style) => quotes = style,
+                    None => {
+                        eprintln!("--quotes needs one of: keep, straight, curly");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--force" => force = true,
+            arg if !arg.starts_with('-') => input_path = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("Unknown option: {}", args[i]);
+                process::exit(1);
+            }
+        }
+        i += 1;
+    }
+
+    let Some(input) = input_path else {
+        eprintln!("Usage: hollow import <file> [OPTIONS]");
+        eprintln!("Run 'hollow import --help' for more information.");
+        process::exit(1);
+    };
+    let markdown = match import::import_file(&input, quotes) {
+        Ok(markdown) => markdown,
         Err(e) => {
-            eprintln!("Export failed: {}", e);
+            eprintln!("Import failed: {}", e);
+            process::exit(1);
+        }
+    };
+
+    // "-" writes to stdout, for piping
+    if output.as_deref() == Some("-") {
+        print!("{}", markdown);
+        return Ok(());
+    }
+    let output = output.map(PathBuf::from).unwrap_or_else(|| input.with_extension("md"));
+    if output.exists() && !force {
+        eprintln!("Import failed: {} already exists (use --force to overwrite)", output.display());
+        process::exit(1);
+    }
+    std::fs::write(&output, markdown)?;
+    println!("Imported {} to {}", input.display(), output.display());
+    Ok(())
+}
+
+/// Fill the title, author and date the flags leave unset from the first
+/// document's front matter
+fn apply_front_matter(flags: &mut ExportFlags, documents: &[export::SourceDocument]) {
+    if let Some(first) = documents.first() {
+        flags.title = flags.title.take().or_else(|| first.field("title"));
+        flags.author = flags.author.take().or_else(|| first.field("author"));
+        flags.date = flags.date.take().or_else(|| first.field("date"));
+    }
+}
+
+/// Build export options from flags, falling back to the [export] config
+fn export_options(flags: ExportFlags, config: Config) -> io::Result<export::ExportOptions> {
+    let css = flags.css.or(config.export.css);
+    let template = flags.template.or(config.export.template);
+    let preamble = flags.preamble.or(config.export.preamble);
+
+    Ok(export::ExportOptions {
+        title: flags.title,
+        css: css.map(|name| read_export_file(&name, "css")).transpose()?,
+        template: template.map(|name| read_export_file(&name, "html")).transpose()?,
+        author: flags.author.or(config.export.author),
+        date: flags.date,
+        language: flags.language.or_else(|| Some(config.spelling.language.replace('_', "-"))),
+        cover: flags.cover,
+        preset: flags.preset,
+        page_size: flags.page_size.or(config.export.page_size),
+        margins: flags.margins.or(config.export.margins),
+        ragged_right: flags.ragged_right || config.export.ragged_right,
+        preamble: preamble.map(|name| read_export_file(&name, "tex")).transpose()?,
+        text_width: flags.text_width.or(config.export.text_width),
+        toc: flags.toc || config.export.toc,
+        smart_punctuation: flags.smart_punctuation || config.export.smart_punctuation,
+    })
+}
+
+/// Output file extension for a format; exits on an unknown format
+fn format_extension(format: &str, options: &export::ExportOptions) -> String {
+    let exporters = export::exporters(options);
+    if let Some(exporter) = exporters.iter().find(|e| e.name() == format) {
+        return exporter.extension().to_string();
+    }
+    let names: Vec<&str> = exporters.iter().map(|e| e.name()).collect();
+    eprintln!("Unknown format: {} (expected one of: {})", format, names.join(", "));
+    process::exit(1);
+}
+
+/// Write documents with the registered exporter for the format
+fn export_documents(
+    format: &str,
+    documents: &[export::SourceDocument],
+    output: &Path,
+    options: &export::ExportOptions,
+) -> io::Result<()> {
+    let exporter = export::find_exporter(format, options)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown format: {}", format)))?;
+    export::export_to_file(exporter.as_ref(), documents, output, options)
+}
+
+/// Read a template or stylesheet given by path or by name in the templates directory
+fn read_export_file(name: &str, extension: &str) -> io::Result<String> {
+    match export::find_template_file(name, extension) {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let dir = export::templates_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_else(|| "~/.config/hollow/templates".to_string());
+            eprintln!("Export failed: '{}' not found (also looked in {})", name, dir);
             process::exit(1);
         }
     }
@@ -202,7 +557,6 @@ fn run_export(args: &[String]) -> io::Result<()> {
 
 fn run_project(args: &[String]) -> io::Result<()> {
     use project::Project;
-    use std::path::Path;
 
     if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
         print_project_help();
@@ -303,6 +657,40 @@ fn run_project(args: &[String]) -> io::Result<()> {
                 }
             }
         }
+        "compile" => run_compile(remaining),
+        "grep" => {
+            let Some(pattern) = remaining.first() else {
+                eprintln!("Usage: hollow project grep <pattern> [file]");
+                process::exit(1);
+            };
+            let path = remaining.get(1).map(|s| s.as_str()).unwrap_or(".hollow-project");
+            let project = match Project::load(path) {
+                Ok(project) => project,
+                Err(e) => {
+                    eprintln!("Failed to load project: {}", e);
+                    process::exit(1);
+                }
+            };
+
+            let mut search = search::Search::new();
+            search.set_query(pattern);
+            if let Some(err) = search.error() {
+                eprintln!("Invalid pattern: {}", err);
+                process::exit(2);
+            }
+
+            let results = project_search::search_project(&project, &search, None);
+            for file in &results {
+                for m in &file.matches {
+                    println!("{}:{}:{}: {}", file.document, m.line + 1, m.col + 1, m.context);
+                }
+            }
+            // Like grep: non-zero when nothing matched
+            if results.is_empty() {
+                process::exit(1);
+            }
+            Ok(())
+        }
         _ => {
             eprintln!("Unknown project command: {}", subcommand);
             print_project_help();
@@ -311,6 +699,308 @@ fn run_project(args: &[String]) -> io::Result<()> {
     }
 }
 
+/// `hollow project compile`: assemble the project and write it in one format
+fn run_compile(args: &[String]) -> io::Result<()> {
+    use project::Project;
+
+    let mut path = PathBuf::from(".hollow-project");
+    let mut format: Option<String> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--format" | "-f" => {
+                i += 1;
+                format = args.get(i).map(|f| f.to_lowercase());
+            }
+            "--output" | "-o" => {
+                i += 1;
+                output = args.get(i).map(PathBuf::from);
+            }
+            arg if !arg.starts_with('-') => path = PathBuf::from(arg),
+            _ => {
+                eprintln!("Unknown option: {}", args[i]);
+                process::exit(1);
+            }
+        }
+        i += 1;
+    }
+    if path.is_dir() {
+        path = path.join(".hollow-project");
+    }
+
+    let project = match Project::load(&path) {
+        Ok(project) => project,
+        Err(e) => {
+            eprintln!("Failed to load project: {}", e);
+            process::exit(1);
+        }
+    };
+    let manuscript = match compile::compile(&project) {
+        Ok(manuscript) => manuscript,
+        Err(e) => {
+            eprintln!("Compile failed: {}", e);
+            process::exit(1);
+        }
+    };
+
+    // Flags override the project's compile settings
+    let format = format.unwrap_or_else(|| project.compile.format.clone());
+    let mut flags = ExportFlags {
+        title: Some(project.name.clone()),
+        ..Default::default()
+    };
+    apply_front_matter(&mut flags, &manuscript.chapters);
+    let options = export_options(flags, Config::load())?;
+    let extension = if format == "md" { format.clone() } else { format_extension(&format, &options) };
+
+    let base = project.base_dir().unwrap_or_default();
+    let output = output.unwrap_or_else(|| match &project.compile.output {
+        Some(file) => base.join(file),
+        None => base.join(format!("{}.{}", project.name, extension)),
+    });
+    if let Some(source) = overwritten_source(&output, &export::source_paths(&path)?) {
+        eprintln!(
+            "Compile failed: {} is part of the project; choose another file with --output",
+            source.display()
+        );
+        process::exit(1);
+    }
+
+    let result = if format == "md" {
+        std::fs::write(&output, manuscript.to_markdown())
+    } else {
+        export_documents(&format, &manuscript.chapters, &output, &options)
+    };
+    match result {
+        Ok(()) => {
+            println!("Compiled {} documents to {}", manuscript.chapters.len(), output.display());
+            for doc in &manuscript.skipped {
+                println!("  skipped note: {}", doc);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Compile failed: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_themes(args: &[String]) -> io::Result<()> {
+    use ratatui::backend::TestBackend;
+    use theme::Theme;
+
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        print_themes_help();
+        return Ok(());
+    }
+
+    match args[0].as_str() {
+        "list" => {
+            let active = Config::load().theme.preset;
+            for name in Theme::available() {
+                let kind = if Theme::presets().contains(&name.as_str()) { "preset" } else { "file" };
+                let marker = if name == active { " *" } else { "" };
+                println!("  {:<16} {}{}", name, kind, marker);
+            }
+            if let Some(dir) = Theme::themes_dir() {
+                println!();
+                println!("Theme files: {}", dir.display());
+            }
+            Ok(())
+        }
+        "preview" => {
+            let name = match args.get(1) {
+                Some(n) => n,
+                None => {
+                    eprintln!("Usage: hollow themes preview <name>");
+                    process::exit(1);
+                }
+            };
+            let theme = match Theme::load(name) {
+                Ok(t) => t.for_terminal(theme::ColorSupport::detect()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            let mut terminal = Terminal::new(TestBackend::new(60, 18))?;
+            terminal.draw(|f| ui::render_theme_preview(f, &theme))?;
+            print!("{}", ui::buffer_to_ansi(terminal.backend().buffer()));
+            Ok(())
+        }
+        other => {
+            eprintln!("Unknown themes command: {}", other);
+            print_themes_help();
+            process::exit(1);
+        }
+    }
+}
+
+fn run_doctor(args: &[String]) -> io::Result<()> {
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        print_doctor_help();
+        return Ok(());
+    }
+
+    match args[0].as_str() {
+        "spell" => {
+            let config = Config::load();
+            let mut checker = spell::SpellChecker::from_config(&config.spelling);
+            if let Ok(project) = project::Project::load(".hollow-project") {
+                checker.set_project_dict(project.dictionary_path());
+            }
+
+            let state = if !config.spelling.enabled {
+                "disabled in config"
+            } else if checker.is_enabled() {
+                "enabled"
+            } else {
+                "unavailable (no dictionary loaded)"
+            };
+            println!("Spell checking: {}", state);
+            println!("Languages: {}", checker.languages().join(", "));
+
+            for report in checker.reports() {
+                println!();
+                println!("{}:", report.language);
+                for (path, found) in &report.tried {
+                    let status = if *found { "found" } else { "not found" };
+                    println!("  tried   {} ({})", path.display(), status);
+                }
+                match &report.source {
+                    Some(spell::DictSource::File(path)) => println!("  loaded  {}", path.display()),
+                    Some(spell::DictSource::Bundled) => {
+                        println!("  loaded  built-in word list (common words only)")
+                    }
+                    None => println!("  loaded  nothing"),
+                }
+            }
+
+            println!();
+            for (name, path, words) in checker.word_lists() {
+                match path {
+                    Some(path) => println!("{} word list: {} ({} words)", name, path.display(), words),
+                    None => println!("{} word list: not configured", name),
+                }
+            }
+
+            // Like other diagnostics tools: non-zero when something is wrong
+            if config.spelling.enabled && !checker.is_enabled() {
+                process::exit(1);
+            }
+            Ok(())
+        }
+        other => {
+            eprintln!("Unknown doctor check: {}", other);
+            print_doctor_help();
+            process::exit(1);
+        }
+    }
+}
+
+fn run_lint(args: &[String]) -> io::Result<()> {
+    if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
+        print_lint_help();
+        return Ok(());
+    }
+
+    let config = Config::load();
+    let linter = lint::Linter::new(&config.lint);
+    let mut problems = 0;
+    let mut unreadable = false;
+
+    for file in args {
+        let text = match std::fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                unreadable = true;
+                continue;
+            }
+        };
+        for issue in linter.lint(&text) {
+            println!("{}:{}:{}: {}", file, issue.line + 1, issue.col + 1, issue.message);
+            problems += 1;
+        }
+    }
+
+    if unreadable {
+        process::exit(2);
+    }
+    if problems > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn print_lint_help() {
+    println!(
+        r#"hollow lint - Check prose style
+
+USAGE:
+    hollow lint <file>...
+
+Flags repeated words, weasel words, likely passive voice, adverb-heavy
+and overlong sentences, and cliches. Prints file:line:col: message for
+each problem. Checks are configured in the [lint] section of the config.
+
+EXIT STATUS:
+    0    No problems found
+    1    Problems found
+    2    A file could not be read
+
+EXAMPLES:
+    hollow lint draft.md
+    hollow lint chapters/*.md"#
+    );
+}
+
+fn print_doctor_help() {
+    println!(
+        r#"hollow doctor - Check that features are set up correctly
+
+USAGE:
+    hollow doctor <check>
+
+CHECKS:
+    spell    Show where dictionaries were looked for, what loaded,
+             and the size of each word list
+
+Dictionaries are searched for in the `dictionary_path` setting, then
+HOLLOW_DICT_PATH and DICPATH (lists of directories), then the system
+Hunspell locations. Without any, en_US falls back to a built-in list
+of common words.
+
+EXAMPLES:
+    hollow doctor spell
+    HOLLOW_DICT_PATH=~/dicts hollow doctor spell"#
+    );
+}
+
+fn print_themes_help() {
+    println!(
+        r#"hollow themes - List and preview color themes
+
+USAGE:
+    hollow themes <command>
+
+COMMANDS:
+    list             List presets and theme files
+    preview <name>   Render a sample page in the given theme
+
+Theme files live in ~/.config/hollow/themes/<name>.toml and may set
+`inherits = "<preset>"` to start from a built-in theme.
+
+EXAMPLES:
+    hollow themes list
+    hollow themes preview sepia
+    hollow --theme sepia draft.md"#
+    );
+}
+
 fn print_project_help() {
     println!(
         r#"hollow project - Manage writing projects
@@ -323,34 +1013,152 @@ COMMANDS:
     info [file]      Show project information
     add <file>...    Add documents to the project
     stats [file]     Show word count statistics
+    grep <pattern> [file]
+                     Search every document; prints doc:line:col: text.
+                     Accepts the search prefixes \v (regex), \w (whole
+                     word), \c/\C/\s (case). Exits 1 if nothing matched.
+    compile [file] [--format <fmt>] [--output <file>]
+                     Assemble every document, in order, into one
+                     manuscript: md (default), html, latex, text, epub,
+                     docx or pdf. Notes are left out.
+
+COMPILE SETTINGS (in .hollow-project):
+    compile:
+      format: pdf                      # Default format
+      output: build/novel.pdf          # Default output file
+      separator: "***"                 # Markdown between documents
+      headings: first-heading          # or file-name, none
+      heading_format: "Chapter {{n}}: {{title}}"
+      notes: [notes/, outline.md]      # Documents to leave out
+
+    A document with `note: true` in its front matter is also left out.
 
 EXAMPLES:
     hollow project init "My Novel"
     hollow project add chapter1.md chapter2.md
     hollow project stats
+    hollow project grep '\v\bAnn(a)?\b'
+    hollow project compile --format epub
     hollow project info path/to/.hollow-project"#
     );
 }
 
 fn print_export_help() {
     println!(
-        r#"hollow export - Export markdown to HTML
+        r#"hollow export - Export markdown to HTML, LaTeX, plain text, EPUB, DOCX or PDF
 
 USAGE:
     hollow export <file> [OPTIONS]
 
 ARGS:
-    <file>    Markdown file to export
+    <file>    Markdown file or project to export
 
 OPTIONS:
     --help, -h           Show this help message
-    --output, -o <file>  Output file (default: <input>.html)
+    --output, -o <file>  Output file (default: <input>.<format extension>)
+    --watch, -w          Export again whenever the file or a project
+                         document changes (Ctrl-C to stop)
     --title, -t <title>  Document title (default: first H1 heading)
+    --author <name>      Author for the {{{{author}}}} placeholder
+    --css <file>         Stylesheet to use instead of the default
+    --template <file>    HTML template to use instead of the default
+    --toc                Put a table of contents at the top of HTML output
+    --smart              Curly quotes, dashes and ellipses in HTML, LaTeX
+                         and text output
+    --format, -f <fmt>   html (default), latex, text, epub, docx or pdf
+    --cover <image>      Cover image for EPUB (jpg, png, gif, svg, webp)
+    --lang <code>        Book language for EPUB (default: spelling language)
+    --preset <name>      DOCX layout: standard (default), manuscript
+                         (Times) or manuscript-courier
+    --page-size <size>   PDF page: letter (default), legal, a4, a5, 6x9,
+                         5x8 or WIDTHxHEIGHT (e.g. 150mmx220mm)
+    --margins <lengths>  PDF margins, one to four lengths in CSS order
+                         (default: 1in)
+    --ragged             Set PDF text ragged right instead of justified
+    --preamble <file>    LaTeX preamble to use instead of the default
+    --width <columns>    Column to wrap plain text at (default: 72, 0 to
+                         keep paragraphs on one line)
+
+Templates, stylesheets and preambles may be paths or names of files in
+~/.config/hollow/templates/ (blog finds blog.html, site finds site.css).
+Defaults can be set in the [export] section of the config.
+
+Every format accepts a single file or a project (its .hollow-project file
+or directory); in EPUB every H1 heading starts a new chapter. PDF export
+needs no external tools; text is set in the bundled DejaVu Serif font.
+
+TEMPLATE PLACEHOLDERS:
+    {{{{title}}}} {{{{author}}}} {{{{date}}}} {{{{word_count}}}} {{{{toc}}}} {{{{body}}}} {{{{css}}}}
 
 EXAMPLES:
     hollow export draft.md
     hollow export draft.md -o published.html
-    hollow export draft.md --title "My Essay""#
+    hollow export draft.md --title "My Essay"
+    hollow export draft.md --template blog --css blog
+    hollow export essay.md --toc --smart
+    hollow export .hollow-project --format pdf --watch
+    hollow export .hollow-project --format epub --cover cover.jpg
+    hollow export novel.md --format docx --preset manuscript --author "Ann Lee"
+    hollow export novel.md --format pdf --page-size 6x9 --margins "0.75in 0.6in"
+    hollow export story.md --format text --width 0"#
+    );
+}
+
+fn print_preview_help() {
+    println!(
+        r#"hollow preview - Live HTML preview in the browser
+
+USAGE:
+    hollow preview <file> [OPTIONS]
+
+ARGS:
+    <file>    Markdown file or project to preview
+
+OPTIONS:
+    --help, -h           Show this help message
+    --port, -p <port>    Port on 127.0.0.1 to serve on (default: {})
+    --title, -t <title>  Document title (default: first H1 heading)
+    --author <name>      Author for the {{{{author}}}} placeholder
+    --css <file>         Stylesheet to use instead of the default
+    --template <file>    HTML template to use instead of the default
+    --toc                Put a table of contents at the top
+    --smart              Curly quotes, dashes and ellipses
+
+Open the printed address in a browser. The page reloads by itself whenever
+the file, or any document of the project, is saved. Images next to the
+document are served too. Nothing is reachable from other machines.
+
+EXAMPLES:
+    hollow preview draft.md
+    hollow preview .hollow-project --port 8080 --toc"#,
+        preview::DEFAULT_PORT
+    );
+}
+
+fn print_import_help() {
+    println!(
+        r#"hollow import - Convert a draft from another tool to Markdown
+
+USAGE:
+    hollow import <file> [OPTIONS]
+
+ARGS:
+    <file>    Word (.docx), HTML (.html, .htm) or plain text (.txt) file
+
+OPTIONS:
+    --help, -h             Show this help message
+    --output, -o <file>    Output file, or - for stdout (default: <file>.md)
+    --quotes, -q <style>   keep (default), straight or curly
+    --force                Overwrite the output file if it exists
+
+Paragraphs, headings, bold and italic, lists, block quotes, scene breaks
+and footnotes are carried over; other formatting is dropped. Hard-wrapped
+plain text is joined back into paragraphs.
+
+EXAMPLES:
+    hollow import chapter-one.docx
+    hollow import notes.txt -o notes.md --quotes curly
+    hollow import post.html -o - | less"#
     );
 }
 
@@ -361,20 +1169,31 @@ fn print_help() {
 USAGE:
     hollow <file> [OPTIONS]
     hollow export <file> [OPTIONS]
+    hollow preview <file> [OPTIONS]
+    hollow import <file> [OPTIONS]
     hollow project <command> [OPTIONS]
+    hollow themes <command>
+    hollow doctor <check>
+    hollow lint <file>...
 
 ARGS:
     <file>    File to edit (created if doesn't exist)
 
 COMMANDS:
-    export    Export markdown to HTML
+    export    Export markdown to HTML, LaTeX, text, EPUB, DOCX or PDF
+    preview   Serve a live-reloading HTML preview on localhost
+    import    Convert a DOCX, HTML or text file to Markdown
     project   Manage writing projects (init, add, stats)
+    themes    List and preview color themes
+    doctor    Check setup (e.g. hollow doctor spell)
+    lint      Check prose style (repeated words, passive voice, ...)
 
 OPTIONS:
     --help, -h          Show this help message
     --version, -v       Show version
     --width <N>         Set text width (default: 80)
     --no-autosave       Disable auto-save
+    --theme <name>      Use a theme for this session
 
 KEY BINDINGS:
     Ctrl+S              Save
@@ -0,0 +1,309 @@
+//! Markdown structure for styling the editing view
+//!
+//! Parses the document with pulldown-cmark and records a style for every byte
+//! of the source. Markers (`#`, `*`, `>`, list bullets, link brackets) are kept
+//! in the text and styled separately so they stay visible and editable.
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+use crate::frontmatter;
+
+/// Markdown styling flags for a single byte of source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MdStyle(u16);
+
+impl MdStyle {
+    pub const HEADING: MdStyle = MdStyle(1);
+    pub const EMPHASIS: MdStyle = MdStyle(1 << 1);
+    pub const STRONG: MdStyle = MdStyle(1 << 2);
+    pub const CODE: MdStyle = MdStyle(1 << 3);
+    pub const LINK: MdStyle = MdStyle(1 << 4);
+    pub const URL: MdStyle = MdStyle(1 << 5);
+    pub const MARKUP: MdStyle = MdStyle(1 << 6);
+    pub const QUOTE: MdStyle = MdStyle(1 << 7);
+    pub const STRIKE: MdStyle = MdStyle(1 << 8);
+
+    /// Check whether all flags in `other` are set
+    pub fn contains(self, other: MdStyle) -> bool {
+        self.0 & other.0 == other.0 && other.0 != 0
+    }
+
+    /// Check whether no flags are set
+    pub fn is_plain(self) -> bool {
+        self.0 == 0
+    }
+
+    fn insert(&mut self, other: MdStyle) {
+        self.0 |= other.0;
+    }
+}
+
+/// Compute the markdown style of every byte in `content`
+pub fn style_map(content: &str) -> Vec<MdStyle> {
+    let mut map = vec![MdStyle::default(); content.len()];
+    let parser = Parser::new_ext(content, Options::all()).into_offset_iter();
+
+    for (event, range) in parser {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                mark(&mut map, range.clone(), MdStyle::HEADING);
+                mark_heading_markers(&mut map, content, range);
+            }
+            Event::Start(Tag::Emphasis) => {
+                mark(&mut map, range.clone(), MdStyle::EMPHASIS);
+                mark_delimiters(&mut map, range, 1);
+            }
+            Event::Start(Tag::Strong) => {
+                mark(&mut map, range.clone(), MdStyle::STRONG);
+                mark_delimiters(&mut map, range, 2);
+            }
+            Event::Start(Tag::Strikethrough) => {
+                mark(&mut map, range.clone(), MdStyle::STRIKE);
+                mark_delimiters(&mut map, range, 2);
+            }
+            Event::Code(_) | Event::Start(Tag::CodeBlock(_)) => {
+                mark(&mut map, range, MdStyle::CODE);
+            }
+            Event::Start(Tag::Link { .. }) | Event::Start(Tag::Image { .. }) => {
+                mark_link(&mut map, content, range);
+            }
+            Event::Start(Tag::BlockQuote) => mark_quote_bars(&mut map, content, range),
+            Event::Start(Tag::Item) => mark_list_marker(&mut map, content, range),
+            Event::Rule
+            | Event::Html(_)
+            | Event::InlineHtml(_)
+            | Event::FootnoteReference(_)
+            | Event::TaskListMarker(_) => mark(&mut map, range, MdStyle::MARKUP),
+            _ => {}
+        }
+    }
+
+    map
+}
+
+/// Byte ranges of prose in a Markdown document, grouped by block (paragraph,
+/// heading, list item, table cell). Prose is text outside code blocks, front
+/// matter and autolinks. Code spans, HTML, link destinations and reference
+/// definitions produce no text events, so they are left out too.
+pub fn prose_blocks(content: &str) -> Vec<Vec<Range<usize>>> {
+    let mut blocks: Vec<Vec<Range<usize>>> = vec![Vec::new()];
+    // One entry per open tag: whether text inside it is skipped
+    let mut skipping: Vec<bool> = Vec::new();
+
+    let start = frontmatter::body_start(content);
+    for (event, range) in Parser::new_ext(&content[start..], Options::all()).into_offset_iter() {
+        let range = range.start + start..range.end + start;
+        match event {
+            Event::Start(tag) => skipping.push(matches!(
+                tag,
+                Tag::CodeBlock(_)
+                    | Tag::MetadataBlock(_)
+                    | Tag::Link { link_type: LinkType::Autolink | LinkType::Email, .. }
+            )),
+            Event::End(tag) => {
+                skipping.pop();
+                let block_end = matches!(
+                    tag,
+                    TagEnd::Paragraph
+                        | TagEnd::Heading(_)
+                        | TagEnd::Item
+                        | TagEnd::TableCell
+                        | TagEnd::FootnoteDefinition
+                );
+                if block_end && blocks.last().is_some_and(|b| !b.is_empty()) {
+                    blocks.push(Vec::new());
+                }
+            }
+            Event::Text(_) if !skipping.contains(&true) => {
+                let block = blocks.last_mut().expect("always one open block");
+                match block.last_mut() {
+                    // Text is split at markup characters; join the pieces back up
+                    Some(last) if last.end == range.start => last.end = range.end,
+                    _ => block.push(range),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    blocks.retain(|b| !b.is_empty());
+    blocks
+}
+
+fn mark(map: &mut [MdStyle], range: Range<usize>, style: MdStyle) {
+    let end = range.end.min(map.len());
+    for s in &mut map[range.start.min(end)..end] {
+        s.insert(style);
+    }
+}
+
+/// Mark `width` delimiter bytes at both ends of an inline span
+fn mark_delimiters(map: &mut [MdStyle], range: Range<usize>, width: usize) {
+    if range.len() < width * 2 {
+        return;
+    }
+    mark(map, range.start..range.start + width, MdStyle::MARKUP);
+    mark(map, range.end - width..range.end, MdStyle::MARKUP);
+}
+
+/// Mark `#` prefixes of ATX headings and `===`/`---` underlines of setext headings
+fn mark_heading_markers(map: &mut [MdStyle], content: &str, range: Range<usize>) {
+    let text = &content[range.clone()];
+    let hashes = text.len() - text.trim_start_matches('#').len();
+    if hashes > 0 {
+        let spaces = text[hashes..].len() - text[hashes..].trim_start().len();
+        mark(map, range.start..range.start + hashes + spaces, MdStyle::MARKUP);
+    } else if let Some(nl) = text.trim_end().rfind('\n') {
+        mark(map, range.start + nl + 1..range.end, MdStyle::MARKUP);
+    }
+}
+
+/// Mark link brackets as markup and the destination as a URL
+fn mark_link(map: &mut [MdStyle], content: &str, range: Range<usize>) {
+    let text = &content[range.clone()];
+    let start = range.start;
+
+    if text.starts_with('<') {
+        // Autolink: <https://example.com>
+        mark(map, range, MdStyle::URL);
+        return;
+    }
+
+    mark(map, range.clone(), MdStyle::LINK);
+    let open = if text.starts_with('!') { 2 } else { 1 };
+    mark(map, start..start + open.min(text.len()), MdStyle::MARKUP);
+
+    // Inline `](url)` or reference `][ref]` destination
+    let dest = if text.ends_with(')') { text.rfind("](") } else { text.rfind("][") };
+    match dest {
+        Some(pos) => {
+            mark(map, start + pos..start + pos + 2, MdStyle::MARKUP);
+            mark(map, start + pos + 2..range.end - 1, MdStyle::URL);
+            mark(map, range.end - 1..range.end, MdStyle::MARKUP);
+        }
+        None if text.ends_with(']') => mark(map, range.end - 1..range.end, MdStyle::MARKUP),
+        None => {}
+    }
+}
+
+/// Mark the leading `>` bars on every line of a block quote
+fn mark_quote_bars(map: &mut [MdStyle], content: &str, range: Range<usize>) {
+    let mut line_start = range.start;
+    for line in content[range.clone()].split_inclusive('\n') {
+        let bar_len = line.len() - line.trim_start_matches(['>', ' ']).len();
+        if line.trim_start().starts_with('>') {
+            mark(map, line_start..line_start + bar_len, MdStyle::QUOTE);
+        }
+        line_start += line.len();
+    }
+}
+
+/// Mark the bullet or number at the start of a list item
+fn mark_list_marker(map: &mut [MdStyle], content: &str, range: Range<usize>) {
+    let text = &content[range.clone()];
+    let marker_len = if text.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 && text[digits..].starts_with(['.', ')']) {
+            digits + 1
+        } else {
+            0
+        }
+    };
+    mark(map, range.start..range.start + marker_len, MdStyle::MARKUP);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style_at(content: &str, needle: &str) -> MdStyle {
+        let map = style_map(content);
+        map[content.find(needle).unwrap()]
+    }
+
+    #[test]
+    fn test_plain_text_unstyled() {
+        let map = style_map("Just some words.");
+        assert!(map.iter().all(|s| s.is_plain()));
+    }
+
+    #[test]
+    fn test_heading_markers() {
+        let content = "## Title\n\nBody";
+        assert!(style_at(content, "#").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "Title").contains(MdStyle::HEADING));
+        assert!(!style_at(content, "Title").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "Body").is_plain());
+    }
+
+    #[test]
+    fn test_setext_heading_underline() {
+        let content = "Title\n=====\n";
+        assert!(style_at(content, "Title").contains(MdStyle::HEADING));
+        assert!(style_at(content, "===").contains(MdStyle::MARKUP));
+    }
+
+    #[test]
+    fn test_emphasis_and_strong() {
+        let content = "a *soft* and **loud** word";
+        assert!(style_at(content, "soft").contains(MdStyle::EMPHASIS));
+        assert!(style_at(content, "*soft").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "loud").contains(MdStyle::STRONG));
+        assert!(style_at(content, "**loud").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "word").is_plain());
+    }
+
+    #[test]
+    fn test_code_span_and_block() {
+        let content = "Use `cargo` here.\n\n```\nfn main() {}\n```\n";
+        assert!(style_at(content, "cargo").contains(MdStyle::CODE));
+        assert!(style_at(content, "fn main").contains(MdStyle::CODE));
+        assert!(style_at(content, "```").contains(MdStyle::CODE));
+        assert!(style_at(content, "here").is_plain());
+    }
+
+    #[test]
+    fn test_link_url() {
+        let content = "See [the docs](https://example.com) now";
+        assert!(style_at(content, "the docs").contains(MdStyle::LINK));
+        assert!(style_at(content, "https").contains(MdStyle::URL));
+        assert!(style_at(content, "](").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "[the").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "now").is_plain());
+    }
+
+    #[test]
+    fn test_quote_bars_and_list_markers() {
+        let content = "> quoted line\n> more\n\n- item one\n12. numbered\n";
+        assert!(style_at(content, "> quoted").contains(MdStyle::QUOTE));
+        assert!(!style_at(content, "quoted").contains(MdStyle::QUOTE));
+        assert!(style_at(content, "> more").contains(MdStyle::QUOTE));
+        assert!(style_at(content, "- item").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "12.").contains(MdStyle::MARKUP));
+        assert!(style_at(content, "item").is_plain());
+    }
+
+    #[test]
+    fn test_prose_blocks() {
+        let content = "# Title\n\nOne line\nand the next.\n\n- item\n\n```\ncode\n```\n";
+        let blocks: Vec<Vec<&str>> = prose_blocks(content)
+            .into_iter()
+            .map(|b| b.into_iter().map(|r| &content[r]).collect())
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![vec!["Title"], vec!["One line", "and the next."], vec!["item"]]
+        );
+    }
+
+    #[test]
+    fn test_prose_blocks_skip_front_matter() {
+        // A blank line in the YAML still counts as front matter
+        let content = "---\ntitle: Storm\n\ntags: [draft]\n---\nRain fell.\n";
+        let prose: Vec<&str> = prose_blocks(content).into_iter().flatten().map(|r| &content[r]).collect();
+        assert_eq!(prose, vec!["Rain fell."]);
+    }
+}
@@ -0,0 +1,1101 @@
+//! Export markdown documents to PDF.
+//!
+//! A small layout engine sets the text itself, so no pandoc or LaTeX is
+//! needed: paragraphs are broken greedily and justified (or set ragged
+//! right), headings are kept with the text that follows, and single lines
+//! are not left stranded at the top or bottom of a page. Text is set in
+//! DejaVu Serif, which is embedded as a subset.
+
+use chrono::Utc;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use crate::export::{self, ExportOptions, Exporter, Metadata, SourceDocument};
+use crate::ttf::Font;
+
+const FONT_DATA: [&[u8]; 4] = [
+    include_bytes!("../fonts/DejaVuSerif.ttf"),
+    include_bytes!("../fonts/DejaVuSerif-Italic.ttf"),
+    include_bytes!("../fonts/DejaVuSerif-Bold.ttf"),
+    include_bytes!("../fonts/DejaVuSerif-BoldItalic.ttf"),
+];
+const FONT_NAMES: [&str; 4] = ["DejaVuSerif", "DejaVuSerif-Italic", "DejaVuSerif-Bold", "DejaVuSerif-BoldItalic"];
+
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.0;
+const FOLIO_SIZE: f32 = 9.0;
+/// Line height as a multiple of the type size
+const LEADING: f32 = 1.4;
+/// Indent of each block quote or list level
+const NEST_INDENT: f32 = 24.0;
+/// Justified word gaps wider than this many spaces are set ragged instead
+const MAX_GAP: f32 = 3.0;
+
+/// Named page sizes in points
+const PAGE_SIZES: &[(&str, f32, f32)] = &[
+    ("letter", 612.0, 792.0),
+    ("legal", 612.0, 1008.0),
+    ("a4", 595.28, 841.89),
+    ("a5", 419.53, 595.28),
+    ("6x9", 432.0, 648.0),
+    ("5x8", 360.0, 576.0),
+];
+
+/// Parse a length such as "1in", "2.5cm", "20mm" or "72pt" (bare numbers are points)
+pub fn parse_length(spec: &str) -> Option<f32> {
+    let spec = spec.trim().to_lowercase();
+    let (number, scale) = if let Some(n) = spec.strip_suffix("pt") {
+        (n, 1.0)
+    } else if let Some(n) = spec.strip_suffix("in") {
+        (n, 72.0)
+    } else if let Some(n) = spec.strip_suffix("mm") {
+        (n, 72.0 / 25.4)
+    } else if let Some(n) = spec.strip_suffix("cm") {
+        (n, 72.0 / 2.54)
+    } else {
+        (spec.as_str(), 1.0)
+    };
+    let value: f32 = number.trim().parse().ok()?;
+    (value >= 0.0 && value.is_finite()).then_some(value * scale)
+}
+
+/// Parse a page size: a name (letter, legal, a4, a5, 6x9, 5x8) or WIDTHxHEIGHT
+pub fn parse_page_size(spec: &str) -> Option<(f32, f32)> {
+    let spec = spec.trim().to_lowercase();
+    if let Some((_, width, height)) = PAGE_SIZES.iter().find(|(name, _, _)| *name == spec) {
+        return Some((*width, *height));
+    }
+    let (width, height) = spec.split_once('x')?;
+    let (width, height) = (parse_length(width)?, parse_length(height)?);
+    (width > 0.0 && height > 0.0).then_some((width, height))
+}
+
+/// Parse margins given CSS-style as one to four lengths: top, right, bottom, left
+pub fn parse_margins(spec: &str) -> Option<[f32; 4]> {
+    let values: Vec<f32> = spec
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(parse_length)
+        .collect::<Option<_>>()?;
+    match values[..] {
+        [all] => Some([all; 4]),
+        [vertical, horizontal] => Some([vertical, horizontal, vertical, horizontal]),
+        [top, horizontal, bottom] => Some([top, horizontal, bottom, horizontal]),
+        [top, right, bottom, left] => Some([top, right, bottom, left]),
+        _ => None,
+    }
+}
+
+/// Page size and text area
+struct PageGeometry {
+    width: f32,
+    height: f32,
+    /// top, right, bottom, left
+    margins: [f32; 4],
+}
+
+impl PageGeometry {
+    fn from_options(options: &ExportOptions) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let (width, height) = match options.page_size.as_deref() {
+            None => (612.0, 792.0),
+            Some(spec) => parse_page_size(spec).ok_or_else(|| {
+                invalid(format!("unknown page size: {} (expected letter, legal, a4, a5, 6x9, 5x8 or WIDTHxHEIGHT)", spec))
+            })?,
+        };
+        let margins = match options.margins.as_deref() {
+            None => [72.0; 4],
+            Some(spec) => parse_margins(spec).ok_or_else(|| invalid(format!("invalid margins: {}", spec)))?,
+        };
+        // Leave room for at least a couple of words and lines
+        if width - margins[1] - margins[3] < 72.0 || height - margins[0] - margins[2] < 72.0 {
+            return Err(invalid("margins leave no room for text".to_string()));
+        }
+        Ok(Self { width, height, margins })
+    }
+
+    fn text_width(&self) -> f32 {
+        self.width - self.margins[1] - self.margins[3]
+    }
+}
+
+/// Typeface style; also the index into the embedded fonts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Regular,
+    Italic,
+    Bold,
+    BoldItalic,
+}
+
+impl Style {
+    fn new(italic: bool, bold: bool) -> Self {
+        match (italic, bold) {
+            (false, false) => Style::Regular,
+            (true, false) => Style::Italic,
+            (false, true) => Style::Bold,
+            (true, true) => Style::BoldItalic,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The embedded typefaces
+struct Fonts {
+    faces: Vec<Font<'static>>,
+}
+
+impl Fonts {
+    fn load() -> io::Result<Self> {
+        let faces = FONT_DATA
+            .iter()
+            .map(|data| Font::parse(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())))
+            .collect::<io::Result<_>>()?;
+        Ok(Self { faces })
+    }
+
+    fn width(&self, style: Style, size: f32, text: &str) -> f32 {
+        let font = &self.faces[style.index()];
+        let units: u32 = text.chars().map(|c| font.advance(font.glyph_id(c)) as u32).sum();
+        units as f32 * size / font.units_per_em as f32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockKind {
+    Paragraph,
+    Heading(u8),
+    Quote,
+    ListItem,
+    SceneBreak,
+    Code,
+    PageBreak,
+}
+
+/// A paragraph-level piece of the document
+#[derive(Debug)]
+struct Block {
+    kind: BlockKind,
+    /// Styled runs of text
+    pieces: Vec<(Style, String)>,
+    /// Left indent from quotes and lists
+    indent: f32,
+    /// Bullet or number hung to the left of a list item's first line
+    marker: Option<String>,
+}
+
+/// Turns the markdown event stream into blocks
+struct BlockBuilder {
+    blocks: Vec<Block>,
+    current: Option<Block>,
+    italic: usize,
+    bold: usize,
+    heading: Option<u8>,
+    quote_depth: usize,
+    /// Numbering of the open lists: `Some(next)` for ordered lists
+    lists: Vec<Option<u64>>,
+    item_marker: Option<String>,
+    /// Footnote label waiting to start the note's first paragraph
+    note_label: Option<String>,
+    in_code_block: bool,
+}
+
+impl BlockBuilder {
+    fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            current: None,
+            italic: 0,
+            bold: 0,
+            heading: None,
+            quote_depth: 0,
+            lists: Vec::new(),
+            item_marker: None,
+            note_label: None,
+            in_code_block: false,
+        }
+    }
+
+    fn indent(&self) -> f32 {
+        (self.quote_depth + self.lists.len()) as f32 * NEST_INDENT
+    }
+
+    fn open(&mut self, kind: BlockKind) {
+        self.close();
+        let marker = if kind == BlockKind::ListItem { self.item_marker.take() } else { None };
+        let pieces = self.note_label.take().map(|label| (Style::Regular, label)).into_iter().collect();
+        self.current = Some(Block { kind, pieces, indent: self.indent(), marker });
+    }
+
+    fn open_in_container(&mut self) {
+        let kind = if !self.lists.is_empty() {
+            BlockKind::ListItem
+        } else if self.quote_depth > 0 {
+            BlockKind::Quote
+        } else {
+            BlockKind::Paragraph
+        };
+        self.open(kind);
+    }
+
+    fn close(&mut self) {
+        if let Some(block) = self.current.take() {
+            self.blocks.push(block);
+        }
+    }
+
+    fn push_block(&mut self, kind: BlockKind, text: &str) {
+        self.close();
+        self.blocks.push(Block {
+            kind,
+            pieces: vec![(Style::Regular, text.to_string())],
+            indent: self.indent(),
+            marker: None,
+        });
+    }
+
+    fn style(&self) -> Style {
+        match self.heading {
+            Some(level) => Style::new(level >= 3 || self.italic > 0, true),
+            None => Style::new(self.italic > 0, self.bold > 0),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.current.is_none() {
+            self.open_in_container();
+        }
+        let style = self.style();
+        if let Some(block) = &mut self.current {
+            block.pieces.push((style, text.to_string()));
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.in_code_block => {
+                for line in text.lines() {
+                    self.push_block(BlockKind::Code, line);
+                }
+            }
+            Event::Text(text) | Event::Code(text) => self.text(&text),
+            Event::SoftBreak | Event::HardBreak => self.text(" "),
+            Event::Rule => self.push_block(BlockKind::SceneBreak, "*\u{2003}*\u{2003}*"),
+            Event::FootnoteReference(label) => self.text(&format!("[{}]", label)),
+            Event::TaskListMarker(done) => self.text(if done { "[x] " } else { "[ ] " }),
+            Event::Html(_) | Event::InlineHtml(_) => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.open_in_container(),
+            Tag::Heading { level, .. } => {
+                let level = match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    _ => 3,
+                };
+                self.heading = Some(level);
+                self.open(BlockKind::Heading(level));
+            }
+            Tag::BlockQuote => {
+                self.close();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.close();
+                self.in_code_block = true;
+            }
+            Tag::List(start) => {
+                self.close();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.close();
+                self.item_marker = Some(match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "\u{2022}".to_string(),
+                });
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.close();
+                self.note_label = Some(format!("{}. ", label));
+            }
+            Tag::TableHead | Tag::TableRow => self.open(BlockKind::Paragraph),
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Image { .. } => self.text("[Image: "),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Item | TagEnd::FootnoteDefinition => self.close(),
+            TagEnd::TableHead | TagEnd::TableRow => self.close(),
+            TagEnd::TableCell => self.text("   "),
+            TagEnd::Heading(_) => {
+                self.close();
+                self.heading = None;
+            }
+            TagEnd::BlockQuote => {
+                self.close();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => self.in_code_block = false,
+            TagEnd::List(_) => {
+                self.close();
+                self.lists.pop();
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Image => self.text("]"),
+            _ => {}
+        }
+    }
+}
+
+/// Collect the blocks of all documents; each document starts a new page
+fn collect_blocks(documents: &[SourceDocument]) -> Vec<Block> {
+    let mut builder = BlockBuilder::new();
+    for (i, doc) in documents.iter().enumerate() {
+        if i > 0 {
+            builder.push_block(BlockKind::PageBreak, "");
+        }
+        for event in Parser::new_ext(&doc.markdown, Options::all()) {
+            builder.event(event);
+        }
+        builder.close();
+    }
+    builder.blocks
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Justify,
+    Left,
+    Center,
+}
+
+/// A word: styled segments with their widths
+struct Word {
+    segments: Vec<(Style, String, f32)>,
+    width: f32,
+}
+
+/// A set line: text items at offsets from the left edge of the block
+#[derive(Debug, Default)]
+struct Line {
+    items: Vec<(f32, Style, String)>,
+}
+
+/// Text placed on a page
+#[derive(Debug)]
+struct TextItem {
+    x: f32,
+    y: f32,
+    style: Style,
+    size: f32,
+    text: String,
+}
+
+/// Split styled runs into words, keeping style changes inside a word
+fn split_words(pieces: &[(Style, String)], fonts: &Fonts, size: f32) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut current: Vec<(Style, String)> = Vec::new();
+    let mut finish = |current: &mut Vec<(Style, String)>| {
+        if current.is_empty() {
+            return;
+        }
+        let segments: Vec<(Style, String, f32)> = current
+            .drain(..)
+            .map(|(style, text)| {
+                let width = fonts.width(style, size, &text);
+                (style, text, width)
+            })
+            .collect();
+        let width = segments.iter().map(|(_, _, w)| w).sum();
+        words.push(Word { segments, width });
+    };
+
+    for (style, text) in pieces {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                finish(&mut current);
+            } else {
+                match current.last_mut() {
+                    Some((last, segment)) if last == style => segment.push(c),
+                    _ => current.push((*style, c.to_string())),
+                }
+            }
+        }
+    }
+    finish(&mut current);
+    words
+}
+
+/// Break words into lines no wider than `width`, the first line narrowed by `first_indent`
+fn set_lines(words: &[Word], fonts: &Fonts, size: f32, width: f32, first_indent: f32, align: Align) -> Vec<Line> {
+    let space = fonts.width(Style::Regular, size, " ");
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let indent = if lines.is_empty() { first_indent } else { 0.0 };
+        let available = width - indent;
+        let mut end = start + 1;
+        let mut natural = words[start].width;
+        while end < words.len() && natural + space + words[end].width <= available {
+            natural += space + words[end].width;
+            end += 1;
+        }
+
+        let gaps = (end - start - 1) as f32;
+        let last = end == words.len();
+        let mut gap = space;
+        let mut x = indent;
+        match align {
+            Align::Justify if !last && gaps > 0.0 => {
+                let justified = space + (available - natural) / gaps;
+                if justified <= space * MAX_GAP {
+                    gap = justified;
+                }
+            }
+            Align::Center => x += ((available - natural) / 2.0).max(0.0),
+            _ => {}
+        }
+
+        let mut line = Line::default();
+        for word in &words[start..end] {
+            for (style, text, width) in &word.segments {
+                line.items.push((x, *style, text.clone()));
+                x += width;
+            }
+            x += gap;
+        }
+        lines.push(line);
+        start = end;
+    }
+    lines
+}
+
+/// Break a code line by characters, keeping its spacing
+fn set_code_line(text: &str, fonts: &Fonts, width: f32) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut used = 0.0;
+    for c in text.chars() {
+        let advance = fonts.width(Style::Regular, CODE_SIZE, c.encode_utf8(&mut [0; 4]));
+        if used + advance > width && !current.is_empty() {
+            lines.push(Line { items: vec![(0.0, Style::Regular, std::mem::take(&mut current))] });
+            used = 0.0;
+        }
+        current.push(c);
+        used += advance;
+    }
+    lines.push(Line { items: vec![(0.0, Style::Regular, current)] });
+    lines
+}
+
+/// Places lines onto pages
+struct Layout<'a> {
+    fonts: &'a Fonts,
+    geometry: &'a PageGeometry,
+    align: Align,
+    pages: Vec<Vec<TextItem>>,
+    /// Top of the next line
+    y: f32,
+    /// Nothing has been placed on the current page yet
+    page_empty: bool,
+}
+
+impl<'a> Layout<'a> {
+    fn new(fonts: &'a Fonts, geometry: &'a PageGeometry, ragged_right: bool) -> Self {
+        Self {
+            fonts,
+            geometry,
+            align: if ragged_right { Align::Left } else { Align::Justify },
+            pages: vec![Vec::new()],
+            y: geometry.height - geometry.margins[0],
+            page_empty: true,
+        }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.y = self.geometry.height - self.geometry.margins[0];
+        self.page_empty = true;
+    }
+
+    /// Vertical space; dropped at the top of a page
+    fn space(&mut self, points: f32) {
+        if !self.page_empty {
+            self.y -= points;
+        }
+    }
+
+    fn room(&self) -> f32 {
+        self.y - self.geometry.margins[2]
+    }
+
+    /// Place lines, moving to a new page where needed. Unless `split` is
+    /// false, a paragraph may break across pages, but never leaving a single
+    /// line at the bottom (orphan) or top (widow) of a page.
+    fn place(&mut self, mut lines: &[Line], size: f32, x: f32, split: bool) {
+        let leading = size * LEADING;
+        while !lines.is_empty() {
+            let fits = (self.room() / leading + 0.001).floor().max(0.0) as usize;
+            let mut count = lines.len().min(fits);
+            if count < lines.len() {
+                if lines.len() - count == 1 && count > 0 {
+                    count -= 1;
+                }
+                if !split || count == 1 {
+                    count = 0;
+                }
+                // A page that cannot hold even one line takes it anyway
+                if count == 0 && self.page_empty {
+                    count = fits.max(1).min(lines.len());
+                }
+            }
+            for line in &lines[..count] {
+                let baseline = self.y - size;
+                let page = self.pages.last_mut().expect("layout always has a page");
+                for (offset, style, text) in &line.items {
+                    page.push(TextItem { x: x + offset, y: baseline, style: *style, size, text: text.clone() });
+                }
+                self.y -= leading;
+                self.page_empty = false;
+            }
+            lines = &lines[count..];
+            if !lines.is_empty() {
+                self.new_page();
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block, indent_first: bool) {
+        let left = self.geometry.margins[3] + block.indent;
+        let width = self.geometry.text_width() - block.indent;
+        let body_leading = BODY_SIZE * LEADING;
+        match block.kind {
+            BlockKind::PageBreak => {
+                if !self.page_empty {
+                    self.new_page();
+                }
+            }
+            BlockKind::Heading(level) => {
+                let (size, before, after) = match level {
+                    1 => (20.0, 0.0, 18.0),
+                    2 => (15.0, 18.0, 8.0),
+                    _ => (12.0, 12.0, 4.0),
+                };
+                // Chapters start on a new page, a little way down
+                if level == 1 {
+                    if !self.page_empty {
+                        self.new_page();
+                    }
+                    self.y -= 48.0;
+                }
+                self.space(before);
+                let words = split_words(&block.pieces, self.fonts, size);
+                let lines = set_lines(&words, self.fonts, size, width, 0.0, Align::Left);
+                // Keep the heading with the first two lines of what follows
+                let needed = lines.len() as f32 * size * LEADING + after + 2.0 * body_leading;
+                if needed > self.room() && !self.page_empty {
+                    self.new_page();
+                }
+                self.place(&lines, size, left, false);
+                self.y -= after;
+            }
+            BlockKind::SceneBreak => {
+                self.space(body_leading / 2.0);
+                let words = split_words(&block.pieces, self.fonts, BODY_SIZE);
+                let lines = set_lines(&words, self.fonts, BODY_SIZE, width, 0.0, Align::Center);
+                self.place(&lines, BODY_SIZE, left, false);
+                self.y -= body_leading / 2.0;
+            }
+            BlockKind::Code => {
+                let text = block.pieces.iter().map(|(_, text)| text.as_str()).collect::<String>();
+                let lines = set_code_line(&text, self.fonts, width - NEST_INDENT);
+                self.place(&lines, CODE_SIZE, left + NEST_INDENT, true);
+            }
+            BlockKind::Paragraph | BlockKind::Quote | BlockKind::ListItem => {
+                let words = split_words(&block.pieces, self.fonts, BODY_SIZE);
+                if words.is_empty() && block.marker.is_none() {
+                    return;
+                }
+                let (first_indent, right, before, after) = match block.kind {
+                    BlockKind::Paragraph if indent_first => (BODY_SIZE * 1.5, 0.0, 0.0, 0.0),
+                    BlockKind::Paragraph => (0.0, 0.0, body_leading / 2.0, 0.0),
+                    BlockKind::Quote => (0.0, NEST_INDENT, body_leading / 4.0, body_leading / 4.0),
+                    _ => (0.0, 0.0, 2.0, 0.0),
+                };
+                let mut lines = set_lines(&words, self.fonts, BODY_SIZE, width - right, first_indent, self.align);
+                if let Some(marker) = &block.marker {
+                    let hang = self.fonts.width(Style::Regular, BODY_SIZE, marker) + BODY_SIZE / 2.0;
+                    if lines.is_empty() {
+                        lines.push(Line::default());
+                    }
+                    lines[0].items.insert(0, (-hang, Style::Regular, marker.clone()));
+                }
+                self.space(before);
+                self.place(&lines, BODY_SIZE, left, true);
+                self.y -= after;
+            }
+        }
+    }
+
+    /// Lay out all blocks and return the pages
+    fn run(mut self, blocks: &[Block]) -> Vec<Vec<TextItem>> {
+        // Paragraphs that follow a paragraph are indented instead of spaced
+        let mut previous: Option<BlockKind> = None;
+        for block in blocks {
+            let indent_first = previous == Some(BlockKind::Paragraph);
+            self.block(block, indent_first);
+            previous = Some(block.kind);
+        }
+        self.pages
+    }
+}
+
+/// Glyphs used from each font, with the character each one shows
+#[derive(Default)]
+struct GlyphUse {
+    glyphs: [BTreeMap<u16, char>; 4],
+}
+
+/// Write a page's content stream
+fn page_content(items: &[TextItem], fonts: &Fonts, used: &mut GlyphUse) -> Vec<u8> {
+    let mut content = String::new();
+    for item in items {
+        let font = &fonts.faces[item.style.index()];
+        let mut hex = String::with_capacity(item.text.len() * 4);
+        for c in item.text.chars() {
+            let glyph = font.glyph_id(c);
+            used.glyphs[item.style.index()].entry(glyph).or_insert(c);
+            hex.push_str(&format!("{:04X}", glyph));
+        }
+        content.push_str(&format!(
+            "BT /F{} {} Tf 1 0 0 1 {} {} Tm <{}> Tj ET\n",
+            item.style.index(),
+            number(item.size),
+            number(item.x),
+            number(item.y),
+            hex
+        ));
+    }
+    content.into_bytes()
+}
+
+/// Format a number with at most two decimals
+fn number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+/// A PDF text string in UTF-16BE, which readers accept for any text
+fn text_string(text: &str) -> String {
+    let mut hex = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        hex.push_str(&format!("{:04X}", unit));
+    }
+    hex.push('>');
+    hex
+}
+
+fn deflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Writes numbered objects and the cross-reference table
+struct PdfWriter {
+    data: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        let mut data = b"%PDF-1.7\n".to_vec();
+        // Binary marker so transfer tools treat the file as binary
+        data.extend_from_slice(b"%\xE2\xE3\xCF\xD3\n");
+        Self { data, offsets: Vec::new() }
+    }
+
+    /// Reserve an object number
+    fn reserve(&mut self) -> usize {
+        self.offsets.push(0);
+        self.offsets.len()
+    }
+
+    fn object(&mut self, id: usize, body: &str) {
+        self.offsets[id - 1] = self.data.len();
+        self.data.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", id, body).as_bytes());
+    }
+
+    fn stream(&mut self, id: usize, dict: &str, contents: &[u8]) {
+        self.offsets[id - 1] = self.data.len();
+        self.data.extend_from_slice(format!("{} 0 obj\n<< {} /Length {} >>\nstream\n", id, dict, contents.len()).as_bytes());
+        self.data.extend_from_slice(contents);
+        self.data.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    fn finish(mut self, root: usize, info: usize) -> Vec<u8> {
+        let xref = self.data.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            table.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        table.push_str(&format!(
+            "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            root,
+            info,
+            xref
+        ));
+        self.data.extend_from_slice(table.as_bytes());
+        self.data
+    }
+}
+
+/// Embed one font as a Type 0 font with Identity-H encoding, so text is
+/// written as glyph ids. Returns the font dictionary's object number.
+fn write_font(pdf: &mut PdfWriter, style: Style, fonts: &Fonts, glyphs: &BTreeMap<u16, char>) -> io::Result<usize> {
+    let font = &fonts.faces[style.index()];
+    let scale = |units: i32| units * 1000 / font.units_per_em as i32;
+
+    let ids: BTreeSet<u16> = glyphs.keys().copied().collect();
+    let subset = font
+        .subset(&ids)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    // Subset fonts are named with a six letter tag
+    let seed = ids.iter().fold(style.index() as u32 + 1, |h, &g| h.wrapping_mul(31).wrapping_add(g as u32));
+    let tag: String = (0..6).map(|i| (b'A' + ((seed >> (i * 5)) % 26) as u8) as char).collect();
+    let name = format!("{}+{}", tag, FONT_NAMES[style.index()]);
+
+    let type0 = pdf.reserve();
+    let cid_font = pdf.reserve();
+    let descriptor = pdf.reserve();
+    let font_file = pdf.reserve();
+    let to_unicode = pdf.reserve();
+
+    pdf.object(
+        type0,
+        &format!(
+            "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+            name, cid_font, to_unicode
+        ),
+    );
+
+    let widths: String = glyphs
+        .keys()
+        .map(|&g| format!("{} [{}]", g, scale(font.advance(g) as i32)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    pdf.object(
+        cid_font,
+        &format!(
+            "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+             /FontDescriptor {} 0 R /DW 1000 /W [{}] /CIDToGIDMap /Identity >>",
+            name, descriptor, widths
+        ),
+    );
+
+    // Serif and nonsymbolic, plus italic
+    let flags = if matches!(style, Style::Italic | Style::BoldItalic) { 2 | 32 | 64 } else { 2 | 32 };
+    let [x_min, y_min, x_max, y_max] = font.bbox.map(|v| scale(v as i32));
+    pdf.object(
+        descriptor,
+        &format!(
+            "<< /Type /FontDescriptor /FontName /{} /Flags {} /FontBBox [{} {} {} {}] /ItalicAngle {} \
+             /Ascent {} /Descent {} /CapHeight {} /StemV {} /FontFile2 {} 0 R >>",
+            name,
+            flags,
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+            number(font.italic_angle),
+            scale(font.ascent as i32),
+            scale(font.descent as i32),
+            scale(font.ascent as i32),
+            if matches!(style, Style::Bold | Style::BoldItalic) { 140 } else { 80 },
+            font_file
+        ),
+    );
+
+    let compressed = deflate(&subset)?;
+    pdf.stream(font_file, &format!("/Filter /FlateDecode /Length1 {}", subset.len()), &compressed);
+
+    let cmap = to_unicode_cmap(glyphs);
+    pdf.stream(to_unicode, "/Filter /FlateDecode", &deflate(cmap.as_bytes())?);
+    Ok(type0)
+}
+
+/// CMap mapping glyph ids back to text, for copying and searching
+fn to_unicode_cmap(glyphs: &BTreeMap<u16, char>) -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let entries: Vec<_> = glyphs.iter().filter(|(&g, _)| g != 0).collect();
+    // At most 100 mappings per section
+    for chunk in entries.chunks(100) {
+        cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+        for (glyph, c) in chunk {
+            let unicode: String = c.encode_utf16(&mut [0; 2]).iter().map(|u| format!("{:04X}", u)).collect();
+            cmap.push_str(&format!("<{:04X}> <{}>\n", glyph, unicode));
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+/// PDF laid out by hollow itself.
+pub struct PdfExporter<'a> {
+    options: &'a ExportOptions,
+}
+
+impl<'a> PdfExporter<'a> {
+    pub fn new(options: &'a ExportOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Exporter for PdfExporter<'_> {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn extension(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn export(&self, documents: &[SourceDocument], _metadata: &Metadata) -> io::Result<Vec<u8>> {
+        build_pdf(documents, self.options)
+    }
+}
+
+/// Lay out documents and build a PDF in memory.
+pub fn build_pdf(documents: &[SourceDocument], options: &ExportOptions) -> io::Result<Vec<u8>> {
+    let geometry = PageGeometry::from_options(options)?;
+    let fonts = Fonts::load()?;
+    let blocks = collect_blocks(documents);
+    let mut pages = Layout::new(&fonts, &geometry, options.ragged_right).run(&blocks);
+
+    // Page numbers, centered in the bottom margin
+    for (i, page) in pages.iter_mut().enumerate() {
+        let folio = (i + 1).to_string();
+        let width = fonts.width(Style::Regular, FOLIO_SIZE, &folio);
+        page.push(TextItem {
+            x: (geometry.width - width) / 2.0,
+            y: geometry.margins[2] / 2.0,
+            style: Style::Regular,
+            size: FOLIO_SIZE,
+            text: folio,
+        });
+    }
+
+    let mut used = GlyphUse::default();
+    let contents: Vec<Vec<u8>> = pages.iter().map(|page| page_content(page, &fonts, &mut used)).collect();
+
+    let mut pdf = PdfWriter::new();
+    let catalog = pdf.reserve();
+    let page_tree = pdf.reserve();
+    let info = pdf.reserve();
+    let resources = pdf.reserve();
+
+    let mut font_refs = String::new();
+    for style in [Style::Regular, Style::Italic, Style::Bold, Style::BoldItalic] {
+        let glyphs = &used.glyphs[style.index()];
+        if !glyphs.is_empty() {
+            let id = write_font(&mut pdf, style, &fonts, glyphs)?;
+            font_refs.push_str(&format!("/F{} {} 0 R ", style.index(), id));
+        }
+    }
+    pdf.object(resources, &format!("<< /Font << {}>> >>", font_refs));
+
+    let mut kids = Vec::new();
+    for content in &contents {
+        let page = pdf.reserve();
+        let stream = pdf.reserve();
+        pdf.object(
+            page,
+            &format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources {} 0 R /Contents {} 0 R >>",
+                page_tree,
+                number(geometry.width),
+                number(geometry.height),
+                resources,
+                stream
+            ),
+        );
+        pdf.stream(stream, "/Filter /FlateDecode", &deflate(content)?);
+        kids.push(format!("{} 0 R", page));
+    }
+    pdf.object(
+        page_tree,
+        &format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), kids.len()),
+    );
+
+    let language = options
+        .language
+        .as_deref()
+        .map(|lang| format!(" /Lang {}", text_string(lang)))
+        .unwrap_or_default();
+    pdf.object(catalog, &format!("<< /Type /Catalog /Pages {} 0 R{} >>", page_tree, language));
+
+    let mut metadata = format!(
+        "/Producer {} /CreationDate (D:{}Z)",
+        text_string("hollow"),
+        Utc::now().format("%Y%m%d%H%M%S")
+    );
+    let title = options.title.clone().or_else(|| documents.first().and_then(|doc| export::extract_title(&doc.markdown)));
+    if let Some(title) = title {
+        metadata.push_str(&format!(" /Title {}", text_string(&title)));
+    }
+    if let Some(author) = &options.author {
+        metadata.push_str(&format!(" /Author {}", text_string(author)));
+    }
+    pdf.object(info, &format!("<< {} >>", metadata));
+
+    Ok(pdf.finish(catalog, info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(markdown: &str) -> Vec<SourceDocument> {
+        vec![SourceDocument::from_markdown("draft", markdown)]
+    }
+
+    #[test]
+    fn test_page_size_and_margins() {
+        assert_eq!(parse_page_size("Letter"), Some((612.0, 792.0)));
+        assert_eq!(parse_page_size("6x9"), Some((432.0, 648.0)));
+        let (w, h) = parse_page_size("210mmx297mm").unwrap();
+        assert!((w - 595.28).abs() < 0.01 && (h - 841.89).abs() < 0.01);
+        assert_eq!(parse_page_size("big"), None);
+
+        assert_eq!(parse_length("0.5in"), Some(36.0));
+        assert_eq!(parse_length("-1in"), None);
+        assert_eq!(parse_margins("1in"), Some([72.0; 4]));
+        assert_eq!(parse_margins("1in 0.5in"), Some([72.0, 36.0, 72.0, 36.0]));
+        assert_eq!(parse_margins("72, 36, 54, 18"), Some([72.0, 36.0, 54.0, 18.0]));
+        assert_eq!(parse_margins("1in 2in 3in 4in 5in"), None);
+
+        let options = ExportOptions { margins: Some("4in".to_string()), ..Default::default() };
+        assert!(PageGeometry::from_options(&options).is_err());
+    }
+
+    #[test]
+    fn test_blocks_from_markdown() {
+        let blocks = collect_blocks(&doc("# Title\n\nShe *ran*.\n\n***\n\n> Quoted\n\n1. first\n2. second\n"));
+        let kinds: Vec<BlockKind> = blocks.iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            [BlockKind::Heading(1), BlockKind::Paragraph, BlockKind::SceneBreak, BlockKind::Quote, BlockKind::ListItem, BlockKind::ListItem]
+        );
+        assert_eq!(blocks[0].pieces, [(Style::Bold, "Title".to_string())]);
+        assert_eq!(blocks[1].pieces[1], (Style::Italic, "ran".to_string()));
+        assert_eq!(blocks[3].indent, NEST_INDENT);
+        assert_eq!(blocks[5].marker.as_deref(), Some("2."));
+
+        // A footnote's label starts the block holding its text
+        let blocks = collect_blocks(&doc("Text.[^1]\n\n[^1]: The *note*.\n"));
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].pieces[0], (Style::Regular, "1. ".to_string()));
+        assert_eq!(blocks[1].pieces[1], (Style::Regular, "The ".to_string()));
+    }
+
+    #[test]
+    fn test_justified_lines_fill_the_measure() {
+        let fonts = Fonts::load().unwrap();
+        let text = "The quick brown fox jumps over the lazy dog and keeps on running far away. ".repeat(6);
+        let words = split_words(&[(Style::Regular, text)], &fonts, BODY_SIZE);
+        let right_edge = |line: &Line| {
+            let (x, style, text) = line.items.last().unwrap();
+            x + fonts.width(*style, BODY_SIZE, text)
+        };
+
+        let lines = set_lines(&words, &fonts, BODY_SIZE, 300.0, 16.0, Align::Justify);
+        assert!(lines.len() > 3);
+        assert_eq!(lines[0].items[0].0, 16.0);
+        for line in &lines[..lines.len() - 1] {
+            assert!((right_edge(line) - 300.0).abs() < 0.01);
+        }
+        assert!(right_edge(lines.last().unwrap()) < 300.0);
+
+        let ragged = set_lines(&words, &fonts, BODY_SIZE, 300.0, 0.0, Align::Left);
+        assert!(ragged.iter().all(|line| right_edge(line) <= 300.0));
+        assert!(ragged.iter().any(|line| right_edge(line) < 290.0));
+    }
+
+    #[test]
+    fn test_paragraphs_avoid_widows_and_orphans() {
+        let fonts = Fonts::load().unwrap();
+        let geometry = PageGeometry { width: 300.0, height: 300.0, margins: [50.0; 4] };
+        let leading = BODY_SIZE * LEADING;
+        let mut layout = Layout::new(&fonts, &geometry, false);
+        let line = || Line { items: vec![(0.0, Style::Regular, "line".to_string())] };
+
+        // Leave room for exactly two lines, then place a three line paragraph
+        let fits = (200.0 / leading) as usize;
+        layout.place(&(0..fits - 2).map(|_| line()).collect::<Vec<_>>(), BODY_SIZE, 50.0, true);
+        layout.place(&[line(), line(), line()], BODY_SIZE, 50.0, true);
+        // Splitting would leave a widow or an orphan, so the paragraph moves
+        assert_eq!(layout.pages.len(), 2);
+        assert_eq!(layout.pages[1].len(), 3);
+    }
+
+    #[test]
+    fn test_build_pdf_structure() {
+        let text = "A paragraph of plain words that runs on for a while. ".repeat(40);
+        let markdown = format!("# Chapter One\n\n{}\n\n{}\n\n# Chapter Two\n\n### Étude\n\nIn *italic* and **bold**.\n", text, text);
+        let options = ExportOptions {
+            title: Some("Book".to_string()),
+            page_size: Some("a5".to_string()),
+            ..Default::default()
+        };
+        let data = build_pdf(&doc(&markdown), &options).unwrap();
+        assert!(data.starts_with(b"%PDF-1.7"));
+        assert!(data.ends_with(b"%%EOF\n"));
+
+        // Every xref entry points at its object
+        let text = String::from_utf8_lossy(&data);
+        let tail = String::from_utf8_lossy(&data[data.len() - 40..]);
+        let startxref: usize = tail.split("startxref\n").nth(1).unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(data[startxref..].starts_with(b"xref\n"));
+        let xref = String::from_utf8_lossy(&data[startxref..]);
+        for (id, entry) in xref.lines().skip(3).take_while(|l| l.ends_with(" n ")).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(data[offset..].starts_with(format!("{} 0 obj", id + 1).as_bytes()));
+        }
+
+        assert!(text.contains("/MediaBox [0 0 419.53 595.28]"));
+        let count: usize = text.split("/Count ").nth(1).unwrap().split(' ').next().unwrap().parse().unwrap();
+        assert!(count >= 3);
+        for name in FONT_NAMES {
+            assert!(text.contains(&format!("+{} ", name)), "missing {}", name);
+        }
+        assert!(text.contains(&format!("/Title {}", text_string("Book"))));
+    }
+}
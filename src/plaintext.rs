@@ -0,0 +1,304 @@
+//! Export markdown as plain text.
+//!
+//! Markdown syntax is stripped and paragraphs are reflowed to a fixed
+//! width, for submission forms that want pasted text. Scene breaks are kept
+//! as `* * *`, lists get hanging indents and block quotes are indented.
+
+use pulldown_cmark::{Event, Options, Tag, TagEnd};
+use std::io;
+
+use crate::export::{self, ExportOptions, Exporter, Metadata, SourceDocument};
+
+/// Column to wrap at when none is configured
+pub const DEFAULT_WIDTH: usize = 72;
+
+/// Indent of each block quote level
+const QUOTE_INDENT: usize = 4;
+/// Indent of each list level, wide enough for "1. "
+const LIST_INDENT: usize = 3;
+
+/// Plain text reflowed to a fixed width.
+pub struct TextExporter {
+    width: usize,
+    parser_options: Options,
+}
+
+impl TextExporter {
+    pub fn new(options: &ExportOptions) -> Self {
+        Self {
+            width: options.text_width.unwrap_or(DEFAULT_WIDTH),
+            parser_options: export::parser_options(options),
+        }
+    }
+}
+
+impl Exporter for TextExporter {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn export(&self, documents: &[SourceDocument], _metadata: &Metadata) -> io::Result<Vec<u8>> {
+        let mut writer = TextWriter::new(self.width);
+        for event in export::parse_documents(documents, self.parser_options) {
+            writer.event(event);
+        }
+        Ok(writer.finish().into_bytes())
+    }
+}
+
+/// Wrap text to `width` columns, starting the first line with `first_prefix`
+/// and the others with `prefix`. Newlines in the text are kept as line
+/// breaks. A width of 0 leaves each line unwrapped.
+pub fn wrap(text: &str, width: usize, first_prefix: &str, prefix: &str) -> String {
+    let mut out = String::new();
+    let mut line = first_prefix.to_string();
+    let mut line_empty = true;
+    for (i, segment) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push_str(line.trim_end());
+            out.push('\n');
+            line = prefix.to_string();
+            line_empty = true;
+        }
+        for word in segment.split_whitespace() {
+            let fits = width == 0 || line.chars().count() + 1 + word.chars().count() <= width;
+            if !line_empty && !fits {
+                out.push_str(&line);
+                out.push('\n');
+                line = prefix.to_string();
+                line_empty = true;
+            }
+            if !line_empty {
+                line.push(' ');
+            }
+            line.push_str(word);
+            line_empty = false;
+        }
+    }
+    out.push_str(line.trim_end());
+    out.push('\n');
+    out
+}
+
+/// Writes plain text for a stream of markdown events
+struct TextWriter {
+    out: String,
+    width: usize,
+    /// Inline text of the open block; newlines are hard breaks
+    text: String,
+    quote_depth: usize,
+    /// Numbering of the open lists: `Some(next)` for ordered lists
+    lists: Vec<Option<u64>>,
+    /// Bullet or number waiting for the first block of a list item
+    item_marker: Option<String>,
+    /// Where each open link's text starts, and its destination
+    links: Vec<(usize, String)>,
+    /// Cells written in the open table row
+    cells: usize,
+    /// The last block written was in a list; list items are not spaced apart
+    last_in_list: bool,
+}
+
+impl TextWriter {
+    fn new(width: usize) -> Self {
+        Self {
+            out: String::new(),
+            width,
+            text: String::new(),
+            quote_depth: 0,
+            lists: Vec::new(),
+            item_marker: None,
+            links: Vec::new(),
+            cells: 0,
+            last_in_list: false,
+        }
+    }
+
+    fn indent(&self) -> String {
+        " ".repeat(self.quote_depth * QUOTE_INDENT + self.lists.len() * LIST_INDENT)
+    }
+
+    /// Append a finished block, separated from the previous one by a blank line
+    fn write_block(&mut self, block: &str) {
+        let in_list = !self.lists.is_empty();
+        if !self.out.is_empty() && (!in_list || !self.last_in_list) {
+            self.out.push('\n');
+        }
+        self.out.push_str(block);
+        self.last_in_list = in_list;
+    }
+
+    /// Wrap and write the open block's text
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.text);
+        let marker = self.item_marker.take();
+        if text.trim().is_empty() && marker.is_none() {
+            return;
+        }
+        let indent = self.indent();
+        let first = match marker {
+            // The marker hangs in the indent of its list level
+            Some(marker) => {
+                let outer = indent.len().saturating_sub(LIST_INDENT);
+                format!("{}{:<width$}", &indent[..outer], marker, width = LIST_INDENT)
+            }
+            None => indent.clone(),
+        };
+        let block = wrap(&text, self.width, &first, &indent);
+        self.write_block(&block);
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) | Event::Code(text) => self.text.push_str(&text),
+            Event::SoftBreak => self.text.push(' '),
+            Event::HardBreak => self.text.push('\n'),
+            Event::Rule => {
+                self.flush();
+                let block = format!("{}* * *\n", self.indent());
+                self.write_block(&block);
+            }
+            Event::FootnoteReference(label) => self.text.push_str(&format!("[{}]", export::footnote_label(&label))),
+            Event::TaskListMarker(done) => self.text.push_str(if done { "[x] " } else { "[ ] " }),
+            Event::Html(_) | Event::InlineHtml(_) => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph | Tag::Heading { .. } => self.flush(),
+            Tag::BlockQuote => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => self.flush(),
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                self.item_marker = Some(match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "-".to_string(),
+                });
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.flush();
+                self.text.push_str(&format!("[{}] ", export::footnote_label(&label)));
+            }
+            Tag::TableHead | Tag::TableRow => {
+                self.flush();
+                self.cells = 0;
+            }
+            Tag::TableCell => {
+                if self.cells > 0 {
+                    self.text.push_str(" | ");
+                }
+                self.cells += 1;
+            }
+            Tag::Link { dest_url, .. } => self.links.push((self.text.len(), dest_url.to_string())),
+            Tag::Image { .. } => self.text.push_str("[Image: "),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::FootnoteDefinition => self.flush(),
+            TagEnd::TableHead | TagEnd::TableRow => self.flush(),
+            TagEnd::BlockQuote => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                let indent = format!("{}{}", self.indent(), " ".repeat(QUOTE_INDENT));
+                let code = std::mem::take(&mut self.text);
+                let block: String = code
+                    .lines()
+                    .map(|line| format!("{}\n", format!("{}{}", indent, line).trim_end()))
+                    .collect();
+                self.write_block(&block);
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::Link => {
+                // Keep the address unless the link text already shows it
+                if let Some((start, dest)) = self.links.pop() {
+                    if !dest.starts_with('#') && self.text[start..] != dest {
+                        self.text.push_str(&format!(" ({})", dest));
+                    }
+                }
+            }
+            TagEnd::Image => self.text.push(']'),
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Options, Parser};
+
+    fn text(markdown: &str, width: usize) -> String {
+        let mut writer = TextWriter::new(width);
+        for event in Parser::new_ext(markdown, Options::all()) {
+            writer.event(event);
+        }
+        writer.finish()
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("one two three four", 9, "", ""), "one two\nthree\nfour\n");
+        assert_eq!(wrap("one two three", 10, "- ", "  "), "- one two\n  three\n");
+        assert_eq!(wrap("one two\nthree", 0, "", ""), "one two\nthree\n");
+        // Words longer than the line stay whole
+        assert_eq!(wrap("a extraordinarily b", 5, "", ""), "a\nextraordinarily\nb\n");
+    }
+
+    #[test]
+    fn test_strips_markdown_and_reflows() {
+        let out = text(
+            "# The *Road*\n\nShe ran **fast**, then `slow`,\nthen not at all and sat down by the river.\n\n***\n\nNext [scene](https://x.com).\n",
+            30,
+        );
+        assert_eq!(
+            out,
+            "The Road\n\nShe ran fast, then slow, then\nnot at all and sat down by the\nriver.\n\n* * *\n\nNext scene (https://x.com).\n"
+        );
+    }
+
+    #[test]
+    fn test_lists_and_quotes() {
+        let out = text("- one two three four\n- five\n  1. six\n\n> quoted words here\n", 14);
+        assert_eq!(
+            out,
+            "-  one two\n   three four\n-  five\n   1. six\n\n    quoted\n    words here\n"
+        );
+    }
+
+    #[test]
+    fn test_unwrapped_when_width_is_zero() {
+        let long = "word ".repeat(40);
+        let out = text(&format!("{}\n\nShort.", long), 0);
+        assert_eq!(out, format!("{}\n\nShort.\n", long.trim_end()));
+    }
+}
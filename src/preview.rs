@@ -0,0 +1,393 @@
+//! Live preview of the HTML export
+//!
+//! `hollow preview` serves the rendered document at `/` on 127.0.0.1, the
+//! images and stylesheets it refers to by their relative paths, and a
+//! server-sent events stream at `/events`. The page listens to the stream and
+//! reloads whenever a source changes. Everything is served by this process;
+//! the page loads nothing from elsewhere.
+//!
+//! Requests must name the server itself in their Host header, so a web page
+//! can't reach it through a rebound DNS name, and no other file is served.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::export::html_escape;
+use crate::watch::Watcher;
+
+/// Port used when none is given
+pub const DEFAULT_PORT: u16 = 4000;
+
+/// How often an idle event stream sends a comment, which also notices
+/// browsers that have gone away
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Reloads the page when the server says so
+const RELOAD_SCRIPT: &str =
+    "<script>new EventSource(\"/events\").onmessage = function () { location.reload(); };</script>\n";
+
+/// The latest render and how many times the sources have changed
+struct Page {
+    html: String,
+    /// Files the page refers to, relative to the root
+    files: HashSet<PathBuf>,
+    version: u64,
+}
+
+struct Shared {
+    page: Mutex<Page>,
+    changed: Condvar,
+    /// Directory that other requested files are served from, canonicalized
+    root: PathBuf,
+    /// Port the server listens on, which the Host header must name
+    port: u16,
+}
+
+impl Shared {
+    fn page(&self) -> MutexGuard<'_, Page> {
+        self.page.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Serve the preview of `input` until the process is stopped. `render`
+/// produces the HTML page, and runs again whenever a source changes.
+pub fn serve<F>(listener: TcpListener, input: &Path, render: F) -> io::Result<()>
+where
+    F: Fn() -> io::Result<String> + Send + 'static,
+{
+    let root = match input.parent() {
+        _ if input.is_dir() => input.to_path_buf(),
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let root = fs::canonicalize(&root).unwrap_or(root);
+    let html = render_page(&render);
+    let shared = Arc::new(Shared {
+        page: Mutex::new(Page {
+            files: referenced_files(&html),
+            html,
+            version: 0,
+        }),
+        changed: Condvar::new(),
+        root,
+        port: listener.local_addr()?.port(),
+    });
+
+    let watched = Arc::clone(&shared);
+    let mut watcher = Watcher::new(input);
+    thread::spawn(move || loop {
+        watcher.wait();
+        let html = render_page(&render);
+        let files = referenced_files(&html);
+        let mut page = watched.page();
+        page.html = html;
+        page.files = files;
+        page.version += 1;
+        watched.changed.notify_all();
+    });
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let shared = Arc::clone(&shared);
+        // A browser that disconnects mid-response is not an error worth reporting
+        thread::spawn(move || {
+            let _ = handle(stream, &shared);
+        });
+    }
+    Ok(())
+}
+
+/// Render the page with the reload script, or a page showing why it failed
+fn render_page<F: Fn() -> io::Result<String>>(render: &F) -> String {
+    match render() {
+        Ok(html) => with_reload_script(&html),
+        Err(e) => with_reload_script(&format!(
+            "<!DOCTYPE html>\n<html>\n<body>\n<h1>Preview failed</h1>\n<pre>{}</pre>\n</body>\n</html>\n",
+            html_escape(&e.to_string())
+        )),
+    }
+}
+
+/// Add the reload script at the end of the body
+fn with_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(end) => format!("{}{}{}", &html[..end], RELOAD_SCRIPT, &html[end..]),
+        None => format!("{}\n{}", html, RELOAD_SCRIPT),
+    }
+}
+
+fn handle(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut host = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("/");
+    if !allowed_host(host.as_deref(), shared.port) {
+        return respond(&mut stream, "403 Forbidden", "text/plain", b"Forbidden\n");
+    }
+    if method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"Method not allowed\n");
+    }
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    match path {
+        "/" | "/index.html" => {
+            let html = shared.page().html.clone();
+            respond(&mut stream, "200 OK", "text/html; charset=utf-8", html.as_bytes())
+        }
+        "/events" => events(stream, shared),
+        _ => {
+            let file = local_file(&shared.root, path, &shared.page().files);
+            match file.and_then(|file| Some((asset_type(&file)?, file))) {
+                Some((content_type, file)) => respond(&mut stream, "200 OK", content_type, &fs::read(&file)?),
+                None => respond(&mut stream, "404 Not Found", "text/plain", b"Not found\n"),
+            }
+        }
+    }
+}
+
+/// Whether a Host header names this server: 127.0.0.1 or localhost with its port
+fn allowed_host(host: Option<&str>, port: u16) -> bool {
+    host.is_some_and(|host| host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port))
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// Stream a reload event every time the page changes
+fn events(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+    )?;
+    stream.flush()?;
+    let mut seen = shared.page().version;
+    loop {
+        let page = shared.page();
+        let (page, _) = shared
+            .changed
+            .wait_timeout_while(page, KEEPALIVE, |page| page.version == seen)
+            .unwrap_or_else(|e| e.into_inner());
+        let message: &[u8] = if page.version == seen {
+            b": keep-alive\n\n"
+        } else {
+            seen = page.version;
+            b"data: reload\n\n"
+        };
+        drop(page);
+        // Fails once the browser has gone, which ends the stream
+        stream.write_all(message)?;
+        stream.flush()?;
+    }
+}
+
+/// The file a request path names under `root`, if the page refers to it and
+/// it is an image or stylesheet. Paths that would leave `root`, symlinks
+/// included, are refused.
+fn local_file(root: &Path, path: &str, referenced: &HashSet<PathBuf>) -> Option<PathBuf> {
+    let relative = relative_path(path)?;
+    if !referenced.contains(&relative) || asset_type(&relative).is_none() {
+        return None;
+    }
+    let file = fs::canonicalize(root.join(relative)).ok()?;
+    (file.starts_with(root) && file.is_file()).then_some(file)
+}
+
+/// Relative paths in the page's `src` and `href` attributes
+fn referenced_files(html: &str) -> HashSet<PathBuf> {
+    let mut files = HashSet::new();
+    for attribute in ["src=\"", "href=\""] {
+        for (start, _) in html.match_indices(attribute) {
+            let value = &html[start + attribute.len()..];
+            let Some(end) = value.find('"') else { continue };
+            // Links to other sites have a scheme; these don't
+            let value = value[..end].replace("&amp;", "&");
+            if !value.contains(':') {
+                files.extend(relative_path(&value));
+            }
+        }
+    }
+    files
+}
+
+/// A URL path as a plain relative path, without query, fragment or `.`
+/// parts. Paths with `..` or a root are refused.
+fn relative_path(url: &str) -> Option<PathBuf> {
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    let decoded = percent_decode(path.trim_start_matches('/'))?;
+    let mut relative = PathBuf::new();
+    for component in Path::new(&decoded).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Decode `%XX` escapes in a URL path
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Content type of a file that may be served: images and stylesheets only
+fn asset_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "css" => Some("text/css; charset=utf-8"),
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "svg" => Some("image/svg+xml"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_with_reload_script() {
+        let html = with_reload_script("<html><body><p>Hi</p></body></html>");
+        assert!(html.ends_with(&format!("<p>Hi</p>{}</body></html>", RELOAD_SCRIPT)));
+        assert!(with_reload_script("<p>Hi</p>").ends_with(RELOAD_SCRIPT));
+    }
+
+    #[test]
+    fn test_local_file_stays_in_root() {
+        let dir = tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join("img")).unwrap();
+        fs::write(root.join("img/old map.png"), "png").unwrap();
+        fs::write(root.join("notes.txt"), "secret").unwrap();
+        fs::write(root.join("unused.png"), "png").unwrap();
+        let referenced = referenced_files(
+            "<img src=\"./img/old%20map.png\" /><a href=\"notes.txt\">n</a>\
+             <img src=\"img/missing.png\" /><a href=\"https://example.com/x.png\">x</a>",
+        );
+
+        assert_eq!(local_file(&root, "/img/old%20map.png", &referenced), Some(root.join("img/old map.png")));
+        assert_eq!(local_file(&root, "/img/missing.png", &referenced), None);
+        // Only images and stylesheets the page refers to
+        assert_eq!(local_file(&root, "/notes.txt", &referenced), None);
+        assert_eq!(local_file(&root, "/unused.png", &referenced), None);
+        assert_eq!(local_file(&root, "/../etc/passwd", &referenced), None);
+        assert_eq!(local_file(&root, "/img/%2e%2e/%2e%2e/etc/passwd", &referenced), None);
+        assert_eq!(percent_decode("bad%2"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_local_file_refuses_symlinks_out_of_root() {
+        let outside = tempdir().unwrap();
+        fs::write(outside.path().join("secret.png"), "png").unwrap();
+        let dir = tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("secret.png"), root.join("map.png")).unwrap();
+
+        let referenced = referenced_files("<img src=\"map.png\" />");
+        assert_eq!(local_file(&root, "/map.png", &referenced), None);
+    }
+
+    #[test]
+    fn test_allowed_host() {
+        assert!(allowed_host(Some("127.0.0.1:4000"), 4000));
+        assert!(allowed_host(Some("localhost:4000"), 4000));
+        assert!(!allowed_host(Some("localhost:4001"), 4000));
+        assert!(!allowed_host(Some("evil.example:4000"), 4000));
+        assert!(!allowed_host(None, 4000));
+    }
+
+    #[test]
+    fn test_serves_page_and_reload_events() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("draft.md");
+        fs::write(&input, "First").unwrap();
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let source = input.clone();
+        thread::spawn(move || {
+            serve(listener, &input, move || {
+                Ok(format!("<html><body>{}</body></html>", fs::read_to_string(&source)?))
+            })
+        });
+
+        let get = |target: &str, host: &str| {
+            let mut response = String::new();
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", target, host).unwrap();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let host = format!("localhost:{}", port);
+        let page = get("/", &host);
+        assert!(page.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(page.contains("<body>First<script>"));
+        let refused = get("/", &format!("evil.example:{}", port));
+        assert!(refused.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+
+        let mut events = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        events.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        write!(events, "GET /events HTTP/1.1\r\nHost: 127.0.0.1:{}\r\n\r\n", port).unwrap();
+        let mut reader = BufReader::new(events);
+        let mut line = String::new();
+        while line != "\r\n" {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+
+        thread::sleep(Duration::from_millis(50));
+        fs::write(dir.path().join("draft.md"), "Second version").unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "data: reload\n");
+
+        assert!(get("/", &host).contains("<body>Second version<script>"));
+    }
+}
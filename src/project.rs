@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::frontmatter;
+
 /// A project containing multiple documents
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -17,6 +19,9 @@ pub struct Project {
     /// Project-specific settings (override global config)
     #[serde(default)]
     pub settings: ProjectSettings,
+    /// How `hollow project compile` assembles the manuscript
+    #[serde(default, skip_serializing_if = "CompileSettings::is_default")]
+    pub compile: CompileSettings,
     /// Path to the project file (not serialized)
     #[serde(skip)]
     pub path: Option<PathBuf>,
@@ -33,6 +38,79 @@ pub struct ProjectSettings {
     pub show_streak: Option<bool>,
     /// Custom theme for this project
     pub theme: Option<String>,
+    /// Spell checking languages for the project's documents
+    pub languages: Option<Vec<String>>,
+}
+
+/// Settings for compiling the project into one manuscript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompileSettings {
+    /// Output format: md, or any `hollow export` format
+    #[serde(default = "default_compile_format")]
+    pub format: String,
+    /// Output file relative to the project (default: <project name>.<extension>)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Markdown inserted between documents, such as "***"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+    /// Where chapter headings come from
+    #[serde(default)]
+    pub headings: ChapterHeadings,
+    /// Chapter heading text; {n} is the chapter number, {title} its title
+    #[serde(default = "default_heading_format")]
+    pub heading_format: String,
+    /// Documents left out of the manuscript: paths, or directories ending in '/'
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// Source of each compiled chapter's heading
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChapterHeadings {
+    /// The document's first H1, or its file name when it has none
+    #[default]
+    FirstHeading,
+    /// The file name, replacing any H1 the document has
+    FileName,
+    /// Leave documents as they are
+    None,
+}
+
+impl Default for CompileSettings {
+    fn default() -> Self {
+        Self {
+            format: default_compile_format(),
+            output: None,
+            separator: None,
+            headings: ChapterHeadings::default(),
+            heading_format: default_heading_format(),
+            notes: Vec::new(),
+        }
+    }
+}
+
+impl CompileSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a document is listed as a note
+    pub fn is_note(&self, doc: &str) -> bool {
+        self.notes.iter().any(|note| match note.strip_suffix('/') {
+            Some(dir) => doc.starts_with(&format!("{}/", dir)),
+            None => note == doc,
+        })
+    }
+}
+
+fn default_compile_format() -> String {
+    "md".to_string()
+}
+
+fn default_heading_format() -> String {
+    "{title}".to_string()
 }
 
 /// Project-wide statistics
@@ -53,6 +131,7 @@ impl Project {
             name: name.into(),
             documents: Vec::new(),
             settings: ProjectSettings::default(),
+            compile: CompileSettings::default(),
             path: None,
         }
     }
@@ -103,11 +182,22 @@ impl Project {
         self.path.as_ref().and_then(|p| p.parent().map(PathBuf::from))
     }
 
+    /// The project's spelling word list, `.hollow-dict` beside the project file
+    pub fn dictionary_path(&self) -> Option<PathBuf> {
+        self.base_dir().map(|base| base.join(".hollow-dict"))
+    }
+
     /// Resolve a document path relative to the project
     pub fn resolve_document(&self, doc: &str) -> Option<PathBuf> {
         self.base_dir().map(|base| base.join(doc))
     }
 
+    /// A document's title from its front matter
+    pub fn document_title(&self, doc: &str) -> Option<String> {
+        let text = fs::read_to_string(self.resolve_document(doc)?).ok()?;
+        frontmatter::field(&frontmatter::parse(&text)?, "title")
+    }
+
     /// Calculate project statistics
     pub fn stats(&self) -> Result<ProjectStats, ProjectError> {
         let base = self.base_dir().ok_or(ProjectError::NoPath)?;
@@ -129,9 +219,9 @@ impl Project {
     }
 }
 
-/// Count words in text
+/// Count words in text, leaving out front matter
 fn count_words(text: &str) -> u64 {
-    text.split_whitespace().count() as u64
+    frontmatter::body(text).split_whitespace().count() as u64
 }
 
 /// Project-related errors
@@ -209,6 +299,30 @@ mod tests {
         assert_eq!(loaded.settings.daily_goal, Some(1000));
     }
 
+    #[test]
+    fn test_compile_settings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".hollow-project");
+
+        // Defaults are not written out
+        let project = Project::new("Test");
+        project.save(Some(&path)).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("compile"));
+
+        fs::write(
+            &path,
+            "name: Book\ndocuments: [a.md, notes/cast.md]\ncompile:\n  format: pdf\n  headings: file-name\n  notes: [notes/]\n",
+        )
+        .unwrap();
+        let loaded = Project::load(&path).unwrap();
+        assert_eq!(loaded.compile.format, "pdf");
+        assert_eq!(loaded.compile.headings, ChapterHeadings::FileName);
+        assert_eq!(loaded.compile.heading_format, "{title}");
+        assert!(loaded.compile.is_note("notes/cast.md"));
+        assert!(!loaded.compile.is_note("a.md"));
+        assert!(!loaded.compile.is_note("notes.md"));
+    }
+
     #[test]
     fn test_resolve_document() {
         let dir = tempdir().unwrap();
@@ -228,7 +342,7 @@ mod tests {
         let path = dir.path().join(".hollow-project");
         
         // Create test documents
-        fs::write(dir.path().join("ch1.md"), "one two three").unwrap();
+        fs::write(dir.path().join("ch1.md"), "---\ntitle: Arrival\n---\none two three").unwrap();
         fs::write(dir.path().join("ch2.md"), "four five").unwrap();
         
         let mut project = Project::new("Test");
@@ -239,6 +353,9 @@ mod tests {
         let stats = project.stats().unwrap();
         assert_eq!(stats.total_words, 5);
         assert_eq!(stats.document_count, 2);
+
+        assert_eq!(project.document_title("ch1.md").as_deref(), Some("Arrival"));
+        assert_eq!(project.document_title("ch2.md"), None);
     }
 
     #[test]
@@ -0,0 +1,338 @@
+//! Search and replace across every document in a project
+//!
+//! Matching is done with the same `Search` used for the open buffer, so flags
+//! and inline prefixes behave identically. The open document is searched from
+//! the editor's rope rather than from disk, so unsaved edits are included.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ropey::Rope;
+
+use crate::project::{Project, ProjectError};
+use crate::search::Search;
+use crate::versions::VersionStore;
+
+/// A match in a project document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectMatch {
+    /// Line number (0-based)
+    pub line: usize,
+    /// Match start and end as char positions in the document
+    pub start: usize,
+    pub end: usize,
+    /// Text of the matching line, without its newline
+    pub context: String,
+    /// Match start and end as char positions within `context`
+    pub col: usize,
+    pub col_end: usize,
+}
+
+/// All matches in one document
+#[derive(Debug, Clone)]
+pub struct FileResults {
+    /// Document name as listed in the project
+    pub document: String,
+    pub path: PathBuf,
+    pub matches: Vec<ProjectMatch>,
+}
+
+/// A planned replacement in one document
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub document: String,
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+    pub replacements: usize,
+    /// Char ranges of `before` to replace, with their new text
+    pub ranges: Vec<(usize, usize, String)>,
+    /// Changed lines as (line number, old text, new text)
+    pub lines: Vec<(usize, String, String)>,
+}
+
+/// Project search overlay state
+#[derive(Debug, Default)]
+pub struct ProjectSearch {
+    pub query: String,
+    pub with: String,
+    /// Whether typing goes to the replacement field
+    pub editing_with: bool,
+    pub results: Vec<FileResults>,
+    /// Index into the flattened list of matches
+    pub selected: usize,
+    /// Pending replace, shown for confirmation
+    pub preview: Option<Vec<FileChange>>,
+}
+
+impl ProjectSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total number of matches across files
+    pub fn match_count(&self) -> usize {
+        self.results.iter().map(|f| f.matches.len()).sum()
+    }
+
+    /// The selected match and the file it is in
+    pub fn selected_match(&self) -> Option<(&FileResults, &ProjectMatch)> {
+        self.results
+            .iter()
+            .flat_map(|f| f.matches.iter().map(move |m| (f, m)))
+            .nth(self.selected)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.match_count() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+}
+
+/// Read a document, taking the open buffer's content if it is that file
+fn document_rope(path: &Path, open: Option<(&Path, &Rope)>) -> Option<Rope> {
+    if let Some((open_path, rope)) = open {
+        if same_file(open_path, path) {
+            return Some(rope.clone());
+        }
+    }
+    fs::read_to_string(path).ok().map(|s| Rope::from_str(&s))
+}
+
+/// Compare paths, resolving them when both exist
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Line text without its trailing newline
+fn line_text(rope: &Rope, line: usize) -> String {
+    let mut text = rope.line(line).to_string();
+    if text.ends_with('\n') {
+        text.pop();
+    }
+    text
+}
+
+/// Search every document in the project. Unreadable documents are skipped.
+pub fn search_project(
+    project: &Project,
+    search: &Search,
+    open: Option<(&Path, &Rope)>,
+) -> Vec<FileResults> {
+    let mut results = Vec::new();
+
+    for doc in &project.documents {
+        let Some(path) = project.resolve_document(doc) else {
+            continue;
+        };
+        let Some(rope) = document_rope(&path, open) else {
+            continue;
+        };
+
+        let matches: Vec<ProjectMatch> = search
+            .all_matches(&rope)
+            .into_iter()
+            .map(|(start, end)| {
+                let line = rope.char_to_line(start);
+                let col = start - rope.line_to_char(line);
+                ProjectMatch {
+                    line,
+                    start,
+                    end,
+                    context: line_text(&rope, line),
+                    col,
+                    col_end: col + end - start,
+                }
+            })
+            .collect();
+
+        if !matches.is_empty() {
+            results.push(FileResults {
+                document: doc.clone(),
+                path,
+                matches,
+            });
+        }
+    }
+
+    results
+}
+
+/// Work out what replacing every match in the project would change
+pub fn plan_replace(
+    project: &Project,
+    search: &Search,
+    replacement: &str,
+    open: Option<(&Path, &Rope)>,
+) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+
+    for doc in &project.documents {
+        let Some(path) = project.resolve_document(doc) else {
+            continue;
+        };
+        let Some(rope) = document_rope(&path, open) else {
+            continue;
+        };
+
+        let replacements: Vec<(usize, usize, String)> = search
+            .all_matches(&rope)
+            .into_iter()
+            .map(|m| (m.0, m.1, search.expand(&rope, m, replacement)))
+            .collect();
+        if replacements.is_empty() {
+            continue;
+        }
+
+        // Rewrite each affected line on its own for the preview,
+        // applying its replacements right to left
+        let mut lines: Vec<(usize, String, String)> = Vec::new();
+        for (start, _, _) in &replacements {
+            let line = rope.char_to_line(*start);
+            if lines.last().map(|l| l.0) != Some(line) {
+                let old = line_text(&rope, line);
+                lines.push((line, old.clone(), old));
+            }
+        }
+        for (line, _, new) in &mut lines {
+            let line_start = rope.line_to_char(*line);
+            let mut chars: Vec<char> = new.chars().collect();
+            for (start, end, text) in replacements.iter().rev() {
+                if rope.char_to_line(*start) == *line {
+                    chars.splice(start - line_start..end - line_start, text.chars());
+                }
+            }
+            *new = chars.into_iter().collect();
+        }
+
+        let mut after = rope.clone();
+        for (start, end, text) in replacements.iter().rev() {
+            after.remove(*start..*end);
+            after.insert(*start, text);
+        }
+
+        changes.push(FileChange {
+            document: doc.clone(),
+            path,
+            before: rope.to_string(),
+            after: after.to_string(),
+            replacements: replacements.len(),
+            ranges: replacements,
+            lines,
+        });
+    }
+
+    changes
+}
+
+/// Write planned changes, first saving a version of each file's current content
+/// Returns the number of replacements made; an error names the document that failed
+pub fn apply_replace(
+    changes: &[FileChange],
+    store: Option<&VersionStore>,
+) -> Result<usize, ProjectError> {
+    let mut total = 0;
+    for change in changes {
+        let failed = |e: &dyn std::fmt::Display| ProjectError::Io(format!("{}: {}", change.document, e));
+        if let Some(store) = store {
+            store
+                .save_version(&change.path.to_string_lossy(), &change.before)
+                .map_err(|e| failed(&e))?;
+        }
+        fs::write(&change.path, &change.after).map_err(|e| failed(&e))?;
+        total += change.replacements;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn project_with(files: &[(&str, &str)]) -> (tempfile::TempDir, Project) {
+        let dir = tempdir().unwrap();
+        let mut project = Project::new("Test");
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+            project.add_document(*name);
+        }
+        project.path = Some(dir.path().join(".hollow-project"));
+        (dir, project)
+    }
+
+    fn search_for(query: &str) -> Search {
+        let mut search = Search::new();
+        search.set_query(query);
+        search
+    }
+
+    #[test]
+    fn test_search_groups_by_file() {
+        let (_dir, project) = project_with(&[
+            ("one.md", "Anna left.\nThen Anna came back."),
+            ("two.md", "Nobody here."),
+            ("three.md", "Anna again."),
+        ]);
+
+        let results = search_project(&project, &search_for("anna"), None);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].document, "one.md");
+        assert_eq!(results[0].matches.len(), 2);
+        assert_eq!(results[0].matches[1].line, 1);
+        assert_eq!(results[0].matches[1].context, "Then Anna came back.");
+        assert_eq!(results[0].matches[1].col, 5);
+        assert_eq!(results[1].document, "three.md");
+    }
+
+    #[test]
+    fn test_search_uses_open_buffer() {
+        let (dir, project) = project_with(&[("one.md", "on disk")]);
+        let rope = Rope::from_str("unsaved Anna");
+        let path = dir.path().join("one.md");
+
+        let results = search_project(&project, &search_for("anna"), Some((&path, &rope)));
+        assert_eq!(results[0].matches[0].start, 8);
+    }
+
+    #[test]
+    fn test_plan_and_apply_replace() {
+        let (dir, project) = project_with(&[
+            ("one.md", "Anna met Anna.\nOther line."),
+            ("two.md", "No match."),
+        ]);
+
+        let changes = plan_replace(&project, &search_for("Anna"), "Hanna", None);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].replacements, 2);
+        assert_eq!(
+            changes[0].lines,
+            vec![(0, "Anna met Anna.".to_string(), "Hanna met Hanna.".to_string())]
+        );
+
+        // Nothing is written until applied
+        assert_eq!(fs::read_to_string(dir.path().join("one.md")).unwrap(), "Anna met Anna.\nOther line.");
+        assert_eq!(apply_replace(&changes, None).unwrap(), 2);
+        assert_eq!(fs::read_to_string(dir.path().join("one.md")).unwrap(), "Hanna met Hanna.\nOther line.");
+    }
+
+    #[test]
+    fn test_apply_replace_error_names_document() {
+        let (dir, project) = project_with(&[("one.md", "Anna.")]);
+        let changes = plan_replace(&project, &search_for("Anna"), "Hanna", None);
+        assert_eq!(changes[0].ranges, vec![(0, 4, "Hanna".to_string())]);
+
+        fs::remove_file(dir.path().join("one.md")).unwrap();
+        fs::create_dir(dir.path().join("one.md")).unwrap();
+        let err = apply_replace(&changes, None).unwrap_err();
+        assert!(err.to_string().contains("one.md: "), "{}", err);
+    }
+}
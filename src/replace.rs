@@ -0,0 +1,215 @@
+//! Find and replace
+//!
+//! A replace runs in two steps: the prompt collects the pattern, replacement
+//! and scope, then a session walks the matches in scope, replacing or
+//! skipping each one, or all that remain at once.
+
+use crate::editor::Editor;
+use crate::search::Search;
+
+/// Which part of the document a replace covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplaceScope {
+    #[default]
+    Document,
+    /// The paragraph around the cursor when the replace starts
+    Paragraph,
+}
+
+impl ReplaceScope {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReplaceScope::Document => "document",
+            ReplaceScope::Paragraph => "paragraph",
+        }
+    }
+}
+
+/// Replace prompt fields and the running confirm session
+#[derive(Debug, Default)]
+pub struct Replace {
+    pub find: String,
+    pub with: String,
+    /// Whether typing goes to the replacement field
+    pub editing_with: bool,
+    pub scope: ReplaceScope,
+    /// Char range being replaced in; the end moves as replacements change its length
+    range: (usize, usize),
+    current: Option<(usize, usize)>,
+    replaced: usize,
+}
+
+impl Replace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reset the prompt for a new replace
+    pub fn reset(&mut self) {
+        *self = Self {
+            scope: self.scope,
+            ..Self::default()
+        };
+    }
+
+    /// Type a character into the active field
+    pub fn push(&mut self, c: char) {
+        self.field_mut().push(c);
+    }
+
+    /// Delete the last character of the active field
+    pub fn pop(&mut self) {
+        self.field_mut().pop();
+    }
+
+    fn field_mut(&mut self) -> &mut String {
+        if self.editing_with {
+            &mut self.with
+        } else {
+            &mut self.find
+        }
+    }
+
+    pub fn toggle_scope(&mut self) {
+        self.scope = match self.scope {
+            ReplaceScope::Document => ReplaceScope::Paragraph,
+            ReplaceScope::Paragraph => ReplaceScope::Document,
+        };
+    }
+
+    /// Start walking matches of `search` in scope. Returns false if there are none.
+    pub fn start(&mut self, editor: &Editor, search: &Search) -> bool {
+        self.range = match self.scope {
+            ReplaceScope::Document => (0, editor.content().len_chars()),
+            ReplaceScope::Paragraph => editor.paragraph_range(),
+        };
+        self.replaced = 0;
+        self.current = None;
+        self.advance(editor, search, self.range.0);
+        self.current.is_some()
+    }
+
+    /// The match waiting for confirmation
+    pub fn current(&self) -> Option<(usize, usize)> {
+        self.current
+    }
+
+    /// Number of replacements made in this session
+    pub fn replaced(&self) -> usize {
+        self.replaced
+    }
+
+    /// Replace the current match and move to the next
+    pub fn replace_current(&mut self, editor: &mut Editor, search: &Search) {
+        let Some(m) = self.current else {
+            return;
+        };
+        let text = search.expand(editor.content(), m, &self.with);
+        let len = text.chars().count();
+        editor.replace_ranges(&[(m.0, m.1, text)]);
+        self.range.1 = self.range.1 + len - (m.1 - m.0);
+        self.replaced += 1;
+        self.advance(editor, search, m.0 + len);
+    }
+
+    /// Leave the current match and move to the next
+    pub fn skip(&mut self, editor: &Editor, search: &Search) {
+        if let Some(m) = self.current {
+            self.advance(editor, search, m.1);
+        }
+    }
+
+    /// Replace the current match and every one after it as a single undo step
+    pub fn replace_all(&mut self, editor: &mut Editor, search: &Search) {
+        let Some(m) = self.current else {
+            return;
+        };
+        let replacements: Vec<_> = search
+            .matches_in(editor.content(), m.0, self.range.1)
+            .into_iter()
+            .map(|r| (r.0, r.1, search.expand(editor.content(), r, &self.with)))
+            .collect();
+        self.replaced += replacements.len();
+        editor.replace_ranges(&replacements);
+        self.current = None;
+    }
+
+    /// Stop the session
+    pub fn finish(&mut self) {
+        self.current = None;
+    }
+
+    fn advance(&mut self, editor: &Editor, search: &Search, from: usize) {
+        self.current = search
+            .matches_in(editor.content(), from, self.range.1)
+            .into_iter()
+            .next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(content: &str, find: &str, with: &str) -> (Editor, Search, Replace) {
+        let mut editor = Editor::new();
+        editor.set_content(content);
+        let mut search = Search::new();
+        search.set_query(find);
+        let mut replace = Replace::new();
+        replace.with = with.to_string();
+        (editor, search, replace)
+    }
+
+    #[test]
+    fn test_confirm_each() {
+        let (mut editor, search, mut replace) = setup("Ann and Ann and Ann", "Ann", "Bea");
+        assert!(replace.start(&editor, &search));
+        assert_eq!(replace.current(), Some((0, 3)));
+
+        replace.replace_current(&mut editor, &search);
+        assert_eq!(replace.current(), Some((8, 11)));
+        replace.skip(&editor, &search);
+        replace.replace_current(&mut editor, &search);
+        assert_eq!(replace.current(), None);
+
+        assert_eq!(editor.content().to_string(), "Bea and Ann and Bea");
+        assert_eq!(replace.replaced(), 2);
+    }
+
+    #[test]
+    fn test_replace_all_is_one_undo_step() {
+        let (mut editor, search, mut replace) = setup("Ann and Ann and Ann", "Ann", "Beatrice");
+        replace.start(&editor, &search);
+        replace.replace_all(&mut editor, &search);
+        assert_eq!(editor.content().to_string(), "Beatrice and Beatrice and Beatrice");
+
+        editor.undo();
+        assert_eq!(editor.content().to_string(), "Ann and Ann and Ann");
+    }
+
+    #[test]
+    fn test_paragraph_scope() {
+        let (mut editor, search, mut replace) = setup("Ann here.\n\nAnn there.\nAnn too.", "Ann", "Bea");
+        editor.move_cursor(crate::editor::Direction::Down, crate::editor::Unit::Document);
+        replace.scope = ReplaceScope::Paragraph;
+        replace.start(&editor, &search);
+        replace.replace_all(&mut editor, &search);
+        assert_eq!(editor.content().to_string(), "Ann here.\n\nBea there.\nBea too.");
+    }
+
+    #[test]
+    fn test_regex_capture_groups() {
+        let (mut editor, search, mut replace) =
+            setup("Smith, John\nDoe, Jane", r"\v^(\w+), (\w+)$", "$2 $1");
+        replace.start(&editor, &search);
+        replace.replace_all(&mut editor, &search);
+        assert_eq!(editor.content().to_string(), "John Smith\nJane Doe");
+    }
+
+    #[test]
+    fn test_no_matches() {
+        let (editor, search, mut replace) = setup("nothing here", "Ann", "Bea");
+        assert!(!replace.start(&editor, &search));
+    }
+}
@@ -1,9 +1,78 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+
+use regex::{Regex, RegexBuilder};
 use ropey::Rope;
 
+/// How search compares letter case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseMode {
+    #[default]
+    Insensitive,
+    Sensitive,
+    /// Case-sensitive only when the query contains an uppercase letter
+    Smart,
+}
+
+/// Options controlling how a query matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchFlags {
+    pub regex: bool,
+    pub whole_word: bool,
+    pub case: CaseMode,
+}
+
+impl SearchFlags {
+    /// Strip inline flag prefixes from a query, applying them on top of these flags
+    ///
+    /// `\v` regex, `\w` whole word, `\c` ignore case, `\C` match case, `\s` smart case.
+    /// Prefixes may be stacked: `\C\v^Chapter`. Once regex mode is on, the
+    /// rest of the query is the pattern, so `\s+$` and `\w+ing` keep their
+    /// escapes; `\v` therefore goes last.
+    pub fn parse(self, query: &str) -> (SearchFlags, &str) {
+        let mut flags = self;
+        let mut rest = query;
+        while !flags.regex {
+            match rest.get(..2) {
+                Some(r"\v") => flags.regex = true,
+                Some(r"\w") => flags.whole_word = true,
+                Some(r"\c") => flags.case = CaseMode::Insensitive,
+                Some(r"\C") => flags.case = CaseMode::Sensitive,
+                Some(r"\s") => flags.case = CaseMode::Smart,
+                _ => return (flags, rest),
+            }
+            rest = &rest[2..];
+        }
+        (flags, rest)
+    }
+
+    /// Short label for the search prompt, e.g. `regex word Aa`
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if self.regex {
+            parts.push("regex");
+        }
+        if self.whole_word {
+            parts.push("word");
+        }
+        parts.push(match self.case {
+            CaseMode::Insensitive => "aa",
+            CaseMode::Sensitive => "Aa",
+            CaseMode::Smart => "smart",
+        });
+        parts.join(" ")
+    }
+}
+
 /// Search functionality for the editor
 pub struct Search {
     query: String,
-    query_lower: String,
+    /// Flags toggled from the search prompt; inline prefixes apply on top
+    base_flags: SearchFlags,
+    flags: SearchFlags,
+    regex: Option<Regex>,
+    error: Option<String>,
 }
 
 impl Search {
@@ -11,14 +80,17 @@ impl Search {
     pub fn new() -> Self {
         Self {
             query: String::new(),
-            query_lower: String::new(),
+            base_flags: SearchFlags::default(),
+            flags: SearchFlags::default(),
+            regex: None,
+            error: None,
         }
     }
 
-    /// Set the search query
+    /// Set the search query, including any inline flag prefixes
     pub fn set_query(&mut self, query: &str) {
         self.query = query.to_string();
-        self.query_lower = query.to_lowercase();
+        self.compile();
     }
 
     /// Get the current query
@@ -26,87 +98,201 @@ impl Search {
         &self.query
     }
 
+    /// Flags in effect for the current query
+    pub fn flags(&self) -> SearchFlags {
+        self.flags
+    }
+
+    /// Flags toggled from the search prompt
+    pub fn base_flags(&self) -> SearchFlags {
+        self.base_flags
+    }
+
+    /// Error from compiling the current query, if it is an invalid regex
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Toggle regex matching
+    pub fn toggle_regex(&mut self) {
+        self.base_flags.regex = !self.base_flags.regex;
+        self.compile();
+    }
+
+    /// Toggle whole-word matching
+    pub fn toggle_whole_word(&mut self) {
+        self.base_flags.whole_word = !self.base_flags.whole_word;
+        self.compile();
+    }
+
+    /// Cycle case mode: insensitive, sensitive, smart
+    pub fn cycle_case(&mut self) {
+        self.base_flags.case = match self.base_flags.case {
+            CaseMode::Insensitive => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Smart,
+            CaseMode::Smart => CaseMode::Insensitive,
+        };
+        self.compile();
+    }
+
     /// Clear the search
     pub fn clear(&mut self) {
         self.query.clear();
-        self.query_lower.clear();
+        self.compile();
     }
 
     /// Check if search is active
     pub fn is_active(&self) -> bool {
-        !self.query.is_empty()
+        self.regex.is_some()
     }
 
-    /// Find next match after the given char position
-    /// Returns (start, end) char positions if found
-    pub fn find_next(&self, content: &Rope, from_char: usize) -> Option<(usize, usize)> {
-        if self.query.is_empty() {
-            return None;
-        }
+    /// Build the matcher for the current query and flags
+    fn compile(&mut self) {
+        let (flags, pattern) = self.base_flags.parse(&self.query);
+        self.flags = flags;
+        self.regex = None;
+        self.error = None;
 
-        let text: String = content.chars().collect();
-        let text_lower = text.to_lowercase();
-        let query_len = self.query.chars().count();
+        if pattern.is_empty() {
+            return;
+        }
 
-        // Search from position
-        if let Some(pos) = text_lower[from_char..].find(&self.query_lower) {
-            let start = from_char + pos;
-            return Some((start, start + query_len));
+        let mut source = if flags.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        if flags.whole_word {
+            source = format!(r"\b(?:{})\b", source);
         }
+        let case_insensitive = match flags.case {
+            CaseMode::Insensitive => true,
+            CaseMode::Sensitive => false,
+            CaseMode::Smart => !pattern.chars().any(char::is_uppercase),
+        };
+
+        match RegexBuilder::new(&source).case_insensitive(case_insensitive).build() {
+            Ok(regex) => self.regex = Some(regex),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
 
-        // Wrap around: search from beginning
-        if from_char > 0 {
-            if let Some(pos) = text_lower[..from_char].find(&self.query_lower) {
-                return Some((pos, pos + query_len));
-            }
+    /// Matches on a single line as (start, end) char positions in the document.
+    /// Only the line itself is borrowed from the rope, and only copied if it
+    /// straddles chunk boundaries.
+    fn line_matches(&self, content: &Rope, line_idx: usize) -> Vec<(usize, usize)> {
+        let Some(regex) = &self.regex else {
+            return Vec::new();
+        };
+
+        let line: Cow<str> = content.line(line_idx).into();
+        let text = line.strip_suffix('\n').unwrap_or(&line);
+        let mut char_pos = content.line_to_char(line_idx);
+        let mut last_byte = 0;
+
+        regex
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                char_pos += text[last_byte..m.start()].chars().count();
+                let start = char_pos;
+                char_pos += m.as_str().chars().count();
+                last_byte = m.end();
+                (start, char_pos)
+            })
+            .collect()
+    }
+
+    /// Find next match starting at or after the given char position, wrapping around
+    /// Returns (start, end) char positions if found
+    pub fn find_next(&self, content: &Rope, from_char: usize) -> Option<(usize, usize)> {
+        if !self.is_active() {
+            return None;
         }
 
-        None
+        let from_char = from_char.min(content.len_chars());
+        let from_line = content.char_to_line(from_char);
+
+        (from_line..content.len_lines())
+            .flat_map(|l| self.line_matches(content, l))
+            .find(|m| m.0 >= from_char)
+            // Wrap around: search from beginning
+            .or_else(|| {
+                (0..=from_line)
+                    .flat_map(|l| self.line_matches(content, l))
+                    .find(|m| m.0 < from_char)
+            })
     }
 
-    /// Find previous match before the given char position
+    /// Find previous match starting before the given char position, wrapping around
     /// Returns (start, end) char positions if found
     pub fn find_prev(&self, content: &Rope, from_char: usize) -> Option<(usize, usize)> {
-        if self.query.is_empty() {
+        if !self.is_active() {
             return None;
         }
 
-        let text: String = content.chars().collect();
-        let text_lower = text.to_lowercase();
-        let query_len = self.query.chars().count();
+        let from_char = from_char.min(content.len_chars());
+        let from_line = content.char_to_line(from_char);
+
+        (0..=from_line)
+            .rev()
+            .flat_map(|l| self.line_matches(content, l).into_iter().rev())
+            .find(|m| m.0 < from_char)
+            // Wrap around: search from end
+            .or_else(|| {
+                (from_line..content.len_lines())
+                    .rev()
+                    .flat_map(|l| self.line_matches(content, l).into_iter().rev())
+                    .find(|m| m.0 >= from_char)
+            })
+    }
 
-        // Search backwards from position
-        if from_char > 0 {
-            if let Some(pos) = text_lower[..from_char].rfind(&self.query_lower) {
-                return Some((pos, pos + query_len));
-            }
+    /// Find matches lying entirely within a char range
+    pub fn matches_in(&self, content: &Rope, start: usize, end: usize) -> Vec<(usize, usize)> {
+        if !self.is_active() || start >= end {
+            return Vec::new();
         }
 
-        // Wrap around: search from end
-        if from_char < text.len() {
-            if let Some(pos) = text_lower[from_char..].rfind(&self.query_lower) {
-                let start = from_char + pos;
-                return Some((start, start + query_len));
+        let end = end.min(content.len_chars());
+        let first = content.char_to_line(start.min(end));
+        let last = content.char_to_line(end);
+        (first..=last.min(content.len_lines().saturating_sub(1)))
+            .flat_map(|l| self.line_matches(content, l))
+            .filter(|m| m.0 >= start && m.1 <= end)
+            .collect()
+    }
+
+    /// Replacement text for a match. In regex mode `$1`, `${name}` and `$$`
+    /// expand from the match's capture groups; otherwise it is used literally.
+    pub fn expand(&self, content: &Rope, m: (usize, usize), replacement: &str) -> String {
+        let Some(regex) = self.regex.as_ref().filter(|_| self.flags.regex) else {
+            return replacement.to_string();
+        };
+
+        let line_idx = content.char_to_line(m.0);
+        let line: Cow<str> = content.line(line_idx).into();
+        let text = line.strip_suffix('\n').unwrap_or(&line);
+        let byte = content.slice(content.line_to_char(line_idx)..m.0).len_bytes();
+
+        match regex.captures_at(text, byte) {
+            Some(caps) => {
+                let mut out = String::new();
+                caps.expand(replacement, &mut out);
+                out
             }
+            None => replacement.to_string(),
         }
-
-        None
     }
 
     /// Find all matches in the content
     /// Returns vec of (start, end) char positions
     pub fn all_matches(&self, content: &Rope) -> Vec<(usize, usize)> {
-        if self.query.is_empty() {
+        if !self.is_active() {
             return Vec::new();
         }
 
-        let text: String = content.chars().collect();
-        let text_lower = text.to_lowercase();
-        let query_len = self.query.chars().count();
-
-        text_lower
-            .match_indices(&self.query_lower)
-            .map(|(pos, _)| (pos, pos + query_len))
+        (0..content.len_lines())
+            .flat_map(|l| self.line_matches(content, l))
             .collect()
     }
 }
@@ -117,6 +303,106 @@ impl Default for Search {
     }
 }
 
+/// Most searches kept in the history file
+const HISTORY_LIMIT: usize = 100;
+
+/// Previous search queries, oldest first, saved between sessions
+pub struct SearchHistory {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+    /// Index of the entry being shown while browsing with Up/Down
+    browsing: Option<usize>,
+    /// What was typed before browsing started
+    draft: String,
+}
+
+impl SearchHistory {
+    /// Load history from ~/.config/hollow/search_history
+    pub fn load() -> Self {
+        Self::load_from(dirs::config_dir().map(|p| p.join("hollow").join("search_history")))
+    }
+
+    /// Load history from a file; `None` keeps it in memory only
+    pub fn load_from(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|l| !l.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            entries,
+            path,
+            browsing: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Record a query, moving it to the newest position
+    pub fn push(&mut self, query: &str) {
+        self.browsing = None;
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e != query);
+        self.entries.push(query.to_string());
+        if self.entries.len() > HISTORY_LIMIT {
+            self.entries.remove(0);
+        }
+        let _ = self.save();
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = self.entries.join("\n");
+        content.push('\n');
+        fs::write(path, content)
+    }
+
+    /// Stop browsing, e.g. when a new search starts
+    pub fn reset(&mut self) {
+        self.browsing = None;
+    }
+
+    /// Step to an older query. `current` is what is in the prompt now.
+    pub fn prev(&mut self, current: &str) -> Option<&str> {
+        let index = match self.browsing {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(0) => 0,
+            Some(i) => i - 1,
+        };
+        self.browsing = Some(index);
+        Some(&self.entries[index])
+    }
+
+    /// Step to a newer query, ending at what was typed before browsing
+    pub fn next(&mut self) -> Option<&str> {
+        let index = self.browsing?;
+        if index + 1 < self.entries.len() {
+            self.browsing = Some(index + 1);
+            Some(&self.entries[index + 1])
+        } else {
+            self.browsing = None;
+            Some(&self.draft)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,4 +519,150 @@ mod tests {
         assert_eq!(search.find_prev(&rope, 10), None);
         assert!(search.all_matches(&rope).is_empty());
     }
+
+    #[test]
+    fn test_multibyte_char_positions() {
+        let mut search = Search::new();
+        search.set_query("world");
+        let rope = make_rope("héllo wörld\nnaïve world");
+
+        // Char positions, not byte positions
+        assert_eq!(search.all_matches(&rope), vec![(18, 23)]);
+        search.set_query("WÖRLD");
+        assert_eq!(search.find_next(&rope, 0), Some((6, 11)));
+    }
+
+    #[test]
+    fn test_case_modes() {
+        let mut search = Search::new();
+        let rope = make_rope("Hello hello");
+
+        search.set_query("\\Chello");
+        assert_eq!(search.all_matches(&rope), vec![(6, 11)]);
+
+        search.set_query("\\shello");
+        assert_eq!(search.all_matches(&rope).len(), 2);
+        search.set_query("\\sHello");
+        assert_eq!(search.all_matches(&rope), vec![(0, 5)]);
+
+        search.cycle_case();
+        search.set_query("hello");
+        assert_eq!(search.flags().case, CaseMode::Sensitive);
+        assert_eq!(search.all_matches(&rope), vec![(6, 11)]);
+        // Inline prefix overrides the toggled flag
+        search.set_query("\\chello");
+        assert_eq!(search.all_matches(&rope).len(), 2);
+    }
+
+    #[test]
+    fn test_whole_word() {
+        let mut search = Search::new();
+        let rope = make_rope("cat concatenate cat.");
+
+        search.set_query("cat");
+        assert_eq!(search.all_matches(&rope).len(), 3);
+        search.set_query("\\wcat");
+        assert_eq!(search.all_matches(&rope), vec![(0, 3), (16, 19)]);
+    }
+
+    #[test]
+    fn test_regex() {
+        let mut search = Search::new();
+        let rope = make_rope("Chapter 1\nchapter twelve\nChapter 22");
+
+        search.set_query("\\v^chapter \\d+$");
+        assert_eq!(search.all_matches(&rope), vec![(0, 9), (25, 35)]);
+
+        // Without the regex flag, metacharacters are literal
+        search.set_query("\\d+");
+        assert!(search.all_matches(&rope).is_empty());
+        assert!(search.is_active());
+    }
+
+    #[test]
+    fn test_invalid_regex_reports_error() {
+        let mut search = Search::new();
+        search.set_query("\\v(unclosed");
+        assert!(!search.is_active());
+        assert!(search.error().is_some());
+
+        search.toggle_regex();
+        search.set_query("(unclosed");
+        assert!(search.error().is_some());
+        search.toggle_regex();
+        assert!(search.error().is_none());
+        assert!(search.is_active());
+    }
+
+    #[test]
+    fn test_flags_label() {
+        let (flags, rest) = SearchFlags::default().parse("\\w\\C\\vfoo");
+        assert_eq!(rest, "foo");
+        assert_eq!(flags.label(), "regex word Aa");
+    }
+
+    #[test]
+    fn test_regex_escapes_are_not_prefixes() {
+        let (flags, rest) = SearchFlags::default().parse(r"\v\w+ing");
+        assert!(flags.regex && !flags.whole_word);
+        assert_eq!(rest, r"\w+ing");
+
+        let regex = SearchFlags { regex: true, ..Default::default() };
+        let (flags, rest) = regex.parse(r"\s+$");
+        assert_eq!(flags.case, CaseMode::Insensitive);
+        assert_eq!(rest, r"\s+$");
+    }
+
+    #[test]
+    fn test_matches_in_range() {
+        let mut search = Search::new();
+        search.set_query("a");
+        let rope = make_rope("a a\na a");
+        assert_eq!(search.matches_in(&rope, 2, 5), vec![(2, 3), (4, 5)]);
+    }
+
+    #[test]
+    fn test_expand_capture_groups() {
+        let mut search = Search::new();
+        let rope = make_rope("Smith, John\nDoe, Jane");
+
+        search.set_query(r"\v(\w+), (\w+)");
+        let m = search.all_matches(&rope)[1];
+        assert_eq!(search.expand(&rope, m, "$2 $1"), "Jane Doe");
+
+        // Literal mode leaves $ alone
+        search.set_query("Doe");
+        let m = search.all_matches(&rope)[0];
+        assert_eq!(search.expand(&rope, m, "$1"), "$1");
+    }
+
+    #[test]
+    fn test_history_browse() {
+        let mut history = SearchHistory::load_from(None);
+        history.push("one");
+        history.push("two");
+        history.push("one");
+
+        assert_eq!(history.prev("typed"), Some("one"));
+        assert_eq!(history.prev("one"), Some("two"));
+        // Stops at the oldest
+        assert_eq!(history.prev("two"), Some("two"));
+        assert_eq!(history.next(), Some("one"));
+        assert_eq!(history.next(), Some("typed"));
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn test_history_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("search_history");
+
+        let mut history = SearchHistory::load_from(Some(path.clone()));
+        history.push("Chapter");
+        history.push(r"\v\d+");
+
+        let mut reloaded = SearchHistory::load_from(Some(path));
+        assert_eq!(reloaded.prev(""), Some(r"\v\d+"));
+        assert_eq!(reloaded.prev(""), Some("Chapter"));
+    }
 }
@@ -1,17 +1,123 @@
 use std::collections::HashSet;
 use std::fs;
 use std::io::{self, BufRead, Write};
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
+use serde_yaml::Value;
 use spellbook::Dictionary;
 
-/// Spell checker with personal dictionary support
+use crate::config::{expand_home, SpellingConfig};
+use crate::frontmatter;
+use crate::markdown;
+
+/// Spell checker with layered word lists on top of the main dictionary:
+/// the global personal list, `custom_dict` from config, the project's
+/// `.hollow-dict`, and `<!-- hollow-spell: ... -->` comments in the document
 pub struct SpellChecker {
-    dictionary: Option<Dictionary>,
+    /// Every dictionary loaded so far, one per language
+    dictionaries: Vec<LoadedDictionary>,
+    /// Languages checked against: a word is correct if any of them accepts it
+    languages: Vec<String>,
+    /// Searched first for dictionaries (the `dictionary_path` setting)
+    dictionary_path: Option<PathBuf>,
     personal_words: HashSet<String>,
     personal_dict_path: PathBuf,
+    custom_words: HashSet<String>,
+    custom_dict_path: Option<PathBuf>,
+    project_words: HashSet<String>,
+    project_dict_path: Option<PathBuf>,
+    /// Words ignored until the editor is closed
+    session_words: HashSet<String>,
+    skip: SkipRules,
     enabled: bool,
-    language: String,
+}
+
+/// Word list used for en_US when no Hunspell dictionary is installed.
+/// It covers common words only; install Hunspell dictionaries for more.
+const BUNDLED_EN_US_AFF: &str = include_str!("../dict/en_US.aff");
+const BUNDLED_EN_US_DIC: &str = include_str!("../dict/en_US.dic");
+
+/// Where a language's dictionary came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum DictSource {
+    File(PathBuf),
+    /// The built-in en_US word list
+    Bundled,
+}
+
+/// How a language's dictionary was looked for, for `hollow doctor spell`
+#[derive(Debug, Clone)]
+pub struct DictReport {
+    pub language: String,
+    /// .dic paths tried, in order, and whether each existed
+    pub tried: Vec<(PathBuf, bool)>,
+    /// What was loaded, if anything
+    pub source: Option<DictSource>,
+}
+
+struct LoadedDictionary {
+    dictionary: Option<Dictionary>,
+    report: DictReport,
+}
+
+/// A word list that "add to dictionary" can write to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DictLayer {
+    /// ~/.config/hollow/personal.dic, used for every document
+    Personal,
+    /// The `custom_dict` file from config
+    Custom,
+    /// `.hollow-dict` next to `.hollow-project`
+    Project,
+    /// A `<!-- hollow-spell: ... -->` comment in the document itself
+    Document,
+}
+
+impl DictLayer {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DictLayer::Personal => "Personal dictionary",
+            DictLayer::Custom => "Custom dictionary",
+            DictLayer::Project => "Project dictionary",
+            DictLayer::Document => "This document",
+        }
+    }
+}
+
+/// Kinds of token that are never flagged, each switchable in config
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkipRules {
+    /// Words in capitals, like acronyms (NASA)
+    pub all_caps: bool,
+    /// Words containing digits (mp3, 2nd)
+    pub digits: bool,
+    /// Email addresses
+    pub emails: bool,
+    /// @mentions
+    pub mentions: bool,
+}
+
+impl Default for SkipRules {
+    fn default() -> Self {
+        Self {
+            all_caps: true,
+            digits: true,
+            emails: true,
+            mentions: true,
+        }
+    }
+}
+
+impl From<&SpellingConfig> for SkipRules {
+    fn from(config: &SpellingConfig) -> Self {
+        Self {
+            all_caps: config.skip_all_caps,
+            digits: config.skip_words_with_digits,
+            emails: config.skip_emails,
+            mentions: config.skip_mentions,
+        }
+    }
 }
 
 /// A misspelled word with its position
@@ -30,52 +136,214 @@ pub struct SpellCheckResult {
     pub misspellings: Vec<Misspelling>,
 }
 
+impl SpellCheckResult {
+    /// First misspelling after (line, char col), wrapping to the first one
+    pub fn next_after(&self, line: usize, col: usize) -> Option<&Misspelling> {
+        self.misspellings
+            .iter()
+            .find(|m| (m.line, m.col) > (line, col))
+            .or_else(|| self.misspellings.first())
+    }
+
+    /// Last misspelling before (line, char col), wrapping to the last one
+    pub fn prev_before(&self, line: usize, col: usize) -> Option<&Misspelling> {
+        self.misspellings
+            .iter()
+            .rev()
+            .find(|m| (m.line, m.col) < (line, col))
+            .or_else(|| self.misspellings.last())
+    }
+
+    /// First misspelling at or after (line, char col), without wrapping
+    pub fn first_from(&self, line: usize, col: usize) -> Option<&Misspelling> {
+        self.misspellings.iter().find(|m| (m.line, m.col) >= (line, col))
+    }
+}
+
+/// A pass through every misspelling in the document, one at a time
+#[derive(Debug, Default)]
+pub struct SpellReview {
+    /// The misspelling being reviewed
+    pub current: Option<Misspelling>,
+    pub suggestions: Vec<String>,
+    /// Selected suggestion
+    pub index: usize,
+    /// Number of words replaced so far
+    pub changed: usize,
+}
+
+impl SpellReview {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move on to a new misspelling
+    pub fn show(&mut self, misspelling: Misspelling, suggestions: Vec<String>) {
+        self.current = Some(misspelling);
+        self.suggestions = suggestions;
+        self.index = 0;
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.suggestions.get(self.index).map(String::as_str)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.index + 1 < self.suggestions.len() {
+            self.index += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.index = self.index.saturating_sub(1);
+    }
+}
+
 impl SpellChecker {
     /// Create a new spell checker with the given language
+    #[cfg(test)]
     pub fn new(language: &str) -> Self {
+        Self::with_dictionary_path(language, None)
+    }
+
+    /// Create a spell checker that looks in `dictionary_path` before the
+    /// standard locations
+    fn with_dictionary_path(language: &str, dictionary_path: Option<PathBuf>) -> Self {
         let personal_dict_path = dirs::config_dir()
             .map(|p| p.join("hollow").join("personal.dic"))
             .unwrap_or_else(|| PathBuf::from("personal.dic"));
 
         let mut checker = Self {
-            dictionary: None,
+            dictionaries: Vec::new(),
+            languages: Vec::new(),
+            dictionary_path,
             personal_words: HashSet::new(),
             personal_dict_path,
+            custom_words: HashSet::new(),
+            custom_dict_path: None,
+            project_words: HashSet::new(),
+            project_dict_path: None,
+            session_words: HashSet::new(),
+            skip: SkipRules::default(),
             enabled: true,
-            language: language.to_string(),
         };
 
-        checker.load_dictionary();
+        checker.set_languages(&[language.to_string()]);
         checker.load_personal_dictionary();
         checker
     }
 
-    /// Load the main dictionary for the configured language
-    fn load_dictionary(&mut self) {
-        let dic_paths = Self::get_dictionary_paths(&self.language);
-
-        for (aff_path, dic_path) in dic_paths {
-            if aff_path.exists() && dic_path.exists() {
-                if let Ok(aff) = fs::read_to_string(&aff_path) {
-                    if let Ok(dic) = fs::read_to_string(&dic_path) {
-                        match Dictionary::new(&aff, &dic) {
-                            Ok(dict) => {
-                                self.dictionary = Some(dict);
-                                return;
-                            }
-                            Err(_) => continue,
-                        }
-                    }
+    /// Create a spell checker from the `[spelling]` config section
+    pub fn from_config(config: &SpellingConfig) -> Self {
+        let languages = config.active_languages();
+        let dictionary_path = config.dictionary_path.as_deref().map(expand_home);
+        let mut checker = Self::with_dictionary_path(&languages[0], dictionary_path);
+        checker.set_languages(&languages);
+        checker.set_enabled(config.enabled);
+        checker.set_skip_rules(SkipRules::from(config));
+        checker.set_custom_dict(config.custom_dict.as_deref());
+        checker
+    }
+
+    /// Check against these languages, loading any dictionaries not loaded yet
+    pub fn set_languages(&mut self, languages: &[String]) {
+        for language in languages {
+            if !self.dictionaries.iter().any(|d| &d.report.language == language) {
+                let loaded = self.load_dictionary(language);
+                self.dictionaries.push(loaded);
+            }
+        }
+        self.languages = languages.to_vec();
+    }
+
+    /// Active languages
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    /// How each active language's dictionary was found
+    pub fn reports(&self) -> impl Iterator<Item = &DictReport> {
+        self.dictionaries
+            .iter()
+            .filter(|d| self.languages.contains(&d.report.language))
+            .map(|d| &d.report)
+    }
+
+    /// Loaded dictionaries for the active languages
+    fn active_dictionaries(&self) -> impl Iterator<Item = &Dictionary> {
+        self.dictionaries
+            .iter()
+            .filter(|d| self.languages.contains(&d.report.language))
+            .filter_map(|d| d.dictionary.as_ref())
+    }
+
+    /// Load the main dictionary for a language from the first location that
+    /// has it, falling back to the bundled word list for en_US
+    fn load_dictionary(&self, language: &str) -> LoadedDictionary {
+        Self::load_first(language, self.get_dictionary_paths(language))
+    }
+
+    /// Load a language's dictionary from the first of `paths` that has it,
+    /// or the bundled word list
+    fn load_first(language: &str, paths: Vec<(PathBuf, PathBuf)>) -> LoadedDictionary {
+        let mut report = DictReport {
+            language: language.to_string(),
+            tried: Vec::new(),
+            source: None,
+        };
+
+        for (aff_path, dic_path) in paths {
+            let found = aff_path.exists() && dic_path.exists();
+            report.tried.push((dic_path.clone(), found));
+            if !found {
+                continue;
+            }
+            if let (Ok(aff), Ok(dic)) = (fs::read_to_string(&aff_path), fs::read_to_string(&dic_path)) {
+                if let Ok(dict) = Dictionary::new(&aff, &dic) {
+                    report.source = Some(DictSource::File(dic_path));
+                    return LoadedDictionary { dictionary: Some(dict), report };
                 }
             }
         }
+
+        if language == "en_US" {
+            if let Ok(dict) = Dictionary::new(BUNDLED_EN_US_AFF, BUNDLED_EN_US_DIC) {
+                report.source = Some(DictSource::Bundled);
+                return LoadedDictionary { dictionary: Some(dict), report };
+            }
+        }
+
         // No dictionary found - spell checking will be disabled
+        LoadedDictionary { dictionary: None, report }
     }
 
-    /// Get potential dictionary paths for a language
-    fn get_dictionary_paths(language: &str) -> Vec<(PathBuf, PathBuf)> {
+    /// Get potential dictionary paths for a language: the `dictionary_path`
+    /// setting, then `HOLLOW_DICT_PATH` and `DICPATH`, then system locations
+    fn get_dictionary_paths(&self, language: &str) -> Vec<(PathBuf, PathBuf)> {
         let mut paths = Vec::new();
 
+        let in_dir = |dir: &Path| {
+            (
+                dir.join(format!("{}.aff", language)),
+                dir.join(format!("{}.dic", language)),
+            )
+        };
+
+        // Explicit setting: a directory, or a .dic file with its .aff beside it
+        if let Some(ref path) = self.dictionary_path {
+            if path.extension().is_some_and(|e| e == "dic") {
+                paths.push((path.with_extension("aff"), path.clone()));
+            } else {
+                paths.push(in_dir(path));
+            }
+        }
+
+        for var in ["HOLLOW_DICT_PATH", "DICPATH"] {
+            if let Some(value) = std::env::var_os(var) {
+                paths.extend(std::env::split_paths(&value).map(|dir| in_dir(&dir)));
+            }
+        }
+
         // System paths
         #[cfg(target_os = "linux")]
         {
@@ -146,29 +414,66 @@ impl SpellChecker {
 
     /// Load personal dictionary
     fn load_personal_dictionary(&mut self) {
-        if let Ok(file) = fs::File::open(&self.personal_dict_path) {
-            let reader = io::BufReader::new(file);
-            for line in reader.lines().map_while(Result::ok) {
-                let word = line.trim().to_lowercase();
-                if !word.is_empty() {
-                    self.personal_words.insert(word);
-                }
-            }
-        }
+        self.personal_words = read_word_list(&self.personal_dict_path);
     }
 
-    /// Save personal dictionary
-    fn save_personal_dictionary(&self) -> io::Result<()> {
-        if let Some(parent) = self.personal_dict_path.parent() {
-            fs::create_dir_all(parent)?;
+    /// Use the word list at `path` (from the `custom_dict` setting)
+    pub fn set_custom_dict(&mut self, path: Option<&str>) {
+        self.custom_dict_path = path.map(expand_home);
+        self.custom_words = self
+            .custom_dict_path
+            .as_deref()
+            .map(read_word_list)
+            .unwrap_or_default();
+    }
+
+    /// Use the project's word list at `path`, which need not exist yet
+    pub fn set_project_dict(&mut self, path: Option<PathBuf>) {
+        self.project_words = path.as_deref().map(read_word_list).unwrap_or_default();
+        self.project_dict_path = path;
+    }
+
+    /// Layers that can be written to: always personal and document,
+    /// custom and project only when configured
+    pub fn writable_layers(&self) -> Vec<DictLayer> {
+        let mut layers = vec![DictLayer::Personal];
+        if self.custom_dict_path.is_some() {
+            layers.push(DictLayer::Custom);
         }
-        let mut file = fs::File::create(&self.personal_dict_path)?;
-        let mut words: Vec<_> = self.personal_words.iter().collect();
-        words.sort();
-        for word in words {
-            writeln!(file, "{}", word)?;
+        if self.project_dict_path.is_some() {
+            layers.push(DictLayer::Project);
+        }
+        layers.push(DictLayer::Document);
+        layers
+    }
+
+    /// Add a word to a file-backed layer. Document words live in the text
+    /// itself; see `inline_word_edit`.
+    pub fn add_word(&mut self, word: &str, layer: DictLayer) -> io::Result<()> {
+        let lower = word.to_lowercase();
+        match layer {
+            DictLayer::Personal => {
+                self.personal_words.insert(lower);
+                write_word_list(&self.personal_dict_path, &self.personal_words)
+            }
+            DictLayer::Custom => match self.custom_dict_path {
+                Some(ref path) => {
+                    self.custom_words.insert(lower);
+                    // Append rather than rewrite: the file is the user's own
+                    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+                    writeln!(file, "{}", word)
+                }
+                None => Ok(()),
+            },
+            DictLayer::Project => match self.project_dict_path {
+                Some(ref path) => {
+                    self.project_words.insert(lower);
+                    write_word_list(path, &self.project_words)
+                }
+                None => Ok(()),
+            },
+            DictLayer::Document => Ok(()),
         }
-        Ok(())
     }
 
     /// Check if a word is spelled correctly
@@ -179,47 +484,59 @@ impl SpellChecker {
 
         let lower = word.to_lowercase();
 
-        // Check personal dictionary first
-        if self.personal_words.contains(&lower) {
+        // Check the word lists first
+        if self.personal_words.contains(&lower)
+            || self.custom_words.contains(&lower)
+            || self.project_words.contains(&lower)
+            || self.session_words.contains(&lower)
+        {
             return true;
         }
 
-        // Check main dictionary
-        if let Some(ref dict) = self.dictionary {
-            dict.check(word)
-        } else {
-            // No dictionary loaded - assume correct
-            true
-        }
+        // Check the main dictionaries; with none loaded, assume correct
+        let mut dicts = self.active_dictionaries().peekable();
+        dicts.peek().is_none() || dicts.any(|dict| dict.check(word))
     }
 
     /// Get spelling suggestions for a word
+    /// Suggestions from every active language, in order, without duplicates
     pub fn suggest(&self, word: &str) -> Vec<String> {
-        if let Some(ref dict) = self.dictionary {
-            let mut suggestions = Vec::new();
-            dict.suggest(word, &mut suggestions);
-            suggestions.truncate(5);
-            suggestions
-        } else {
-            Vec::new()
+        let mut suggestions: Vec<String> = Vec::new();
+        for dict in self.active_dictionaries() {
+            let mut found = Vec::new();
+            dict.suggest(word, &mut found);
+            for s in found {
+                if !suggestions.contains(&s) {
+                    suggestions.push(s);
+                }
+            }
         }
+        suggestions.truncate(5);
+        suggestions
     }
 
-    /// Add a word to the personal dictionary
-    pub fn add_to_personal(&mut self, word: &str) {
-        let lower = word.to_lowercase();
-        self.personal_words.insert(lower);
-        let _ = self.save_personal_dictionary();
+    /// Ignore a word until the editor is closed, without saving it
+    pub fn ignore_for_session(&mut self, word: &str) {
+        self.session_words.insert(word.to_lowercase());
+    }
+
+    /// Paths and sizes of the word lists, for `hollow doctor spell`
+    pub fn word_lists(&self) -> Vec<(&'static str, Option<&Path>, usize)> {
+        vec![
+            ("Personal", Some(self.personal_dict_path.as_path()), self.personal_words.len()),
+            ("Custom", self.custom_dict_path.as_deref(), self.custom_words.len()),
+            ("Project", self.project_dict_path.as_deref(), self.project_words.len()),
+        ]
     }
 
     /// Check if spell checking is available (dictionary loaded)
     pub fn is_available(&self) -> bool {
-        self.dictionary.is_some()
+        self.active_dictionaries().next().is_some()
     }
 
     /// Check if spell checking is enabled
     pub fn is_enabled(&self) -> bool {
-        self.enabled && self.dictionary.is_some()
+        self.enabled && self.is_available()
     }
 
     /// Toggle spell checking on/off
@@ -232,55 +549,47 @@ impl SpellChecker {
         self.enabled = enabled;
     }
 
+    /// Set which kinds of token are never checked
+    pub fn set_skip_rules(&mut self, rules: SkipRules) {
+        self.skip = rules;
+    }
+
     /// Check a line of text and return misspellings
+    #[cfg(test)]
     pub fn check_line(&self, line: &str, line_num: usize) -> Vec<Misspelling> {
         if !self.is_enabled() {
             return Vec::new();
         }
+        self.check_segment(line, line_num, 0..line.len(), &HashSet::new())
+    }
 
-        let mut misspellings = Vec::new();
-        let mut word_start = None;
-        let mut col = 0;
-
-        for (i, c) in line.char_indices() {
-            if c.is_alphabetic() || c == '\'' {
-                if word_start.is_none() {
-                    word_start = Some((i, col));
-                }
-            } else if let Some((start, start_col)) = word_start {
-                let word = &line[start..i];
-                if word.len() > 1 && !self.check_word(word) {
-                    misspellings.push(Misspelling {
-                        word: word.to_string(),
-                        start,
-                        end: i,
-                        line: line_num,
-                        col: start_col,
-                    });
-                }
-                word_start = None;
-            }
-            col += 1;
-        }
-
-        // Check last word
-        if let Some((start, start_col)) = word_start {
-            let word = &line[start..];
-            if word.len() > 1 && !self.check_word(word) {
-                misspellings.push(Misspelling {
-                    word: word.to_string(),
-                    start,
-                    end: line.len(),
-                    line: line_num,
-                    col: start_col,
-                });
-            }
-        }
-
-        misspellings
+    /// Check the words of `line` that lie within the byte range `segment`
+    /// Words in `inline` (lowercase) are accepted as well.
+    fn check_segment(
+        &self,
+        line: &str,
+        line_num: usize,
+        segment: Range<usize>,
+        inline: &HashSet<String>,
+    ) -> Vec<Misspelling> {
+        candidate_words(line, segment, &self.skip)
+            .into_iter()
+            .filter(|r| {
+                let word = &line[r.clone()];
+                !inline.contains(&word.to_lowercase()) && !self.check_word(word)
+            })
+            .map(|r| Misspelling {
+                word: line[r.clone()].to_string(),
+                start: r.start,
+                end: r.end,
+                line: line_num,
+                col: line[..r.start].chars().count(),
+            })
+            .collect()
     }
 
-    /// Check entire text and return all misspellings
+    /// Check entire text and return all misspellings.
+    /// Only prose is checked: code, URLs, HTML and front matter are skipped.
     pub fn check_text(&self, text: &str) -> SpellCheckResult {
         let mut result = SpellCheckResult::default();
 
@@ -288,8 +597,27 @@ impl SpellChecker {
             return result;
         }
 
-        for (line_num, line) in text.lines().enumerate() {
-            result.misspellings.extend(self.check_line(line, line_num));
+        let prose = prose_ranges(text);
+        let inline = inline_words(text);
+        let mut next = 0;
+        let mut line_start = 0;
+        for (line_num, raw) in text.split('\n').enumerate() {
+            let line = raw.strip_suffix('\r').unwrap_or(raw);
+            let line_end = line_start + line.len();
+
+            // Skip ranges that ended before this line
+            while next < prose.len() && prose[next].end <= line_start {
+                next += 1;
+            }
+            for range in prose[next..].iter().take_while(|r| r.start < line_end) {
+                let start = range.start.max(line_start) - line_start;
+                let end = range.end.min(line_end) - line_start;
+                if start < end {
+                    result.misspellings.extend(self.check_segment(line, line_num, start..end, &inline));
+                }
+            }
+
+            line_start += raw.len() + 1;
         }
 
         result
@@ -322,6 +650,197 @@ impl SpellChecker {
     }
 }
 
+/// Languages named by a document's front matter `lang:` key, which may be
+/// a single language, a comma-separated list or a YAML list
+pub fn document_languages(text: &str) -> Vec<String> {
+    let names: Vec<String> = match frontmatter::get(text, "lang") {
+        Some(Value::String(s)) => s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(str::to_string)
+            .collect(),
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    names
+        .iter()
+        .map(|n| n.trim())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.replace('-', "_"))
+        .collect()
+}
+
+/// The next choice when cycling languages: all known languages together,
+/// then each one on its own
+pub fn next_languages(known: &[String], current: &[String]) -> Vec<String> {
+    let mut choices: Vec<Vec<String>> = Vec::new();
+    if known.len() > 1 {
+        choices.push(known.to_vec());
+    }
+    choices.extend(known.iter().map(|l| vec![l.clone()]));
+
+    match choices.iter().position(|c| c == current) {
+        Some(i) => choices[(i + 1) % choices.len()].clone(),
+        None => choices.first().cloned().unwrap_or_else(|| current.to_vec()),
+    }
+}
+
+/// Read a word list: one word per line, lowercased. Hunspell `.dic` files
+/// work too: the leading word count and any `/FLAGS` are ignored.
+fn read_word_list(path: &Path) -> HashSet<String> {
+    let mut words = HashSet::new();
+    if let Ok(file) = fs::File::open(path) {
+        let reader = io::BufReader::new(file);
+        for line in reader.lines().map_while(Result::ok) {
+            let word = line.split('/').next().unwrap_or("").trim();
+            if !word.is_empty() && !word.chars().all(|c| c.is_ascii_digit()) {
+                words.insert(word.to_lowercase());
+            }
+        }
+    }
+    words
+}
+
+/// Write a word list, sorted, creating parent directories as needed
+fn write_word_list(path: &Path, words: &HashSet<String>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::File::create(path)?;
+    let mut words: Vec<_> = words.iter().collect();
+    words.sort();
+    for word in words {
+        writeln!(file, "{}", word)?;
+    }
+    Ok(())
+}
+
+const INLINE_MARKER: &str = "hollow-spell:";
+
+/// Byte ranges of the word lists in `<!-- hollow-spell: ... -->` comments
+fn inline_comment_bodies(text: &str) -> Vec<Range<usize>> {
+    let mut bodies = Vec::new();
+    let mut from = 0;
+    while let Some(found) = text[from..].find(INLINE_MARKER) {
+        let marker = from + found;
+        let start = marker + INLINE_MARKER.len();
+        let end = text[start..].find("-->").map_or(text.len(), |e| start + e);
+        if text[..marker].trim_end().ends_with("<!--") {
+            bodies.push(start..end);
+        }
+        from = end.max(start);
+    }
+    bodies
+}
+
+/// Words accepted by `<!-- hollow-spell: word word -->` comments, lowercased
+pub fn inline_words(text: &str) -> HashSet<String> {
+    inline_comment_bodies(text)
+        .into_iter()
+        .flat_map(|body| {
+            text[body]
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|w| !w.is_empty())
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Where and what to insert to add `word` to the document's inline word list:
+/// into the first `hollow-spell` comment, or a new one at the end
+pub fn inline_word_edit(text: &str, word: &str) -> (usize, String) {
+    if let Some(body) = inline_comment_bodies(text).first() {
+        let words = text[body.clone()].trim_end();
+        return (body.start + words.len(), format!(" {}", word));
+    }
+    let newline = if text.is_empty() || text.ends_with('\n') { "" } else { "\n" };
+    (text.len(), format!("{}\n<!-- {} {} -->\n", newline, INLINE_MARKER, word))
+}
+
+/// Byte ranges of prose in a Markdown document, across all blocks
+fn prose_ranges(text: &str) -> Vec<Range<usize>> {
+    markdown::prose_blocks(text).into_iter().flatten().collect()
+}
+
+/// Whether a whitespace-separated chunk is a bare URL
+fn is_url(chunk: &str) -> bool {
+    chunk.contains("://") || chunk.starts_with("www.")
+}
+
+/// Whether a whitespace-separated chunk is an email address
+fn is_email(chunk: &str) -> bool {
+    match chunk.split_once('@') {
+        Some((user, domain)) => {
+            user.chars().any(char::is_alphanumeric)
+                && domain.trim_end_matches(|c: char| !c.is_alphanumeric()).contains('.')
+        }
+        None => false,
+    }
+}
+
+/// Byte ranges of the words in `line[segment]` that should be spell checked
+fn candidate_words(line: &str, segment: Range<usize>, skip: &SkipRules) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let text = &line[segment.clone()];
+
+    for (offset, chunk) in text
+        .split_whitespace()
+        .map(|c| (c.as_ptr() as usize - text.as_ptr() as usize, c))
+    {
+        if is_url(chunk) || (skip.emails && is_email(chunk)) {
+            continue;
+        }
+
+        let base = segment.start + offset;
+        let mut word_start = None;
+        for (i, c) in chunk.char_indices().chain(std::iter::once((chunk.len(), ' '))) {
+            if c.is_alphanumeric() || c == '\'' {
+                word_start.get_or_insert(i);
+                continue;
+            }
+            let Some(start) = word_start.take() else {
+                continue;
+            };
+            let word = &chunk[start..i];
+
+            if skip.mentions && chunk[..start].ends_with('@') {
+                continue;
+            }
+            if skip.all_caps && word.chars().filter(|c| c.is_alphabetic()).count() > 1
+                && !word.chars().any(char::is_lowercase)
+            {
+                continue;
+            }
+            if word.chars().any(|c| c.is_ascii_digit()) {
+                if skip.digits {
+                    continue;
+                }
+                // Check the letters on either side of the digits on their own
+                let mut run_start = None;
+                for (j, c) in word.char_indices().chain(std::iter::once((word.len(), ' '))) {
+                    if c.is_alphabetic() || c == '\'' {
+                        run_start.get_or_insert(j);
+                    } else if let Some(rs) = run_start.take() {
+                        if j - rs > 1 {
+                            words.push(base + start + rs..base + start + j);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            if word.len() > 1 {
+                words.push(base + start..base + i);
+            }
+        }
+    }
+
+    words
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,7 +862,7 @@ mod tests {
     #[test]
     fn test_check_word_without_dict() {
         let mut checker = SpellChecker::new("nonexistent_lang");
-        checker.dictionary = None;
+        checker.dictionaries.clear();
         // Without dictionary, all words are "correct"
         assert!(checker.check_word("anything"));
     }
@@ -403,10 +922,14 @@ mod tests {
     }
 
     #[test]
-    fn test_add_to_personal() {
+    fn test_add_to_personal_layer() {
+        let dir = tempfile::tempdir().unwrap();
         let mut checker = SpellChecker::new("en_US");
-        checker.add_to_personal("MyCustomWord");
+        checker.personal_dict_path = dir.path().join("hollow").join("personal.dic");
+        checker.personal_words.clear();
+        checker.add_word("MyCustomWord", DictLayer::Personal).unwrap();
         assert!(checker.personal_words.contains("mycustomword"));
+        assert_eq!(fs::read_to_string(&checker.personal_dict_path).unwrap(), "mycustomword\n");
     }
 
     #[test]
@@ -434,10 +957,197 @@ mod tests {
         assert_eq!(m.end - m.start, 4);
     }
 
+    #[test]
+    fn test_ignore_for_session() {
+        let mut checker = SpellChecker::new("en_US");
+        checker.ignore_for_session("Zorblax");
+        assert!(checker.check_word("zorblax"));
+        assert!(!checker.personal_words.contains("zorblax"));
+    }
+
+    fn misspelling(line: usize, col: usize) -> Misspelling {
+        Misspelling {
+            word: "tset".to_string(),
+            start: col,
+            end: col + 4,
+            line,
+            col,
+        }
+    }
+
+    #[test]
+    fn test_next_and_prev_misspelling_wrap() {
+        let result = SpellCheckResult {
+            misspellings: vec![misspelling(0, 5), misspelling(2, 0), misspelling(2, 10)],
+        };
+
+        assert_eq!(result.next_after(0, 0), Some(&misspelling(0, 5)));
+        assert_eq!(result.next_after(0, 5), Some(&misspelling(2, 0)));
+        assert_eq!(result.next_after(2, 10), Some(&misspelling(0, 5)));
+
+        assert_eq!(result.prev_before(2, 10), Some(&misspelling(2, 0)));
+        assert_eq!(result.prev_before(0, 5), Some(&misspelling(2, 10)));
+
+        assert_eq!(result.first_from(2, 0), Some(&misspelling(2, 0)));
+        assert_eq!(result.first_from(2, 11), None);
+    }
+
+    fn words<'a>(line: &'a str, skip: &SkipRules) -> Vec<&'a str> {
+        candidate_words(line, 0..line.len(), skip)
+            .into_iter()
+            .map(|r| &line[r])
+            .collect()
+    }
+
+    #[test]
+    fn test_candidate_words_skip_rules() {
+        let line = "Ask NASA about mp3 files at me@example.com or ping @sam via https://x.io/page";
+        assert_eq!(
+            words(line, &SkipRules::default()),
+            vec!["Ask", "about", "files", "at", "or", "ping", "via"]
+        );
+
+        let none = SkipRules { all_caps: false, digits: false, emails: false, mentions: false };
+        assert_eq!(
+            words(line, &none),
+            vec!["Ask", "NASA", "about", "mp", "files", "at", "me", "example", "com", "or", "ping", "sam", "via"]
+        );
+    }
+
+    #[test]
+    fn test_prose_ranges_skip_markdown_structure() {
+        let text = "---\ntitle: Drafft\n---\n\nSome `codde` and [a link](http://exmple.com).\n\n```\nfn mian() {}\n```\n\n<div class=\"nte\">\n\n[ref]: http://refrence.org\n\nSee <https://autolnk.io> now.\n";
+        let prose: Vec<&str> = prose_ranges(text).into_iter().map(|r| &text[r]).collect();
+        let joined = prose.join("|");
+
+        assert!(joined.contains("Some "));
+        assert!(joined.contains("a link"));
+        assert!(joined.contains("See "));
+        for skipped in ["Drafft", "codde", "exmple", "mian", "nte", "refrence", "autolnk"] {
+            assert!(!joined.contains(skipped), "{} was treated as prose", skipped);
+        }
+    }
+
+    #[test]
+    fn test_inline_words() {
+        let text = "Kael rode out.\n<!-- hollow-spell: Kael, Veyra -->\n<!--hollow-spell: Ostmark-->";
+        let words = inline_words(text);
+        assert!(words.contains("kael"));
+        assert!(words.contains("veyra"));
+        assert!(words.contains("ostmark"));
+        assert_eq!(words.len(), 3);
+    }
+
+    #[test]
+    fn test_inline_word_edit() {
+        let text = "Text.\n<!-- hollow-spell: Kael -->\n";
+        let (pos, insert) = inline_word_edit(text, "Veyra");
+        let mut edited = text.to_string();
+        edited.insert_str(pos, &insert);
+        assert_eq!(edited, "Text.\n<!-- hollow-spell: Kael Veyra -->\n");
+
+        let (pos, insert) = inline_word_edit("No comment yet", "Kael");
+        assert_eq!(pos, 14);
+        assert_eq!(insert, "\n\n<!-- hollow-spell: Kael -->\n");
+    }
+
+    #[test]
+    fn test_project_and_custom_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let custom = dir.path().join("names.dic");
+        fs::write(&custom, "2\nKael/S\nVeyra\n").unwrap();
+        let project = dir.path().join(".hollow-dict");
+
+        let mut checker = SpellChecker::new("en_US");
+        checker.set_custom_dict(custom.to_str());
+        checker.set_project_dict(Some(project.clone()));
+        assert!(checker.custom_words.contains("kael"));
+        assert!(checker.custom_words.contains("veyra"));
+        assert!(!checker.custom_words.contains("2"));
+        assert_eq!(
+            checker.writable_layers(),
+            vec![DictLayer::Personal, DictLayer::Custom, DictLayer::Project, DictLayer::Document]
+        );
+
+        checker.add_word("Ostmark", DictLayer::Project).unwrap();
+        assert_eq!(fs::read_to_string(&project).unwrap(), "ostmark\n");
+        checker.add_word("Brann", DictLayer::Custom).unwrap();
+        assert!(fs::read_to_string(&custom).unwrap().ends_with("Veyra\nBrann\n"));
+    }
+
+    #[test]
+    fn test_document_languages() {
+        assert_eq!(document_languages("---\nlang: de-DE\n---\nText"), vec!["de_DE"]);
+        assert_eq!(
+            document_languages("---\nlang: en_US, de_DE\n---\n"),
+            vec!["en_US", "de_DE"]
+        );
+        assert_eq!(
+            document_languages("---\nlang: [en_US, de_DE]\n---\n"),
+            vec!["en_US", "de_DE"]
+        );
+        assert!(document_languages("No front matter").is_empty());
+    }
+
+    #[test]
+    fn test_next_languages_cycles() {
+        let known = vec!["en_US".to_string(), "de_DE".to_string()];
+        let both = known.clone();
+        let en = vec!["en_US".to_string()];
+        let de = vec!["de_DE".to_string()];
+
+        assert_eq!(next_languages(&known, &both), en);
+        assert_eq!(next_languages(&known, &en), de);
+        assert_eq!(next_languages(&known, &de), both);
+        // A single language has nothing to cycle to
+        assert_eq!(next_languages(&en, &en), en);
+    }
+
+    #[test]
+    fn test_multiple_languages_without_dicts() {
+        let mut checker = SpellChecker::new("xx_NONE");
+        checker.set_languages(&["xx_NONE".to_string(), "yy_NONE".to_string()]);
+        assert_eq!(checker.languages(), ["xx_NONE", "yy_NONE"]);
+        assert!(!checker.is_available());
+        assert!(checker.check_word("anything"));
+    }
+
+    #[test]
+    fn test_bundled_fallback_and_search_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let checker = SpellChecker::with_dictionary_path("en_US", Some(dir.path().to_path_buf()));
+
+        let report = checker.reports().next().unwrap();
+        // The explicit path is tried first
+        assert_eq!(report.tried[0], (dir.path().join("en_US.dic"), false));
+
+        // With nothing on the search path the bundled list is used
+        let loaded = SpellChecker::load_first("en_US", Vec::new());
+        assert_eq!(loaded.report.source, Some(DictSource::Bundled));
+        let mut checker = SpellChecker::new("xx_NONE");
+        checker.dictionaries = vec![loaded];
+        checker.set_languages(&["en_US".to_string()]);
+        assert!(checker.check_word("writing"));
+        assert!(checker.check_word("Happiest"));
+        assert!(!checker.check_word("wrting"));
+    }
+
+    #[test]
+    fn test_bundled_word_list_parses() {
+        let dict = Dictionary::new(BUNDLED_EN_US_AFF, BUNDLED_EN_US_DIC).unwrap();
+        for word in ["cats", "boxes", "cities", "walked", "making", "seeing", "quickly", "kindness", "don't"] {
+            assert!(dict.check(word), "{} should be accepted", word);
+        }
+        // Affixes are given per word, so function words and irregular verbs don't take them
+        for word in ["teh", "ofs", "anded", "isly", "thes", "stoped", "begined", "advices", "quicklyness"] {
+            assert!(!dict.check(word), "{} should be rejected", word);
+        }
+    }
+
     #[test]
     fn test_suggest_without_dict() {
         let mut checker = SpellChecker::new("en_US");
-        checker.dictionary = None;
+        checker.dictionaries.clear();
         let suggestions = checker.suggest("tset");
         assert!(suggestions.is_empty());
     }
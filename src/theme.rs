@@ -1,9 +1,12 @@
 //! Theme configuration for Hollow
 //!
 //! Provides customizable color themes for the editor.
+//! Themes are either built-in presets or TOML files in ~/.config/hollow/themes/.
 
 use ratatui::style::Color;
 use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// A color theme for the editor
 #[derive(Debug, Clone, Deserialize)]
@@ -51,43 +54,221 @@ pub struct Theme {
     /// Border color
     #[serde(default = "default_border")]
     pub border: ThemeColor,
+
+    /// Markdown heading text (drawn bold)
+    #[serde(default = "default_heading")]
+    pub heading: ThemeColor,
+
+    /// Markdown markers: `#`, `*`, list bullets, link brackets
+    #[serde(default = "default_markup")]
+    pub markup: ThemeColor,
+
+    /// Code spans and fenced code blocks
+    #[serde(default = "default_code")]
+    pub code: ThemeColor,
+
+    /// Link text
+    #[serde(default = "default_link")]
+    pub link: ThemeColor,
+
+    /// Link destinations
+    #[serde(default = "default_url")]
+    pub url: ThemeColor,
+
+    /// Block quote bars
+    #[serde(default = "default_quote")]
+    pub quote: ThemeColor,
 }
 
-/// Color representation that can be RGB or named
-#[derive(Debug, Clone, Deserialize)]
+/// Color representation that can be RGB, an ANSI index, or a string
+///
+/// Strings may be a color name, `#rrggbb`, `#rgb` or `ansi:N`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(untagged)]
 pub enum ThemeColor {
     /// RGB color as [r, g, b]
     Rgb([u8; 3]),
-    /// Named color (e.g., "white", "black", "reset")
+    /// ANSI 256-color palette index
+    Indexed(u8),
+    /// Named or hex color (e.g., "white", "#ff8800", "ansi:208")
     Named(String),
 }
 
+/// Names accepted for the 16 standard terminal colors, in ANSI index order
+const ANSI_NAMES: [&str; 16] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "gray",
+    "darkgray", "lightred", "lightgreen", "lightyellow", "lightblue", "lightmagenta", "lightcyan", "white",
+];
+
+/// Approximate RGB values of the 16 standard colors (xterm defaults)
+const ANSI_RGB: [[u8; 3]; 16] = [
+    [0, 0, 0], [205, 0, 0], [0, 205, 0], [205, 205, 0],
+    [0, 0, 238], [205, 0, 205], [0, 205, 205], [229, 229, 229],
+    [127, 127, 127], [255, 0, 0], [0, 255, 0], [255, 255, 0],
+    [92, 92, 255], [255, 0, 255], [0, 255, 255], [255, 255, 255],
+];
+
+/// Channel levels of the 6x6x6 color cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
 impl ThemeColor {
     /// Convert to ratatui Color
+    ///
+    /// Invalid strings map to `Color::Reset`; use `parse` to detect them.
     pub fn to_color(&self) -> Color {
+        self.parse().unwrap_or(Color::Reset)
+    }
+
+    /// Parse into a ratatui Color, describing the problem if the value is invalid
+    pub fn parse(&self) -> Result<Color, String> {
         match self {
-            ThemeColor::Rgb([r, g, b]) => Color::Rgb(*r, *g, *b),
-            ThemeColor::Named(name) => match name.to_lowercase().as_str() {
-                "black" => Color::Black,
-                "white" => Color::White,
-                "red" => Color::Red,
-                "green" => Color::Green,
-                "yellow" => Color::Yellow,
-                "blue" => Color::Blue,
-                "magenta" => Color::Magenta,
-                "cyan" => Color::Cyan,
-                "gray" | "grey" => Color::Gray,
-                "darkgray" | "darkgrey" => Color::DarkGray,
-                "lightred" => Color::LightRed,
-                "lightgreen" => Color::LightGreen,
-                "lightyellow" => Color::LightYellow,
-                "lightblue" => Color::LightBlue,
-                "lightmagenta" => Color::LightMagenta,
-                "lightcyan" => Color::LightCyan,
-                "reset" | "default" => Color::Reset,
-                _ => Color::Reset,
-            },
+            ThemeColor::Rgb([r, g, b]) => Ok(Color::Rgb(*r, *g, *b)),
+            ThemeColor::Indexed(i) => Ok(Color::Indexed(*i)),
+            ThemeColor::Named(name) => parse_color_str(name),
+        }
+    }
+
+    /// Reduce the color to what the terminal can display
+    pub fn for_support(&self, support: ColorSupport) -> ThemeColor {
+        let color = match self.parse() {
+            Ok(c) => c,
+            Err(_) => return self.clone(),
+        };
+        match (support, color) {
+            (ColorSupport::TrueColor, _) => self.clone(),
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => ThemeColor::Indexed(nearest_256([r, g, b])),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => {
+                ThemeColor::Named(ANSI_NAMES[nearest_16([r, g, b])].to_string())
+            }
+            (ColorSupport::Ansi16, Color::Indexed(i)) if i >= 16 => {
+                ThemeColor::Named(ANSI_NAMES[nearest_16(index_to_rgb(i))].to_string())
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Parse a color string: a name, `#rrggbb`, `#rgb` or `ansi:N`
+fn parse_color_str(value: &str) -> Result<Color, String> {
+    let lower = value.trim().to_lowercase();
+
+    if let Some(hex) = lower.strip_prefix('#') {
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("invalid hex color '{}'", value))?;
+        return match digits.as_slice() {
+            [r, g, b] => Ok(Color::Rgb(r * 17, g * 17, b * 17)),
+            [r1, r2, g1, g2, b1, b2] => Ok(Color::Rgb(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2)),
+            _ => Err(format!("hex color '{}' must have 3 or 6 digits", value)),
+        };
+    }
+
+    if let Some(index) = lower.strip_prefix("ansi:") {
+        return index
+            .parse::<u8>()
+            .map(Color::Indexed)
+            .map_err(|_| format!("ANSI color index in '{}' must be 0-255", value));
+    }
+
+    match lower.as_str() {
+        "grey" => Ok(Color::Gray),
+        "darkgrey" => Ok(Color::DarkGray),
+        "reset" | "default" => Ok(Color::Reset),
+        name => ANSI_NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|i| named_color(i as u8))
+            .ok_or_else(|| format!("unknown color name '{}'", value)),
+    }
+}
+
+/// The ratatui color for one of the 16 standard colors
+fn named_color(index: u8) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::Gray,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (*x as i32 - *y as i32).pow(2) as u32)
+        .sum()
+}
+
+/// RGB value of a 256-color palette index
+fn index_to_rgb(index: u8) -> [u8; 3] {
+    match index {
+        0..=15 => ANSI_RGB[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            [CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize]]
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            [level, level, level]
+        }
+    }
+}
+
+/// Nearest entry in the 256-color palette (cube or grayscale ramp)
+fn nearest_256(rgb: [u8; 3]) -> u8 {
+    (16..=255u8)
+        .min_by_key(|i| distance(rgb, index_to_rgb(*i)))
+        .unwrap_or(16)
+}
+
+/// Index of the nearest of the 16 standard colors
+fn nearest_16(rgb: [u8; 3]) -> usize {
+    (0..16)
+        .min_by_key(|i| distance(rgb, ANSI_RGB[*i]))
+        .unwrap_or(0)
+}
+
+/// How many colors the terminal can display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorSupport {
+    /// Detect color support from COLORTERM and TERM
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+        Self::from_env(colorterm.as_deref(), term.as_deref())
+    }
+
+    /// Determine color support from COLORTERM and TERM values
+    pub fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        let colorterm = colorterm.unwrap_or("").to_lowercase();
+        let term = term.unwrap_or("").to_lowercase();
+
+        if colorterm == "truecolor" || colorterm == "24bit" || term.contains("direct") {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
         }
     }
 }
@@ -137,6 +318,30 @@ fn default_border() -> ThemeColor {
     ThemeColor::Named("gray".to_string())
 }
 
+fn default_heading() -> ThemeColor {
+    ThemeColor::Named("white".to_string())
+}
+
+fn default_markup() -> ThemeColor {
+    ThemeColor::Named("darkgray".to_string())
+}
+
+fn default_code() -> ThemeColor {
+    ThemeColor::Named("gray".to_string())
+}
+
+fn default_link() -> ThemeColor {
+    ThemeColor::Named("cyan".to_string())
+}
+
+fn default_url() -> ThemeColor {
+    ThemeColor::Named("darkgray".to_string())
+}
+
+fn default_quote() -> ThemeColor {
+    ThemeColor::Named("gray".to_string())
+}
+
 impl Default for Theme {
     fn default() -> Self {
         Self::dark()
@@ -158,6 +363,12 @@ impl Theme {
             success: ThemeColor::Named("green".to_string()),
             warning: ThemeColor::Named("yellow".to_string()),
             border: ThemeColor::Named("gray".to_string()),
+            heading: ThemeColor::Named("white".to_string()),
+            markup: ThemeColor::Named("darkgray".to_string()),
+            code: ThemeColor::Named("gray".to_string()),
+            link: ThemeColor::Named("cyan".to_string()),
+            url: ThemeColor::Named("darkgray".to_string()),
+            quote: ThemeColor::Named("gray".to_string()),
         }
     }
 
@@ -175,6 +386,12 @@ impl Theme {
             success: ThemeColor::Rgb([0, 150, 0]),
             warning: ThemeColor::Rgb([200, 150, 0]),
             border: ThemeColor::Rgb([180, 180, 180]),
+            heading: ThemeColor::Rgb([0, 0, 0]),
+            markup: ThemeColor::Rgb([160, 160, 160]),
+            code: ThemeColor::Rgb([120, 120, 120]),
+            link: ThemeColor::Rgb([0, 90, 180]),
+            url: ThemeColor::Rgb([160, 160, 160]),
+            quote: ThemeColor::Rgb([180, 180, 180]),
         }
     }
 
@@ -192,6 +409,12 @@ impl Theme {
             success: ThemeColor::Rgb([100, 140, 80]),
             warning: ThemeColor::Rgb([180, 140, 60]),
             border: ThemeColor::Rgb([180, 160, 130]),
+            heading: ThemeColor::Rgb([60, 40, 20]),
+            markup: ThemeColor::Rgb([170, 150, 125]),
+            code: ThemeColor::Rgb([140, 120, 100]),
+            link: ThemeColor::Rgb([140, 80, 30]),
+            url: ThemeColor::Rgb([170, 150, 125]),
+            quote: ThemeColor::Rgb([180, 160, 130]),
         }
     }
 
@@ -209,6 +432,12 @@ impl Theme {
             success: ThemeColor::Rgb([133, 153, 0]),        // green
             warning: ThemeColor::Rgb([203, 75, 22]),        // orange
             border: ThemeColor::Rgb([88, 110, 117]),        // base01
+            heading: ThemeColor::Rgb([38, 139, 210]),       // blue
+            markup: ThemeColor::Rgb([88, 110, 117]),        // base01
+            code: ThemeColor::Rgb([88, 110, 117]),          // base01
+            link: ThemeColor::Rgb([42, 161, 152]),          // cyan
+            url: ThemeColor::Rgb([88, 110, 117]),           // base01
+            quote: ThemeColor::Rgb([88, 110, 117]),         // base01
         }
     }
 
@@ -223,15 +452,240 @@ impl Theme {
         }
     }
 
+    /// All color roles with their field names
+    fn colors(&self) -> [(&'static str, &ThemeColor); 16] {
+        [
+            ("background", &self.background),
+            ("text", &self.text),
+            ("dim", &self.dim),
+            ("cursor", &self.cursor),
+            ("status_bg", &self.status_bg),
+            ("status_text", &self.status_text),
+            ("highlight", &self.highlight),
+            ("success", &self.success),
+            ("warning", &self.warning),
+            ("border", &self.border),
+            ("heading", &self.heading),
+            ("markup", &self.markup),
+            ("code", &self.code),
+            ("link", &self.link),
+            ("url", &self.url),
+            ("quote", &self.quote),
+        ]
+    }
+
+    /// Check that every color parses, naming the first invalid field
+    pub fn validate(&self) -> Result<(), ThemeError> {
+        for (field, color) in self.colors() {
+            if let Err(reason) = color.parse() {
+                return Err(ThemeError::InvalidColor {
+                    field: field.to_string(),
+                    reason,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Reduce every color to what the terminal can display
+    pub fn for_terminal(mut self, support: ColorSupport) -> Self {
+        for color in [
+            &mut self.background,
+            &mut self.text,
+            &mut self.dim,
+            &mut self.cursor,
+            &mut self.status_bg,
+            &mut self.status_text,
+            &mut self.highlight,
+            &mut self.success,
+            &mut self.warning,
+            &mut self.border,
+            &mut self.heading,
+            &mut self.markup,
+            &mut self.code,
+            &mut self.link,
+            &mut self.url,
+            &mut self.quote,
+        ] {
+            *color = color.for_support(support);
+        }
+        self
+    }
+
     /// List available preset themes
     pub fn presets() -> &'static [&'static str] {
         &["dark", "light", "sepia", "solarized"]
     }
+
+    /// Get the user theme directory (~/.config/hollow/themes)
+    pub fn themes_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|p| p.join("hollow").join("themes"))
+    }
+
+    /// Load a theme by name, checking theme files before presets
+    pub fn load(name: &str) -> Result<Self, ThemeError> {
+        match Self::themes_dir() {
+            Some(dir) => Self::load_from(&dir, name),
+            None => Self::from_name(name).ok_or_else(|| ThemeError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Load a theme by name from the given theme directory
+    pub fn load_from(dir: &Path, name: &str) -> Result<Self, ThemeError> {
+        // A name is a file in `dir`, never a path out of it
+        if name.contains(['/', '\\']) {
+            return Err(ThemeError::InvalidName(name.to_string()));
+        }
+        let path = dir.join(format!("{}.toml", name));
+        if path.exists() {
+            return Self::load_file(&path);
+        }
+        Self::from_name(name).ok_or_else(|| ThemeError::NotFound(name.to_string()))
+    }
+
+    /// Load a theme from a TOML file
+    ///
+    /// Colors missing from the file come from the preset named by `inherits`
+    /// (dark if not set).
+    pub fn load_file(path: &Path) -> Result<Self, ThemeError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ThemeError::Io(format!("{}: {}", path.display(), e)))?;
+        let file: ThemeFile = toml::from_str(&content)
+            .map_err(|e| ThemeError::Parse(format!("{}: {}", path.display(), e)))?;
+
+        let fallback_name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("custom");
+        let theme = file.resolve(fallback_name)?;
+        theme.validate().map_err(|e| match e {
+            ThemeError::InvalidColor { field, reason } => ThemeError::InvalidColor {
+                field,
+                reason: format!("{} in {}", reason, path.display()),
+            },
+            e => e,
+        })?;
+        Ok(theme)
+    }
+
+    /// List all available themes (presets and theme files), sorted by name
+    pub fn available() -> Vec<String> {
+        match Self::themes_dir() {
+            Some(dir) => Self::available_in(&dir),
+            None => Self::presets().iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// List presets plus theme files found in the given directory
+    pub fn available_in(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = Self::presets().iter().map(|s| s.to_string()).collect();
+
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("toml") {
+                    continue;
+                }
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
 }
 
+/// A theme file on disk; every color is optional and falls back to the inherited preset
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ThemeFile {
+    name: Option<String>,
+    /// Preset to take unspecified colors from
+    inherits: Option<String>,
+    background: Option<ThemeColor>,
+    text: Option<ThemeColor>,
+    dim: Option<ThemeColor>,
+    cursor: Option<ThemeColor>,
+    status_bg: Option<ThemeColor>,
+    status_text: Option<ThemeColor>,
+    highlight: Option<ThemeColor>,
+    success: Option<ThemeColor>,
+    warning: Option<ThemeColor>,
+    border: Option<ThemeColor>,
+    heading: Option<ThemeColor>,
+    markup: Option<ThemeColor>,
+    code: Option<ThemeColor>,
+    link: Option<ThemeColor>,
+    url: Option<ThemeColor>,
+    quote: Option<ThemeColor>,
+}
+
+impl ThemeFile {
+    /// Merge the file's colors over its base preset
+    fn resolve(self, fallback_name: &str) -> Result<Theme, ThemeError> {
+        let mut theme = match self.inherits {
+            Some(ref preset) => Theme::from_name(preset)
+                .ok_or_else(|| ThemeError::NotFound(preset.clone()))?,
+            None => Theme::dark(),
+        };
+
+        theme.name = self.name.unwrap_or_else(|| fallback_name.to_string());
+        if let Some(c) = self.background { theme.background = c; }
+        if let Some(c) = self.text { theme.text = c; }
+        if let Some(c) = self.dim { theme.dim = c; }
+        if let Some(c) = self.cursor { theme.cursor = c; }
+        if let Some(c) = self.status_bg { theme.status_bg = c; }
+        if let Some(c) = self.status_text { theme.status_text = c; }
+        if let Some(c) = self.highlight { theme.highlight = c; }
+        if let Some(c) = self.success { theme.success = c; }
+        if let Some(c) = self.warning { theme.warning = c; }
+        if let Some(c) = self.border { theme.border = c; }
+        if let Some(c) = self.heading { theme.heading = c; }
+        if let Some(c) = self.markup { theme.markup = c; }
+        if let Some(c) = self.code { theme.code = c; }
+        if let Some(c) = self.link { theme.link = c; }
+        if let Some(c) = self.url { theme.url = c; }
+        if let Some(c) = self.quote { theme.quote = c; }
+
+        Ok(theme)
+    }
+}
+
+/// Theme-related errors
+#[derive(Debug)]
+pub enum ThemeError {
+    /// IO error
+    Io(String),
+    /// Parse error
+    Parse(String),
+    /// No preset or theme file with this name
+    NotFound(String),
+    /// A name that is a path rather than a theme name
+    InvalidName(String),
+    /// A color value that could not be parsed
+    InvalidColor { field: String, reason: String },
+}
+
+impl std::fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "IO error: {}", e),
+            Self::Parse(e) => write!(f, "Parse error: {}", e),
+            Self::NotFound(name) => write!(f, "Unknown theme: {}", name),
+            Self::InvalidName(name) => write!(f, "Invalid theme name: {} (names can't contain path separators)", name),
+            Self::InvalidColor { field, reason } => write!(f, "invalid color for `{}`: {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
 
     #[test]
     fn test_dark_theme() {
@@ -315,6 +769,87 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_hex_colors() {
+        let color = ThemeColor::Named("#ff8000".to_string());
+        assert!(matches!(color.to_color(), Color::Rgb(255, 128, 0)));
+        let color = ThemeColor::Named("#FA0".to_string());
+        assert!(matches!(color.to_color(), Color::Rgb(255, 170, 0)));
+        assert!(ThemeColor::Named("#12345".to_string()).parse().is_err());
+        assert!(ThemeColor::Named("#zzzzzz".to_string()).parse().is_err());
+    }
+
+    #[test]
+    fn test_ansi_index_colors() {
+        assert!(matches!(ThemeColor::Indexed(208).to_color(), Color::Indexed(208)));
+        assert!(matches!(ThemeColor::Named("ansi:42".to_string()).to_color(), Color::Indexed(42)));
+        assert!(ThemeColor::Named("ansi:256".to_string()).parse().is_err());
+    }
+
+    #[test]
+    fn test_parse_colors_from_toml() {
+        let theme: Theme = toml::from_str(
+            "text = \"#102030\"\ndim = 244\ncursor = \"ansi:1\"\nborder = [1, 2, 3]\n",
+        ).unwrap();
+        assert!(matches!(theme.text.to_color(), Color::Rgb(16, 32, 48)));
+        assert!(matches!(theme.dim.to_color(), Color::Indexed(244)));
+        assert!(matches!(theme.cursor.to_color(), Color::Indexed(1)));
+        assert!(matches!(theme.border.to_color(), Color::Rgb(1, 2, 3)));
+    }
+
+    #[test]
+    fn test_validate_names_field() {
+        let mut theme = Theme::dark();
+        assert!(theme.validate().is_ok());
+
+        theme.warning = ThemeColor::Named("notacolor".to_string());
+        let err = theme.validate().unwrap_err();
+        assert!(matches!(err, ThemeError::InvalidColor { ref field, .. } if field == "warning"));
+        assert!(err.to_string().contains("notacolor"));
+    }
+
+    #[test]
+    fn test_detect_color_support() {
+        assert_eq!(ColorSupport::from_env(Some("truecolor"), Some("xterm")), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::from_env(Some("24bit"), None), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::from_env(None, Some("xterm-256color")), ColorSupport::Ansi256);
+        assert_eq!(ColorSupport::from_env(None, Some("xterm")), ColorSupport::Ansi16);
+        assert_eq!(ColorSupport::from_env(None, None), ColorSupport::Ansi16);
+    }
+
+    #[test]
+    fn test_rgb_downsampling() {
+        let color = ThemeColor::Rgb([255, 0, 0]);
+        assert_eq!(color.for_support(ColorSupport::TrueColor), color);
+        assert_eq!(color.for_support(ColorSupport::Ansi256), ThemeColor::Indexed(196));
+        assert_eq!(color.for_support(ColorSupport::Ansi16), ThemeColor::Named("lightred".to_string()));
+
+        // Grays use the grayscale ramp
+        assert_eq!(ThemeColor::Rgb([128, 128, 128]).for_support(ColorSupport::Ansi256), ThemeColor::Indexed(244));
+        // High palette indexes fall back to the 16 standard colors
+        assert_eq!(ThemeColor::Indexed(16).for_support(ColorSupport::Ansi16), ThemeColor::Named("black".to_string()));
+        // Named colors are left alone
+        let named = ThemeColor::Named("cyan".to_string());
+        assert_eq!(named.for_support(ColorSupport::Ansi16), named);
+    }
+
+    #[test]
+    fn test_theme_for_terminal() {
+        let theme = Theme::sepia().for_terminal(ColorSupport::Ansi256);
+        assert!(matches!(theme.background.to_color(), Color::Indexed(_)));
+        assert!(matches!(theme.text.to_color(), Color::Indexed(_)));
+    }
+
+    #[test]
+    fn test_theme_file_invalid_color() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("oops.toml"), "highlight = \"yelow\"\n").unwrap();
+
+        let err = Theme::load_from(dir.path(), "oops").unwrap_err();
+        assert!(err.to_string().contains("`highlight`"));
+        assert!(err.to_string().contains("oops.toml"));
+    }
+
     #[test]
     fn test_unknown_named_color_defaults_to_reset() {
         let color = ThemeColor::Named("notacolor".to_string());
@@ -326,4 +861,74 @@ mod tests {
         let theme = Theme::default();
         assert_eq!(theme.name, "dark");
     }
+
+    #[test]
+    fn test_theme_file_inherits_preset() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("paper.toml"),
+            "inherits = \"sepia\"\ntext = [10, 20, 30]\n",
+        ).unwrap();
+
+        let theme = Theme::load_from(dir.path(), "paper").unwrap();
+        assert_eq!(theme.name, "paper");
+        assert!(matches!(theme.text.to_color(), Color::Rgb(10, 20, 30)));
+        // Unspecified colors come from sepia
+        assert!(matches!(theme.background.to_color(), Color::Rgb(250, 240, 220)));
+    }
+
+    #[test]
+    fn test_theme_file_without_inherits_uses_dark() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("mine.toml"),
+            "name = \"My Theme\"\ncursor = \"red\"\n",
+        ).unwrap();
+
+        let theme = Theme::load_from(dir.path(), "mine").unwrap();
+        assert_eq!(theme.name, "My Theme");
+        assert!(matches!(theme.cursor.to_color(), Color::Red));
+        assert!(matches!(theme.text.to_color(), Color::White));
+    }
+
+    #[test]
+    fn test_theme_file_unknown_parent() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("bad.toml"), "inherits = \"neon\"\n").unwrap();
+
+        let result = Theme::load_from(dir.path(), "bad");
+        assert!(matches!(result, Err(ThemeError::NotFound(ref n)) if n == "neon"));
+    }
+
+    #[test]
+    fn test_load_from_falls_back_to_preset() {
+        let dir = tempdir().unwrap();
+        assert_eq!(Theme::load_from(dir.path(), "solarized").unwrap().name, "solarized");
+        assert!(Theme::load_from(dir.path(), "missing").is_err());
+    }
+
+    #[test]
+    fn test_load_from_rejects_paths() {
+        let dir = tempdir().unwrap();
+        let themes = dir.path().join("themes");
+        fs::create_dir(&themes).unwrap();
+        fs::write(dir.path().join("outside.toml"), "cursor = \"red\"\n").unwrap();
+
+        for name in ["../outside", "..\\outside", "/etc/x"] {
+            let result = Theme::load_from(&themes, name);
+            assert!(matches!(result, Err(ThemeError::InvalidName(ref n)) if n == name));
+        }
+    }
+
+    #[test]
+    fn test_available_includes_theme_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("night.toml"), "").unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        let names = Theme::available_in(dir.path());
+        assert!(names.contains(&"night".to_string()));
+        assert!(names.contains(&"dark".to_string()));
+        assert!(!names.contains(&"notes".to_string()));
+    }
 }
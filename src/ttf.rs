@@ -0,0 +1,381 @@
+//! Just enough TrueType to lay out text and embed fonts in PDFs
+//!
+//! Reads glyph mapping and metrics, and writes subsets that keep only the
+//! glyphs a document uses. Glyph ids are left unchanged in a subset, so
+//! text can be written with the original ids.
+
+use std::collections::BTreeSet;
+
+/// Tables kept in a subset: what PDF viewers need, plus the character map
+const SUBSET_TABLES: &[&[u8; 4]] = &[b"cmap", b"cvt ", b"fpgm", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"prep"];
+
+/// Font parsing errors
+#[derive(Debug)]
+pub enum FontError {
+    /// A required table is missing
+    MissingTable(String),
+    /// The data is truncated or malformed
+    Malformed(String),
+}
+
+impl std::fmt::Display for FontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontError::MissingTable(tag) => write!(f, "font has no {} table", tag),
+            FontError::Malformed(msg) => write!(f, "malformed font: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// A parsed TrueType font
+pub struct Font<'a> {
+    data: &'a [u8],
+    /// (tag, offset, length) of each table
+    tables: Vec<([u8; 4], usize, usize)>,
+    pub units_per_em: u16,
+    pub ascent: i16,
+    pub descent: i16,
+    /// xMin, yMin, xMax, yMax
+    pub bbox: [i16; 4],
+    /// Italic angle in degrees
+    pub italic_angle: f32,
+    num_glyphs: u16,
+    num_h_metrics: u16,
+    long_loca: bool,
+    cmap: Cmap,
+}
+
+/// Location of the character map subtable in use
+enum Cmap {
+    Format4(usize),
+    Format12(usize),
+}
+
+impl<'a> Font<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, FontError> {
+        let num_tables = read_u16(data, 4)? as usize;
+        let mut tables = Vec::with_capacity(num_tables);
+        for i in 0..num_tables {
+            let record = 12 + i * 16;
+            let tag: [u8; 4] = data
+                .get(record..record + 4)
+                .and_then(|t| t.try_into().ok())
+                .ok_or_else(|| FontError::Malformed("table directory".to_string()))?;
+            let offset = read_u32(data, record + 8)? as usize;
+            let length = read_u32(data, record + 12)? as usize;
+            if offset + length > data.len() {
+                return Err(FontError::Malformed(format!("{} table", String::from_utf8_lossy(&tag))));
+            }
+            tables.push((tag, offset, length));
+        }
+
+        let find = |tag: &[u8; 4]| {
+            tables
+                .iter()
+                .find(|(t, _, _)| t == tag)
+                .map(|(_, offset, _)| *offset)
+                .ok_or_else(|| FontError::MissingTable(String::from_utf8_lossy(tag).into_owned()))
+        };
+        let head = find(b"head")?;
+        let hhea = find(b"hhea")?;
+        let maxp = find(b"maxp")?;
+        let cmap = find(b"cmap")?;
+        let italic_angle = match find(b"post") {
+            Ok(post) => read_u32(data, post + 4)? as i32 as f32 / 65536.0,
+            Err(_) => 0.0,
+        };
+
+        Ok(Self {
+            units_per_em: read_u16(data, head + 18)?,
+            bbox: [
+                read_u16(data, head + 36)? as i16,
+                read_u16(data, head + 38)? as i16,
+                read_u16(data, head + 40)? as i16,
+                read_u16(data, head + 42)? as i16,
+            ],
+            long_loca: read_u16(data, head + 50)? == 1,
+            ascent: read_u16(data, hhea + 4)? as i16,
+            descent: read_u16(data, hhea + 6)? as i16,
+            num_h_metrics: read_u16(data, hhea + 34)?,
+            num_glyphs: read_u16(data, maxp + 4)?,
+            italic_angle,
+            cmap: find_cmap(data, cmap)?,
+            data,
+            tables,
+        })
+    }
+
+    fn table(&self, tag: &[u8; 4]) -> Option<&'a [u8]> {
+        self.tables
+            .iter()
+            .find(|(t, _, _)| t == tag)
+            .map(|(_, offset, length)| &self.data[*offset..*offset + *length])
+    }
+
+    /// Glyph id for a character, 0 (the missing glyph) if the font lacks it
+    pub fn glyph_id(&self, c: char) -> u16 {
+        let c = c as u32;
+        let data = self.data;
+        match self.cmap {
+            Cmap::Format4(table) => {
+                if c > 0xFFFF {
+                    return 0;
+                }
+                let seg_count = read_u16(data, table + 6).unwrap_or(0) as usize / 2;
+                let ends = table + 14;
+                let starts = ends + seg_count * 2 + 2;
+                let deltas = starts + seg_count * 2;
+                let range_offsets = deltas + seg_count * 2;
+                for seg in 0..seg_count {
+                    let end = read_u16(data, ends + seg * 2).unwrap_or(0) as u32;
+                    if c > end {
+                        continue;
+                    }
+                    let start = read_u16(data, starts + seg * 2).unwrap_or(0) as u32;
+                    if c < start {
+                        return 0;
+                    }
+                    let delta = read_u16(data, deltas + seg * 2).unwrap_or(0);
+                    let range_offset_at = range_offsets + seg * 2;
+                    let range_offset = read_u16(data, range_offset_at).unwrap_or(0) as usize;
+                    if range_offset == 0 {
+                        return (c as u16).wrapping_add(delta);
+                    }
+                    let at = range_offset_at + range_offset + (c - start) as usize * 2;
+                    return match read_u16(data, at).unwrap_or(0) {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    };
+                }
+                0
+            }
+            Cmap::Format12(table) => {
+                let groups = read_u32(data, table + 12).unwrap_or(0) as usize;
+                for group in 0..groups {
+                    let at = table + 16 + group * 12;
+                    let start = read_u32(data, at).unwrap_or(0);
+                    let end = read_u32(data, at + 4).unwrap_or(0);
+                    if (start..=end).contains(&c) {
+                        return (read_u32(data, at + 8).unwrap_or(0) + c - start) as u16;
+                    }
+                }
+                0
+            }
+        }
+    }
+
+    /// Advance width of a glyph in font units
+    pub fn advance(&self, glyph: u16) -> u16 {
+        let Some(hmtx) = self.table(b"hmtx") else { return 0 };
+        let index = glyph.min(self.num_h_metrics.saturating_sub(1)) as usize;
+        read_u16(hmtx, index * 4).unwrap_or(0)
+    }
+
+    /// Byte range of a glyph's outline within the glyf table
+    fn glyph_range(&self, glyph: u16) -> Option<(usize, usize)> {
+        let loca = self.table(b"loca")?;
+        let glyph = glyph as usize;
+        if self.long_loca {
+            Some((read_u32(loca, glyph * 4).ok()? as usize, read_u32(loca, glyph * 4 + 4).ok()? as usize))
+        } else {
+            Some((read_u16(loca, glyph * 2).ok()? as usize * 2, read_u16(loca, glyph * 2 + 2).ok()? as usize * 2))
+        }
+    }
+
+    /// Build a font holding only the given glyphs (and the components of
+    /// composite glyphs). Other glyphs are left empty.
+    pub fn subset(&self, glyphs: &BTreeSet<u16>) -> Result<Vec<u8>, FontError> {
+        let glyf = self.table(b"glyf").ok_or_else(|| FontError::MissingTable("glyf".to_string()))?;
+
+        // Glyph 0 is always kept; composite glyphs pull in their components
+        let mut keep: BTreeSet<u16> = BTreeSet::new();
+        let mut pending: Vec<u16> = glyphs.iter().copied().chain([0]).collect();
+        while let Some(glyph) = pending.pop() {
+            if glyph >= self.num_glyphs || !keep.insert(glyph) {
+                continue;
+            }
+            let Some((start, end)) = self.glyph_range(glyph) else { continue };
+            if end > start && end <= glyf.len() {
+                pending.extend(composite_components(&glyf[start..end]));
+            }
+        }
+
+        let mut new_glyf = Vec::new();
+        let mut new_loca = Vec::with_capacity((self.num_glyphs as usize + 1) * 4);
+        for glyph in 0..self.num_glyphs {
+            new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+            if keep.contains(&glyph) {
+                if let Some((start, end)) = self.glyph_range(glyph).filter(|(s, e)| s < e && *e <= glyf.len()) {
+                    new_glyf.extend_from_slice(&glyf[start..end]);
+                    while new_glyf.len() % 4 != 0 {
+                        new_glyf.push(0);
+                    }
+                }
+            }
+        }
+        new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+
+        let mut tables: Vec<(&[u8; 4], Vec<u8>)> = Vec::new();
+        for tag in SUBSET_TABLES {
+            let data = match *tag {
+                b"glyf" => new_glyf.clone(),
+                b"loca" => new_loca.clone(),
+                b"head" => {
+                    let mut head = self.table(tag).ok_or_else(|| FontError::MissingTable("head".to_string()))?.to_vec();
+                    // Whole-font checksum no longer applies; loca is rewritten in long format
+                    head[8..12].copy_from_slice(&[0; 4]);
+                    head[50..52].copy_from_slice(&1u16.to_be_bytes());
+                    head
+                }
+                _ => match self.table(tag) {
+                    Some(table) => table.to_vec(),
+                    None => continue,
+                },
+            };
+            tables.push((tag, data));
+        }
+        Ok(write_font(&tables))
+    }
+}
+
+/// Glyph ids referenced by a composite glyph
+fn composite_components(glyph: &[u8]) -> Vec<u16> {
+    const ARGS_ARE_WORDS: u16 = 0x0001;
+    const HAVE_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const HAVE_XY_SCALE: u16 = 0x0040;
+    const HAVE_2X2: u16 = 0x0080;
+
+    let mut components = Vec::new();
+    if read_u16(glyph, 0).map_or(true, |contours| (contours as i16) >= 0) {
+        return components;
+    }
+    let mut at = 10;
+    while let (Ok(flags), Ok(component)) = (read_u16(glyph, at), read_u16(glyph, at + 2)) {
+        components.push(component);
+        at += 4 + if flags & ARGS_ARE_WORDS != 0 { 4 } else { 2 };
+        at += if flags & HAVE_SCALE != 0 {
+            2
+        } else if flags & HAVE_XY_SCALE != 0 {
+            4
+        } else if flags & HAVE_2X2 != 0 {
+            8
+        } else {
+            0
+        };
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    components
+}
+
+/// Find a Unicode character map: full repertoire (format 12) first, then BMP (format 4)
+fn find_cmap(data: &[u8], cmap: usize) -> Result<Cmap, FontError> {
+    let count = read_u16(data, cmap + 2)? as usize;
+    let mut bmp = None;
+    for i in 0..count {
+        let record = cmap + 4 + i * 8;
+        let platform = read_u16(data, record)?;
+        let encoding = read_u16(data, record + 2)?;
+        let table = cmap + read_u32(data, record + 4)? as usize;
+        let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+        if !unicode {
+            continue;
+        }
+        match read_u16(data, table)? {
+            12 => return Ok(Cmap::Format12(table)),
+            4 => bmp = bmp.or(Some(Cmap::Format4(table))),
+            _ => {}
+        }
+    }
+    bmp.ok_or_else(|| FontError::MissingTable("unicode cmap".to_string()))
+}
+
+/// Assemble a font file from its tables
+fn write_font(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let power = 1u16 << (15 - count.leading_zeros());
+    let mut out = Vec::new();
+    out.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    out.extend_from_slice(&count.to_be_bytes());
+    out.extend_from_slice(&(power * 16).to_be_bytes());
+    out.extend_from_slice(&(15 - power.leading_zeros() as u16).to_be_bytes());
+    out.extend_from_slice(&((count - power) * 16).to_be_bytes());
+
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, data) in tables {
+        out.extend_from_slice(*tag);
+        out.extend_from_slice(&checksum(data).to_be_bytes());
+        out.extend_from_slice(&(offset as u32).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().div_ceil(4) * 4;
+    }
+    for (_, data) in tables {
+        out.extend_from_slice(data);
+        while out.len() % 4 != 0 {
+            out.push(0);
+        }
+    }
+    out
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn read_u16(data: &[u8], at: usize) -> Result<u16, FontError> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| FontError::Malformed(format!("read past end at {}", at)))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32, FontError> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| FontError::Malformed(format!("read past end at {}", at)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERIF: &[u8] = include_bytes!("../fonts/DejaVuSerif.ttf");
+
+    #[test]
+    fn test_metrics_and_mapping() {
+        let font = Font::parse(SERIF).unwrap();
+        assert_eq!(font.units_per_em, 2048);
+        assert!(font.ascent > 0 && font.descent < 0);
+
+        let a = font.glyph_id('A');
+        assert_ne!(a, 0);
+        assert_ne!(font.glyph_id('é'), 0);
+        assert_eq!(font.glyph_id('\u{E000}'), 0);
+        // Narrow and wide letters
+        assert!(font.advance(font.glyph_id('i')) < font.advance(font.glyph_id('M')));
+    }
+
+    #[test]
+    fn test_subset_keeps_used_glyphs() {
+        let font = Font::parse(SERIF).unwrap();
+        let a = font.glyph_id('A');
+        let e_acute = font.glyph_id('é');
+        let subset = font.subset(&BTreeSet::from([a, e_acute])).unwrap();
+        assert!(subset.len() < SERIF.len() / 10);
+
+        let small = Font::parse(&subset).unwrap();
+        assert_eq!(small.advance(a), font.advance(a));
+        let (start, end) = small.glyph_range(a).unwrap();
+        assert!(end > start);
+        // Unused glyphs are empty
+        let (start, end) = small.glyph_range(font.glyph_id('Z')).unwrap();
+        assert_eq!(start, end);
+    }
+}
@@ -1,16 +1,24 @@
 use ratatui::{
+    buffer::Buffer,
     layout::{Alignment, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
 use crate::input::Mode;
-use crate::spell::Misspelling;
+use crate::lint::LintIssue;
+use crate::markdown::{self, MdStyle};
+use crate::project_search::ProjectSearch;
+use crate::spell::{DictLayer, Misspelling, SpellReview};
 use crate::stats::WritingStats;
 use crate::theme::Theme;
 use crate::versions::Version;
+use crate::width;
 
 /// Render state passed to UI
 pub struct RenderState<'a> {
@@ -28,8 +36,20 @@ pub struct RenderState<'a> {
     pub search_active: bool,
     pub search_query: &'a str,
     pub search_matches: &'a [(usize, usize)],
+    pub search_status: &'a str,
+    // Find and replace
+    pub replace_active: bool,
+    pub replace_find: &'a str,
+    pub replace_with: &'a str,
+    pub replace_editing_with: bool,
+    pub replace_confirm: bool,
+    pub current_match: Option<(usize, usize)>,
     pub text_width: usize,
+    pub tab_width: usize,
+    pub line_spacing: usize,
     pub show_saved_indicator: bool,
+    /// A message such as a failed export, shown in place of the counts
+    pub message: Option<&'a str>,
     // Goal tracking
     pub daily_goal: usize,
     pub goal_progress: f64,
@@ -50,12 +70,23 @@ pub struct RenderState<'a> {
     pub project_name: Option<&'a str>,
     pub project_docs: &'a [String],
     pub project_doc_index: usize,
+    pub project_doc_titles: &'a [Option<String>],
+    pub project_search: Option<&'a ProjectSearch>,
     pub current_doc: &'a str,
     // Theme
     pub theme: &'a Theme,
     // Spell checking
     pub spell_enabled: bool,
+    pub spell_languages: &'a [String],
     pub misspellings: &'a [Misspelling],
+    pub spell_review: Option<&'a SpellReview>,
+    // Prose linting
+    pub lint_enabled: bool,
+    pub lint_issues: &'a [LintIssue],
+    pub lint_info: Option<&'a str>,
+    // Dictionary picker for "add to dictionary"
+    pub add_word: Option<&'a str>,
+    pub dictionary_layers: &'a [DictLayer],
     // Spell suggestions popup
     pub show_spell_suggestions: bool,
     pub spell_suggestion_word: &'a str,
@@ -65,8 +96,36 @@ pub struct RenderState<'a> {
 
 const WRAP_INDENT: &str = "  "; // 2 spaces for wrapped line continuation per spec 4.3
 
-/// Wrap a single line at word boundaries with indent for continuation
-fn wrap_line(line: &str, width: usize) -> Vec<String> {
+/// Split a line into wrap units: a word with its trailing spaces, or a single
+/// wide grapheme (CJK, emoji), since text may break on either side of those
+fn wrap_units(line: &str) -> Vec<&str> {
+    let mut units = Vec::new();
+    let mut start = 0;
+    let mut after_space = false;
+    let mut after_wide = false;
+
+    for (i, g) in line.grapheme_indices(true) {
+        if g == " " {
+            after_space = true;
+            continue;
+        }
+        let wide = width::is_wide(g);
+        if i > start && (after_space || after_wide || wide) {
+            units.push(&line[start..i]);
+            start = i;
+        }
+        after_space = false;
+        after_wide = wide;
+    }
+    if start < line.len() {
+        units.push(&line[start..]);
+    }
+    units
+}
+
+/// Wrap a single line at word boundaries with indent for continuation.
+/// Widths are display columns, so wide characters take two.
+fn wrap_line(line: &str, width: usize, tab_width: usize) -> Vec<String> {
     if line.is_empty() {
         return vec![String::new()];
     }
@@ -76,100 +135,126 @@ fn wrap_line(line: &str, width: usize) -> Vec<String> {
         return vec![line.to_string()];
     }
 
-    let mut result = Vec::new();
-    let mut current_line = String::new();
-    let mut is_first = true;
+    let limit = |row: usize| if row == 0 { width } else { effective_width };
+    let mut rows: Vec<String> = Vec::new();
+    let mut current = String::new();
 
-    for word in line.split_inclusive(' ') {
-        let prefix = if is_first { "" } else { WRAP_INDENT };
-        let max_width = if is_first { width } else { effective_width };
-
-        if current_line.is_empty() {
-            current_line = format!("{}{}", prefix, word);
-        } else if current_line.len() + word.len() <= max_width {
-            current_line.push_str(word);
-        } else {
-            // Line is full, start a new one
-            result.push(current_line);
-            is_first = false;
-            current_line = format!("{}{}", WRAP_INDENT, word);
+    for unit in wrap_units(line) {
+        // Trailing spaces may hang past the edge
+        let candidate = format!("{}{}", current, unit.trim_end_matches(' '));
+        let fits = width::str_width(&candidate, tab_width) <= limit(rows.len());
+        if !current.is_empty() && !fits {
+            rows.push(std::mem::take(&mut current));
+        }
+        current.push_str(unit);
+
+        // Break words longer than a whole line at grapheme boundaries
+        while width::str_width(current.trim_end_matches(' '), tab_width) > limit(rows.len()) {
+            let split = width::col_to_byte(&current, limit(rows.len()), tab_width)
+                .max(width::next_boundary(&current, 0));
+            if split >= current.len() {
+                break;
+            }
+            let rest = current.split_off(split);
+            rows.push(std::mem::replace(&mut current, rest));
         }
     }
 
-    if !current_line.is_empty() {
-        result.push(current_line);
-    }
-
-    if result.is_empty() {
-        result.push(String::new());
+    if !current.is_empty() || rows.is_empty() {
+        rows.push(current);
     }
 
-    result
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| if i == 0 { row } else { format!("{}{}", WRAP_INDENT, row) })
+        .collect()
 }
 
 /// Build visual lines from content with word wrapping
-/// Returns (visual_lines, line_map) where line_map[visual_idx] = (logical_line, is_continuation)
-fn build_visual_lines(content: &str, width: usize) -> (Vec<String>, Vec<(usize, bool)>) {
+/// Returns (visual_lines, line_map) where line_map[visual_idx] = (logical_line, is_continuation, offset)
+/// and offset is the byte offset within the logical line where the visual line's text starts.
+/// `line_spacing - 1` blank spacer rows follow the last line of each paragraph.
+fn build_visual_lines(
+    content: &str,
+    width: usize,
+    tab_width: usize,
+    line_spacing: usize,
+) -> (Vec<String>, Vec<(usize, bool, usize)>) {
     let mut visual_lines = Vec::new();
     let mut line_map = Vec::new();
+    let mut lines = content.lines().enumerate().peekable();
 
-    for (logical_idx, line) in content.lines().enumerate() {
-        let wrapped = wrap_line(line, width);
+    while let Some((logical_idx, line)) = lines.next() {
+        let next = lines.peek().map(|&(_, next)| next);
+        let wrapped = wrap_line(line, width, tab_width);
+        let mut offset = 0;
         for (i, wrapped_line) in wrapped.into_iter().enumerate() {
+            let indent = if i > 0 { WRAP_INDENT.len() } else { 0 };
+            line_map.push((logical_idx, i > 0, offset));
+            offset += wrapped_line.len() - indent;
             visual_lines.push(wrapped_line);
-            line_map.push((logical_idx, i > 0));
+        }
+        if ends_paragraph(line, next) {
+            for _ in 1..line_spacing {
+                line_map.push((logical_idx, false, line.len()));
+                visual_lines.push(String::new());
+            }
         }
     }
 
     // Handle empty content
     if visual_lines.is_empty() {
         visual_lines.push(String::new());
-        line_map.push((0, false));
+        line_map.push((0, false, 0));
     }
 
     (visual_lines, line_map)
 }
 
+/// Whether `line` is the last of a paragraph followed by a blank line, where
+/// spacer rows go
+fn ends_paragraph(line: &str, next: Option<&str>) -> bool {
+    !line.trim().is_empty() && next.is_some_and(|next| next.trim().is_empty())
+}
+
 /// Find visual line and column for a logical cursor position
 fn logical_to_visual(
     content: &str,
     logical_line: usize,
     logical_col: usize,
     width: usize,
+    tab_width: usize,
+    line_spacing: usize,
 ) -> (usize, usize) {
     let lines: Vec<&str> = content.lines().collect();
     let mut visual_line = 0;
 
-    // Count visual lines before cursor's logical line
+    // Count visual lines and spacer rows before cursor's logical line
     for (idx, line) in lines.iter().enumerate() {
         if idx == logical_line {
             break;
         }
-        visual_line += wrap_line(line, width).len();
+        visual_line += wrap_line(line, width, tab_width).len();
+        if ends_paragraph(line, lines.get(idx + 1).copied()) {
+            visual_line += line_spacing.saturating_sub(1);
+        }
     }
 
     // Now find position within the wrapped lines of the cursor's logical line
     if logical_line < lines.len() {
         let cursor_line_text = lines[logical_line];
-        let wrapped = wrap_line(cursor_line_text, width);
+        let wrapped = wrap_line(cursor_line_text, width, tab_width);
 
-        let mut remaining_col = logical_col;
+        let mut offset = 0;
         for (i, wrapped_line) in wrapped.iter().enumerate() {
-            let line_len = if i == 0 {
-                wrapped_line.len()
-            } else {
-                wrapped_line.len().saturating_sub(WRAP_INDENT.len())
-            };
+            let indent = if i > 0 { WRAP_INDENT.len() } else { 0 };
+            let text = &wrapped_line[indent..];
 
-            if remaining_col <= line_len || i == wrapped.len() - 1 {
-                let visual_col = if i == 0 {
-                    remaining_col
-                } else {
-                    remaining_col + WRAP_INDENT.len()
-                };
-                return (visual_line + i, visual_col);
+            if logical_col <= offset + text.len() || i == wrapped.len() - 1 {
+                let col_in_row = logical_col.saturating_sub(offset).min(text.len());
+                return (visual_line + i, indent + width::byte_to_col(text, col_in_row, tab_width));
             }
-            remaining_col -= line_len;
+            offset += text.len();
         }
     }
 
@@ -230,9 +315,24 @@ pub fn render(frame: &mut Frame, state: &RenderState) {
             frame, area,
             state.project_name.unwrap_or("Project"),
             state.project_docs,
+            state.project_doc_titles,
             state.project_doc_index,
             state.current_doc,
         );
+    } else if let Some(project_search) = state.project_search {
+        render_project_search_overlay(
+            frame,
+            area,
+            state.project_name.unwrap_or("Project"),
+            project_search,
+            state.search_status,
+        );
+    } else if let Some(word) = state.add_word {
+        render_add_word_overlay(frame, area, word, state.dictionary_layers);
+    } else if let Some(message) = state.lint_info {
+        render_lint_info_overlay(frame, area, message);
+    } else if let Some(review) = state.spell_review {
+        render_spell_review_overlay(frame, area, review);
     } else if state.show_spell_suggestions {
         render_spell_suggestions_overlay(
             frame, area,
@@ -241,7 +341,17 @@ pub fn render(frame: &mut Frame, state: &RenderState) {
             state.spell_suggestion_index,
         );
     } else if state.search_active {
-        render_search_prompt(frame, area, state.search_query);
+        render_search_prompt(frame, area, &format!("/{}", state.search_query), state.search_status);
+    } else if state.replace_active {
+        let (find_mark, with_mark) = if state.replace_editing_with { ("", "_") } else { ("_", "") };
+        let prompt = format!(
+            "Replace: {}{}  With: {}{}",
+            state.replace_find, find_mark, state.replace_with, with_mark
+        );
+        render_search_prompt(frame, area, &prompt, state.search_status);
+    } else if state.replace_confirm {
+        let prompt = format!("Replace with \"{}\"? (y)es (n)o (a)ll (q)uit", state.replace_with);
+        render_search_prompt(frame, area, &prompt, state.search_status);
     }
 
     // Position cursor
@@ -253,7 +363,8 @@ fn render_content(frame: &mut Frame, area: Rect, state: &RenderState) -> (u16, u
     let visible_lines = area.height as usize;
 
     // Build visual lines with word wrapping
-    let (visual_lines, line_map) = build_visual_lines(state.content, width);
+    let (visual_lines, line_map) =
+        build_visual_lines(state.content, width, state.tab_width, state.line_spacing);
 
     // Find cursor visual position
     let (cursor_visual_line, cursor_visual_col) = logical_to_visual(
@@ -261,6 +372,8 @@ fn render_content(frame: &mut Frame, area: Rect, state: &RenderState) -> (u16, u
         state.cursor_line,
         state.cursor_col,
         width,
+        state.tab_width,
+        state.line_spacing,
     );
 
     // Calculate scroll to keep cursor visible
@@ -270,34 +383,99 @@ fn render_content(frame: &mut Frame, area: Rect, state: &RenderState) -> (u16, u
         0
     };
 
-    // Build styled lines with search and spell highlighting
+    // Search matches as byte ranges in the document
+    let match_ranges = char_ranges_to_bytes(state.content, state.search_matches);
+    let current_match = state
+        .current_match
+        .and_then(|m| char_ranges_to_bytes(state.content, &[m]).pop());
+
+    // Markdown styling for the whole document, indexed by byte
+    let md_styles = markdown::style_map(state.content);
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(state.content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+
+    // Build styled lines with markdown, spell and search highlighting
     let display_lines: Vec<Line> = visual_lines
         .iter()
         .enumerate()
         .skip(scroll)
         .take(visible_lines)
         .map(|(idx, line)| {
-            let (logical_line, is_continuation) = line_map.get(idx).copied().unwrap_or((0, false));
+            let (logical_line, is_continuation, offset) = line_map.get(idx).copied().unwrap_or((0, false, 0));
+            let indent = if is_continuation { WRAP_INDENT.len() } else { 0 };
+            let doc_offset = line_starts.get(logical_line).copied().unwrap_or(0) + offset;
+
+            let mut styled = markdown_line(line, indent, doc_offset, &md_styles, state.theme);
+
+            // Underline style problems, then misspellings over them
+            if state.lint_enabled {
+                let segment = offset..offset + line.len() - indent;
+                for issue in state.lint_issues.iter().filter(|i| i.line == logical_line) {
+                    let start = issue.start.max(segment.start);
+                    let end = issue.end.min(segment.end);
+                    if start < end {
+                        styled = patch_range(
+                            styled,
+                            indent + start - offset..indent + end - offset,
+                            Style::default().fg(Color::Magenta).add_modifier(Modifier::UNDERLINED),
+                        );
+                    }
+                }
+            }
 
-            // Start with spell highlighting if enabled
-            let base_line = if state.spell_enabled && !state.misspellings.is_empty() {
-                highlight_misspellings(line, logical_line, is_continuation, state.misspellings)
-            } else {
-                Line::from(line.as_str())
-            };
+            // Underline misspellings that fall on this visual line
+            if state.spell_enabled {
+                let reviewing = state.spell_review.and_then(|r| r.current.as_ref());
+                let segment = offset..offset + line.len() - indent;
+                for m in state.misspellings.iter().filter(|m| m.line == logical_line) {
+                    let start = m.start.max(segment.start);
+                    let end = m.end.min(segment.end);
+                    if start < end {
+                        let style = if reviewing == Some(m) {
+                            // The word under review
+                            Style::default().bg(Color::Red).fg(Color::Black).add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED)
+                        };
+                        styled = patch_range(
+                            styled,
+                            indent + start - offset..indent + end - offset,
+                            style,
+                        );
+                    }
+                }
+            }
 
             // Then apply search highlighting on top
-            
-
-            if !state.search_matches.is_empty() && !state.search_query.is_empty() {
-                highlight_matches_on_line(&base_line, state.search_query)
-            } else {
-                base_line
+            let segment = doc_offset..doc_offset + line.len() - indent;
+            for range in &match_ranges {
+                let style = if current_match.as_ref() == Some(range) {
+                    // The match awaiting replace confirmation
+                    Style::default().bg(Color::Cyan).fg(Color::Black).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                };
+                let start = range.start.max(segment.start);
+                let end = range.end.min(segment.end);
+                if start < end {
+                    styled = patch_range(
+                        styled,
+                        indent + start - doc_offset..indent + end - doc_offset,
+                        style,
+                    );
+                }
             }
+
+            expand_tabs(styled, indent, state.tab_width)
         })
         .collect();
 
-    let paragraph = Paragraph::new(display_lines);
+    let paragraph = Paragraph::new(display_lines).style(
+        Style::default()
+            .fg(state.theme.text.to_color())
+            .bg(state.theme.background.to_color()),
+    );
     frame.render_widget(paragraph, area);
 
     // Calculate cursor screen position
@@ -350,126 +528,169 @@ fn highlight_matches(
     }
 }
 
-/// Highlight misspelled words in a line with red underline
-fn highlight_misspellings(
-    line: &str,
-    logical_line: usize,
-    is_continuation: bool,
-    misspellings: &[Misspelling],
-) -> Line<'static> {
-    // Get misspellings for this logical line
-    let line_misspellings: Vec<&Misspelling> = misspellings
-        .iter()
-        .filter(|m| m.line == logical_line)
-        .collect();
-
-    if line_misspellings.is_empty() {
-        return Line::from(line.to_string());
+/// Replace tab characters with spaces up to the next tab stop, counted from
+/// the start of the text after the wrap indent
+fn expand_tabs(line: Line<'static>, indent: usize, tab_width: usize) -> Line<'static> {
+    if !line.spans.iter().any(|s| s.content.contains('\t')) {
+        return line;
     }
 
-    // Account for continuation indent (used in position calculations)
-    let _col_offset = if is_continuation { WRAP_INDENT.len() } else { 0 };
+    let tab_width = tab_width.max(1);
+    let mut col: usize = 0;
+    let spans = line
+        .spans
+        .into_iter()
+        .map(|span| {
+            let mut text = String::with_capacity(span.content.len());
+            for g in span.content.graphemes(true) {
+                if g == "\t" {
+                    let stop = tab_width - col.saturating_sub(indent) % tab_width;
+                    text.push_str(&" ".repeat(stop));
+                    col += stop;
+                } else {
+                    text.push_str(g);
+                    col += width::grapheme_width(g);
+                }
+            }
+            Span::styled(text, span.style)
+        })
+        .collect::<Vec<_>>();
 
+    Line::from(spans).style(line.style)
+}
+
+/// Style a visual line from the document's markdown structure
+/// `doc_offset` is the byte offset in the document of the text after the wrap indent
+fn markdown_line(
+    line: &str,
+    indent: usize,
+    doc_offset: usize,
+    md_styles: &[MdStyle],
+    theme: &Theme,
+) -> Line<'static> {
     let mut spans = Vec::new();
-    let mut last_end = 0;
-    let chars: Vec<char> = line.chars().collect();
-
-    for m in line_misspellings {
-        // Adjust column for visual line (accounting for wrap indent)
-        let visual_col = if is_continuation {
-            // For continuation lines, we need to figure out where this word appears
-            // This is complex with wrapping, so for now highlight based on word match
-            if let Some(pos) = line.to_lowercase().find(&m.word.to_lowercase()) {
-                pos
-            } else {
-                continue;
-            }
-        } else {
-            m.col
-        };
+    if indent > 0 {
+        spans.push(Span::raw(line[..indent].to_string()));
+    }
 
-        if visual_col >= chars.len() {
-            continue;
-        }
+    let text = &line[indent..];
+    let mut run_start = 0;
+    let mut run_style = md_styles.get(doc_offset).copied().unwrap_or_default();
 
-        // Add text before misspelling
-        if visual_col > last_end {
-            let before: String = chars[last_end..visual_col].iter().collect();
-            spans.push(Span::raw(before));
+    for (i, _) in text.char_indices() {
+        let md = md_styles.get(doc_offset + i).copied().unwrap_or_default();
+        if md != run_style {
+            spans.push(Span::styled(text[run_start..i].to_string(), markdown_style(run_style, theme)));
+            run_start = i;
+            run_style = md;
         }
-
-        // Add misspelled word with underline
-        let word_end = (visual_col + m.word.len()).min(chars.len());
-        let word: String = chars[visual_col..word_end].iter().collect();
-        spans.push(Span::styled(
-            word,
-            Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED),
-        ));
-        last_end = word_end;
     }
-
-    // Add remaining text
-    if last_end < chars.len() {
-        let remaining: String = chars[last_end..].iter().collect();
-        spans.push(Span::raw(remaining));
+    if run_start < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[run_start..].to_string(), markdown_style(run_style, theme)));
     }
 
-    if spans.is_empty() {
-        Line::from(line.to_string())
-    } else {
-        Line::from(spans)
-    }
+    Line::from(spans)
 }
 
-/// Apply search highlighting on top of an existing styled line
-fn highlight_matches_on_line(line: &Line, query: &str) -> Line<'static> {
-    if query.is_empty() {
-        // Convert to owned by rebuilding
-        let spans: Vec<Span<'static>> = line.spans.iter()
-            .map(|s| Span::styled(s.content.to_string(), s.style))
-            .collect();
-        return Line::from(spans);
+/// Map markdown flags to a style using the theme's markdown roles
+fn markdown_style(md: MdStyle, theme: &Theme) -> Style {
+    let mut style = Style::default();
+    if md.is_plain() {
+        return style;
+    }
+    if md.contains(MdStyle::HEADING) {
+        style = style.fg(theme.heading.to_color()).add_modifier(Modifier::BOLD);
+    }
+    if md.contains(MdStyle::STRONG) {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if md.contains(MdStyle::EMPHASIS) {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if md.contains(MdStyle::STRIKE) {
+        style = style.add_modifier(Modifier::CROSSED_OUT);
+    }
+    if md.contains(MdStyle::LINK) {
+        style = style.fg(theme.link.to_color());
+    }
+    if md.contains(MdStyle::URL) {
+        style = style.fg(theme.url.to_color());
+    }
+    if md.contains(MdStyle::CODE) {
+        style = style.fg(theme.code.to_color());
     }
+    if md.contains(MdStyle::QUOTE) {
+        style = style.fg(theme.quote.to_color());
+    }
+    if md.contains(MdStyle::MARKUP) {
+        style = style.fg(theme.markup.to_color());
+    }
+    style
+}
 
-    let query_lower = query.to_lowercase();
-    let mut new_spans = Vec::new();
+/// Patch the style of a byte range of a line's text, splitting spans as needed
+fn patch_range(line: Line<'static>, range: Range<usize>, patch: Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+
+    for span in line.spans {
+        let span_start = pos;
+        pos += span.content.len();
+        let start = range.start.max(span_start);
+        let end = range.end.min(pos);
+        if start >= end {
+            spans.push(span);
+            continue;
+        }
 
-    for span in line.spans.iter() {
         let text = span.content.as_ref();
-        let text_lower = text.to_lowercase();
-        let base_style = span.style;
-
-        let mut last_end = 0;
-        for (start, _) in text_lower.match_indices(&query_lower) {
-            // Add text before match with original style
-            if start > last_end {
-                new_spans.push(Span::styled(text[last_end..start].to_string(), base_style));
-            }
-            // Add highlighted match
-            new_spans.push(Span::styled(
-                text[start..start + query.len()].to_string(),
-                Style::default().bg(Color::Yellow).fg(Color::Black),
-            ));
-            last_end = start + query.len();
+        let (a, b) = (start - span_start, end - span_start);
+        if a > 0 {
+            spans.push(Span::styled(text[..a].to_string(), span.style));
         }
-
-        // Add remaining text with original style
-        if last_end < text.len() {
-            new_spans.push(Span::styled(text[last_end..].to_string(), base_style));
-        } else if last_end == 0 {
-            // No matches in this span, keep original
-            new_spans.push(Span::styled(text.to_string(), base_style));
+        spans.push(Span::styled(text[a..b].to_string(), span.style.patch(patch)));
+        if b < text.len() {
+            spans.push(Span::styled(text[b..].to_string(), span.style));
         }
     }
 
-    Line::from(new_spans)
+    Line::from(spans)
+}
+
+/// Convert sorted (start, end) char ranges to byte ranges in `content`
+fn char_ranges_to_bytes(content: &str, ranges: &[(usize, usize)]) -> Vec<Range<usize>> {
+    let mut offsets = content
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(content.len()))
+        .enumerate()
+        .peekable();
+    let mut byte_at = |target: usize| {
+        while let Some(&(char_pos, byte)) = offsets.peek() {
+            if char_pos == target {
+                return byte;
+            }
+            offsets.next();
+        }
+        content.len()
+    };
+    ranges.iter().map(|&(start, end)| byte_at(start)..byte_at(end)).collect()
 }
 
 fn render_status(frame: &mut Frame, area: Rect, state: &RenderState) {
     // Format per spec 2.4: "Words: NNN  |  Session: XXm  |  [Modified]"
     let modified_str = if state.modified { "  |  [Modified]" } else { "" };
     let saved_str = if state.show_saved_indicator { "  Saved" } else { "" };
-    let spell_str = if state.spell_enabled { "  |  [Spell]" } else { "" };
+    let spell_str = if state.spell_enabled {
+        format!("  |  [Spell] {}", state.spell_languages.join("+"))
+    } else {
+        String::new()
+    };
+    let lint_str = if state.lint_enabled {
+        format!("  |  [Lint: {}]", state.lint_issues.len())
+    } else {
+        String::new()
+    };
     
     // Goal progress string
     let goal_str = if state.show_goal && state.daily_goal > 0 {
@@ -494,10 +715,13 @@ fn render_status(frame: &mut Frame, area: Rect, state: &RenderState) {
         String::new()
     };
 
-    let status = format!(
-        "Words: {}  |  Session: {}{}{}{}{}{}",
-        state.word_count, state.elapsed, spell_str, goal_str, streak_str, modified_str, saved_str
-    );
+    let status = match state.message {
+        Some(message) => message.to_string(),
+        None => format!(
+            "Words: {}  |  Session: {}{}{}{}{}{}{}",
+            state.word_count, state.elapsed, spell_str, lint_str, goal_str, streak_str, modified_str, saved_str
+        ),
+    };
 
     let status_line = Paragraph::new(status)
         .style(Style::default()
@@ -535,6 +759,9 @@ fn render_help_overlay(frame: &mut Frame, area: Rect) {
     gg/G            Document start/end
     /               Search
     n/N             Next/prev match
+    Alt+r/w/c       Regex/word/case (in search)
+    R               Find and replace
+    ]s/[s           Next/prev misspelling
 
   EDITING (Navigate mode)
     dd              Delete line
@@ -548,16 +775,20 @@ fn render_help_overlay(frame: &mut Frame, area: Rect) {
     Ctrl+S          Save
     Ctrl+Q          Quit
     Ctrl+G          Toggle status
+    Ctrl+L          Toggle style checks
     s               Writing statistics
     v               Version history
     P               Project documents
+    F               Search/replace in project
+    S               Spelling review
+    L               Cycle spelling language
     ?               Show this help
 
   Press any key to close
 "#;
 
     let width = 50.min(area.width - 4);
-    let height = 34.min(area.height - 2);
+    let height = 41.min(area.height - 2);
     let x = (area.width - width) / 2;
     let y = (area.height - height) / 2;
 
@@ -672,7 +903,7 @@ fn render_stats_overlay(frame: &mut Frame, area: Rect, stats: Option<&WritingSta
     frame.render_widget(stats_para, overlay_area);
 }
 
-fn render_search_prompt(frame: &mut Frame, area: Rect, query: &str) {
+fn render_search_prompt(frame: &mut Frame, area: Rect, prompt: &str, status: &str) {
     let search_area = Rect {
         x: 0,
         y: area.height - 1,
@@ -680,10 +911,14 @@ fn render_search_prompt(frame: &mut Frame, area: Rect, query: &str) {
         height: 1,
     };
 
-    let prompt = format!("/{}", query);
     let search_line = Paragraph::new(prompt).style(Style::default().fg(Color::Cyan));
-
     frame.render_widget(search_line, search_area);
+
+    // Active flags (and regex errors) on the right
+    let flags = Paragraph::new(format!("[{}] ", status))
+        .alignment(Alignment::Right)
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(flags, search_area);
 }
 
 fn render_versions_overlay(frame: &mut Frame, area: Rect, versions: &[Version], selected: usize) {
@@ -837,6 +1072,7 @@ fn render_project_docs_overlay(
     area: Rect,
     project_name: &str,
     docs: &[String],
+    titles: &[Option<String>],
     selected: usize,
     current_doc: &str,
 ) {
@@ -873,7 +1109,12 @@ fn render_project_docs_overlay(
         let is_current = doc == current_doc;
         let prefix = if i == selected { "> " } else { "  " };
         let suffix = if is_current { " [current]" } else { "" };
-        let line_text = format!("{}{}{}", prefix, doc, suffix);
+        // Documents with a front matter title show it before the file name
+        let name = match titles.get(i).and_then(|t| t.as_deref()) {
+            Some(title) => format!("{} ({})", title, doc),
+            None => doc.clone(),
+        };
+        let line_text = format!("{}{}{}", prefix, name, suffix);
 
         let style = if i == selected {
             Style::default().fg(Color::Yellow)
@@ -900,6 +1141,114 @@ fn render_project_docs_overlay(
     frame.render_widget(para, overlay_area);
 }
 
+/// Render project-wide search results grouped by file, or a replace preview
+fn render_project_search_overlay(
+    frame: &mut Frame,
+    area: Rect,
+    project_name: &str,
+    search: &ProjectSearch,
+    status: &str,
+) {
+    let width = 80.min(area.width - 4);
+    let height = area.height.saturating_sub(4).max(8).min(area.height);
+    let x = (area.width - width) / 2;
+    let y = (area.height - height) / 2;
+
+    let overlay_area = Rect { x, y, width, height };
+    frame.render_widget(Clear, overlay_area);
+
+    let content_height = height.saturating_sub(6) as usize;
+    let dim = Style::default().fg(Color::DarkGray);
+    let match_style = Style::default().bg(Color::Yellow).fg(Color::Black);
+    let mut lines: Vec<Line> = Vec::new();
+
+    let (find_mark, with_mark) = if search.editing_with { ("", "_") } else { ("_", "") };
+    lines.push(Line::from(vec![
+        Span::raw(format!(" Find: {}{}", search.query, find_mark)),
+        Span::styled(format!("  [{}]", status), dim),
+    ]));
+    lines.push(Line::from(format!(" Replace with: {}{}", search.with, with_mark)));
+    lines.push(Line::from(""));
+
+    let footer;
+    if let Some(changes) = &search.preview {
+        let total: usize = changes.iter().map(|c| c.replacements).sum();
+        let mut rows: Vec<Line> = Vec::new();
+        for change in changes {
+            rows.push(Line::from(Span::styled(
+                format!(" {} ({} replacements)", change.document, change.replacements),
+                Style::default().fg(Color::Cyan),
+            )));
+            for (line, old, new) in &change.lines {
+                rows.push(Line::from(Span::styled(format!("  {:>5} - {}", line + 1, old), Style::default().fg(Color::Red))));
+                rows.push(Line::from(Span::styled(format!("  {:>5} + {}", line + 1, new), Style::default().fg(Color::Green))));
+            }
+        }
+        lines.extend(rows.into_iter().take(content_height));
+        footer = format!(
+            "  {} replacements in {} files. Versions are saved first.  y: apply  n: cancel",
+            total,
+            changes.len()
+        );
+    } else {
+        // Flatten into rows, remembering which row holds the selected match
+        let mut rows: Vec<Line> = Vec::new();
+        let mut selected_row = 0;
+        let mut index = 0;
+        for file in &search.results {
+            rows.push(Line::from(Span::styled(
+                format!(" {} ({})", file.document, file.matches.len()),
+                Style::default().fg(Color::Cyan),
+            )));
+            for m in &file.matches {
+                let chars: Vec<char> = m.context.chars().collect();
+                let before: String = chars[..m.col].iter().collect();
+                let matched: String = chars[m.col..m.col_end].iter().collect();
+                let after: String = chars[m.col_end..].iter().collect();
+                let prefix = if index == search.selected { ">" } else { " " };
+                let mut spans = vec![
+                    Span::styled(format!(" {}{:>5}: ", prefix, m.line + 1), dim),
+                    Span::raw(before.trim_start().to_string()),
+                    Span::styled(matched, match_style),
+                    Span::raw(after),
+                ];
+                if index == search.selected {
+                    for span in &mut spans {
+                        span.style = span.style.add_modifier(Modifier::BOLD);
+                    }
+                    selected_row = rows.len();
+                }
+                rows.push(Line::from(spans));
+                index += 1;
+            }
+        }
+
+        let scroll = selected_row.saturating_sub(content_height.saturating_sub(1));
+        lines.extend(rows.into_iter().skip(scroll).take(content_height));
+        footer = if search.query.is_empty() {
+            "  Type to search all documents".to_string()
+        } else {
+            format!(
+                "  {} matches in {} files  Up/Down: select  Enter: open  Tab: replace field  Esc: close",
+                search.match_count(),
+                search.results.len()
+            )
+        };
+    }
+
+    while lines.len() < height.saturating_sub(3) as usize {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(footer, dim)));
+
+    let title = format!(" Search {} ", project_name);
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(para, overlay_area);
+}
+
 /// Render spell suggestions popup
 fn render_spell_suggestions_overlay(
     frame: &mut Frame,
@@ -965,3 +1314,304 @@ fn render_spell_suggestions_overlay(
 
     frame.render_widget(para, overlay_area);
 }
+
+/// Render the spelling review popup in the lower part of the screen,
+/// leaving the word under review visible above it
+fn render_spell_review_overlay(frame: &mut Frame, area: Rect, review: &SpellReview) {
+    let Some(ref current) = review.current else {
+        return;
+    };
+
+    let width = 56.min(area.width.saturating_sub(4));
+    let height = (review.suggestions.len().min(5) + 6).min(area.height.saturating_sub(2) as usize) as u16;
+    let x = (area.width - width) / 2;
+    let y = area.height.saturating_sub(height + 2);
+
+    let overlay_area = Rect { x, y, width, height };
+    frame.render_widget(Clear, overlay_area);
+
+    let mut lines: Vec<Line> = vec![Line::from("")];
+    if review.suggestions.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No suggestions",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    for (i, suggestion) in review.suggestions.iter().enumerate().take(5) {
+        let (prefix, style) = if i == review.index {
+            ("> ", Style::default().fg(Color::Yellow))
+        } else {
+            ("  ", Style::default().fg(Color::White))
+        };
+        lines.push(Line::from(Span::styled(format!("{}{}", prefix, suggestion), style)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Enter: replace  a: replace all  n: ignore once",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines.push(Line::from(Span::styled(
+        "  i: ignore for session  Tab: add to dict  q: stop",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let title = format!(
+        " '{}' on line {} | {} changed ",
+        current.word,
+        current.line + 1,
+        review.changed
+    );
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(para, overlay_area);
+}
+
+/// Render the explanation of the style issue under the cursor
+fn render_lint_info_overlay(frame: &mut Frame, area: Rect, message: &str) {
+    let width = 50.min(area.width.saturating_sub(4));
+    let height = 6.min(area.height.saturating_sub(2));
+    let x = (area.width - width) / 2;
+    let y = (area.height - height) / 2;
+
+    let overlay_area = Rect { x, y, width, height };
+    frame.render_widget(Clear, overlay_area);
+
+    let lines = vec![
+        Line::from(""),
+        Line::from(format!("  {}", message)),
+        Line::from(""),
+        Line::from(Span::styled("  Any key: close", Style::default().fg(Color::DarkGray))),
+    ];
+    let para = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(" Style "))
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(para, overlay_area);
+}
+
+/// Render the choice of dictionary to add a word to
+fn render_add_word_overlay(frame: &mut Frame, area: Rect, word: &str, layers: &[DictLayer]) {
+    let width = 40.min(area.width.saturating_sub(4));
+    let height = (layers.len() as u16 + 5).min(area.height.saturating_sub(2));
+    let x = (area.width - width) / 2;
+    let y = (area.height - height) / 2;
+
+    let overlay_area = Rect { x, y, width, height };
+    frame.render_widget(Clear, overlay_area);
+
+    let mut lines: Vec<Line> = vec![Line::from("")];
+    for (i, layer) in layers.iter().enumerate() {
+        lines.push(Line::from(format!("  {}  {}", i + 1, layer.label())));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Number: add  Esc: cancel",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let title = format!(" Add '{}' to ", word);
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(para, overlay_area);
+}
+
+/// Render a sample page showing every color role of a theme
+pub fn render_theme_preview(frame: &mut Frame, theme: &Theme) {
+    let area = frame.area();
+    let text = Style::default().fg(theme.text.to_color()).bg(theme.background.to_color());
+    let dim = text.fg(theme.dim.to_color());
+
+    let body_area = Rect { height: area.height.saturating_sub(1), ..area };
+    let lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(format!("  {}", theme.name), text.add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from(Span::styled("  The quiet of the terminal, and your words.", text)),
+        Line::from(Span::styled("  A dimmed aside for notes and markers.", dim)),
+        Line::from(vec![
+            Span::styled("  Search ", text),
+            Span::styled("highlight", Style::default().bg(theme.highlight.to_color()).fg(Color::Black)),
+            Span::styled(" and a cursor", text),
+            Span::styled(" ", Style::default().bg(theme.cursor.to_color())),
+        ]),
+        Line::from(vec![
+            Span::styled("  ## ", text.fg(theme.markup.to_color())),
+            Span::styled("Heading", text.fg(theme.heading.to_color()).add_modifier(Modifier::BOLD)),
+            Span::styled("  ", text),
+            Span::styled("`code`", text.fg(theme.code.to_color())),
+            Span::styled("  [", text.fg(theme.markup.to_color())),
+            Span::styled("link", text.fg(theme.link.to_color())),
+            Span::styled("](", text.fg(theme.markup.to_color())),
+            Span::styled("url", text.fg(theme.url.to_color())),
+            Span::styled(")", text.fg(theme.markup.to_color())),
+        ]),
+        Line::from(vec![
+            Span::styled("  > ", text.fg(theme.quote.to_color())),
+            Span::styled("A quoted line", text),
+        ]),
+        Line::from(""),
+    ];
+    frame.render_widget(Paragraph::new(lines).style(text), body_area);
+
+    let box_area = Rect {
+        x: area.x + 2,
+        y: area.y + 10,
+        width: area.width.saturating_sub(4).min(40),
+        height: 4.min(body_area.height.saturating_sub(10)),
+    };
+    let box_lines = vec![
+        Line::from(Span::styled(" Goal met [done]", text.fg(theme.success.to_color()))),
+        Line::from(Span::styled(" Unsaved changes", text.fg(theme.warning.to_color()))),
+    ];
+    let boxed = Paragraph::new(box_lines).style(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(text.fg(theme.border.to_color()))
+            .title(" Border "),
+    );
+    frame.render_widget(boxed, box_area);
+
+    let status_area = Rect { y: area.y + area.height.saturating_sub(1), height: 1, ..area };
+    let status = Paragraph::new("Words: 1234  |  Session: 25m  |  [Spell]")
+        .style(Style::default()
+            .fg(theme.status_text.to_color())
+            .bg(theme.status_bg.to_color()))
+        .alignment(Alignment::Center);
+    frame.render_widget(status, status_area);
+}
+
+/// Convert a rendered buffer to a string with ANSI color escapes
+pub fn buffer_to_ansi(buffer: &Buffer) -> String {
+    use crossterm::style::{Attribute, SetAttribute, SetBackgroundColor, SetForegroundColor};
+
+    let mut out = String::new();
+    let width = buffer.area.width as usize;
+
+    for row in buffer.content().chunks(width.max(1)) {
+        let mut last_style: Option<(Color, Color, Modifier)> = None;
+        for cell in row {
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if last_style != Some(style) {
+                out.push_str(&SetAttribute(Attribute::Reset).to_string());
+                out.push_str(&SetForegroundColor(cell.fg.into()).to_string());
+                out.push_str(&SetBackgroundColor(cell.bg.into()).to_string());
+                if cell.modifier.contains(Modifier::BOLD) {
+                    out.push_str(&SetAttribute(Attribute::Bold).to_string());
+                }
+                last_style = Some(style);
+            }
+            out.push_str(cell.symbol());
+        }
+        out.push_str(&SetAttribute(Attribute::Reset).to_string());
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_line_by_display_width() {
+        let line = "世界".repeat(10);
+        let wrapped = wrap_line(&line, 20, 4);
+        assert_eq!(wrapped[0], "世界".repeat(5));
+        assert_eq!(wrapped.len(), 3);
+        assert_eq!(wrapped[1], format!("{}{}", WRAP_INDENT, "世界世界世界世界世"));
+        for row in &wrapped {
+            assert!(width::str_width(row, 4) <= 20);
+        }
+    }
+
+    #[test]
+    fn test_wrap_line_keeps_graphemes_together() {
+        let line = format!("{} tail", "e\u{301}".repeat(30));
+        let wrapped = wrap_line(&line, 20, 4);
+        let rejoined: String = wrapped
+            .iter()
+            .enumerate()
+            .map(|(i, row)| if i == 0 { row.as_str() } else { &row[WRAP_INDENT.len()..] })
+            .collect();
+        assert_eq!(rejoined, line);
+        assert!(wrapped[0].starts_with('e') && wrapped[1][WRAP_INDENT.len()..].starts_with('e'));
+    }
+
+    #[test]
+    fn test_cursor_column_counts_wide_chars() {
+        let content = "a世界🌍b";
+        // Byte 1 + 3 + 3 = 7 is just before the emoji
+        assert_eq!(logical_to_visual(content, 0, 7, 40, 4, 1), (0, 5));
+        assert_eq!(logical_to_visual(content, 0, content.len(), 40, 4, 1), (0, 8));
+
+        let wrapped = "世界".repeat(10);
+        // Start of the second row sits after the continuation indent
+        assert_eq!(logical_to_visual(&wrapped, 0, 30, 20, 4, 1), (0, 20));
+        assert_eq!(logical_to_visual(&wrapped, 0, 33, 20, 4, 1), (1, 4));
+    }
+
+    #[test]
+    fn test_line_spacing_adds_spacer_rows() {
+        // Spacers go only where a paragraph ends, not between its lines
+        let content = "one\ntwo\n\nthree\n\n\nfour";
+        let (lines, map) = build_visual_lines(content, 40, 4, 2);
+        assert_eq!(lines, vec!["one", "two", "", "", "three", "", "", "", "four"]);
+        assert_eq!(map[2], (1, false, 3));
+        assert_eq!(logical_to_visual(content, 3, 1, 40, 4, 2), (4, 1));
+        assert_eq!(logical_to_visual(content, 6, 2, 40, 4, 2), (8, 2));
+
+        let (single, _) = build_visual_lines(content, 40, 4, 1);
+        assert_eq!(single.len(), 7);
+    }
+
+    #[test]
+    fn test_tabs_render_at_tab_width() {
+        let line = expand_tabs(Line::from("a\tb"), 0, 4);
+        assert_eq!(line.to_string(), "a   b");
+        assert_eq!(logical_to_visual("a\tb", 0, 2, 40, 4, 1), (0, 4));
+        assert_eq!(logical_to_visual("a\tb", 0, 2, 40, 8, 1), (0, 8));
+    }
+
+    #[test]
+    fn test_char_ranges_to_bytes() {
+        let content = "héllo wörld";
+        assert_eq!(char_ranges_to_bytes(content, &[(0, 2), (6, 11)]), vec![0..3, 7..13]);
+        assert_eq!(char_ranges_to_bytes(content, &[(1, 1)]), vec![1..1]);
+    }
+
+    #[test]
+    fn test_project_search_overlay_renders() {
+        use crate::project_search::{FileResults, ProjectMatch};
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut search = ProjectSearch::new();
+        search.query = "Anna".to_string();
+        search.results = vec![FileResults {
+            document: "one.md".to_string(),
+            path: "one.md".into(),
+            matches: vec![ProjectMatch {
+                line: 4,
+                start: 40,
+                end: 44,
+                context: "Then Anna came back.".to_string(),
+                col: 5,
+                col_end: 9,
+            }],
+        }];
+
+        let mut terminal = Terminal::new(TestBackend::new(90, 20)).unwrap();
+        terminal
+            .draw(|f| render_project_search_overlay(f, f.area(), "Novel", &search, "aa"))
+            .unwrap();
+        let text = buffer_to_ansi(terminal.backend().buffer());
+        assert!(text.contains("one.md (1)"));
+        assert!(text.contains("Anna"));
+        assert!(text.contains("1 matches in 1 files"));
+    }
+}
@@ -11,6 +11,8 @@ use rusqlite::{Connection, Result as SqlResult};
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
+use crate::frontmatter;
+
 /// A single document version
 #[derive(Debug, Clone)]
 pub struct Version {
@@ -106,9 +108,9 @@ impl VersionStore {
         result
     }
 
-    /// Count words in content
+    /// Count words in content, leaving out front matter
     fn count_words(content: &str) -> usize {
-        content.split_whitespace().count()
+        frontmatter::body(content).split_whitespace().count()
     }
 
     /// Save a new version
@@ -0,0 +1,89 @@
+//! Watch export sources for changes
+//!
+//! Modification times are polled, which needs no platform support and is
+//! cheap for the handful of files a document or project has. The file list
+//! is read again on every poll, so documents added to a project are picked up.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::export;
+
+/// How often sources are checked
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Tracks the files an export reads and when they last changed.
+pub struct Watcher {
+    input: PathBuf,
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Watcher {
+    /// Start watching the sources of `input` as they are now
+    pub fn new(input: &Path) -> Self {
+        let mut watcher = Self {
+            input: input.to_path_buf(),
+            stamps: Vec::new(),
+        };
+        watcher.stamps = watcher.current();
+        watcher
+    }
+
+    /// Whether any source was added, removed or modified since the last call
+    pub fn changed(&mut self) -> bool {
+        let current = self.current();
+        if current == self.stamps {
+            return false;
+        }
+        self.stamps = current;
+        true
+    }
+
+    /// Block until a source changes
+    pub fn wait(&mut self) {
+        while !self.changed() {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn current(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        // A project file that fails to parse mid-edit still counts as a change
+        let paths = export::source_paths(&self.input).unwrap_or_else(|_| vec![self.input.clone()]);
+        paths
+            .into_iter()
+            .map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detects_edits_and_new_documents() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("one.md"), "One").unwrap();
+        fs::write(dir.path().join(".hollow-project"), "name: Book\ndocuments:\n  - one.md\n").unwrap();
+
+        let mut watcher = Watcher::new(dir.path());
+        assert!(!watcher.changed());
+
+        // Sources list the documents and the project file
+        let paths = export::source_paths(dir.path()).unwrap();
+        assert_eq!(paths, [dir.path().join("one.md"), dir.path().join(".hollow-project")]);
+
+        fs::write(dir.path().join("two.md"), "Two").unwrap();
+        fs::write(dir.path().join(".hollow-project"), "name: Book\ndocuments:\n  - one.md\n  - two.md\n").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::remove_file(dir.path().join("two.md")).unwrap();
+        assert!(watcher.changed());
+    }
+}
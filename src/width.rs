@@ -0,0 +1,129 @@
+//! Display width and grapheme helpers
+//!
+//! Columns on screen are measured per grapheme cluster, the same way ratatui
+//! draws them: wide CJK characters and emoji take two cells, combining marks
+//! take none of their own. Tabs expand to the next tab stop.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Display width of a single grapheme cluster
+pub fn grapheme_width(g: &str) -> usize {
+    if g.contains(char::is_control) {
+        0
+    } else {
+        g.width()
+    }
+}
+
+/// Graphemes of `s` with their byte offset and display width, expanding
+/// tabs to the next multiple of `tab_width` columns
+fn cells(s: &str, tab_width: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let tab_width = tab_width.max(1);
+    let mut col = 0;
+    s.grapheme_indices(true).map(move |(i, g)| {
+        let w = if g == "\t" {
+            tab_width - col % tab_width
+        } else {
+            grapheme_width(g)
+        };
+        col += w;
+        (i, w)
+    })
+}
+
+/// Display width of a string
+pub fn str_width(s: &str, tab_width: usize) -> usize {
+    cells(s, tab_width).map(|(_, w)| w).sum()
+}
+
+/// Whether a grapheme is double-width (CJK, emoji); text may wrap on either side of it
+pub fn is_wide(g: &str) -> bool {
+    grapheme_width(g) >= 2
+}
+
+/// Byte offset of the grapheme boundary before `byte` in `line`
+pub fn prev_boundary(line: &str, byte: usize) -> usize {
+    line.grapheme_indices(true)
+        .map(|(i, _)| i)
+        .take_while(|i| *i < byte)
+        .last()
+        .unwrap_or(0)
+}
+
+/// Byte offset of the grapheme boundary after `byte` in `line`
+pub fn next_boundary(line: &str, byte: usize) -> usize {
+    line.grapheme_indices(true)
+        .map(|(i, g)| i + g.len())
+        .find(|end| *end > byte)
+        .unwrap_or(line.len())
+}
+
+/// Display column of a byte offset in `line`
+pub fn byte_to_col(line: &str, byte: usize, tab_width: usize) -> usize {
+    cells(line, tab_width)
+        .take_while(|(i, _)| *i < byte)
+        .map(|(_, w)| w)
+        .sum()
+}
+
+/// Byte offset of the last grapheme boundary at or before display column `col`
+pub fn col_to_byte(line: &str, col: usize, tab_width: usize) -> usize {
+    let mut width = 0;
+    for (i, w) in cells(line, tab_width) {
+        if width + w > col {
+            return i;
+        }
+        width += w;
+    }
+    line.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_widths() {
+        assert_eq!(str_width("hello", 4), 5);
+        assert_eq!(str_width("世界", 4), 4);
+        assert_eq!(str_width("e\u{301}", 4), 1); // e + combining acute
+        assert_eq!(str_width("🌍", 4), 2);
+        assert!(is_wide("世"));
+        assert!(!is_wide("é"));
+    }
+
+    #[test]
+    fn test_boundaries_skip_combining_marks() {
+        let line = "ae\u{301}b";
+        // 'e' + U+0301 is one grapheme spanning bytes 1..4
+        assert_eq!(next_boundary(line, 1), 4);
+        assert_eq!(prev_boundary(line, 4), 1);
+        assert_eq!(next_boundary(line, 4), 5);
+        assert_eq!(prev_boundary(line, 0), 0);
+        assert_eq!(next_boundary(line, 5), 5);
+    }
+
+    #[test]
+    fn test_col_byte_round_trip() {
+        let line = "a世b";
+        assert_eq!(byte_to_col(line, 0, 4), 0);
+        assert_eq!(byte_to_col(line, 1, 4), 1);
+        assert_eq!(byte_to_col(line, 4, 4), 3);
+        assert_eq!(col_to_byte(line, 3, 4), 4);
+        // Column inside a wide character snaps back to its start
+        assert_eq!(col_to_byte(line, 2, 4), 1);
+        assert_eq!(col_to_byte(line, 99, 4), line.len());
+    }
+
+    #[test]
+    fn test_tabs_expand_to_tab_stops() {
+        assert_eq!(str_width("\t", 4), 4);
+        assert_eq!(str_width("ab\tc", 4), 5);
+        assert_eq!(str_width("ab\tc", 8), 9);
+        assert_eq!(byte_to_col("\tx", 1, 4), 4);
+        // Columns inside the tab snap to its start
+        assert_eq!(col_to_byte("\tx", 2, 4), 0);
+        assert_eq!(col_to_byte("\tx", 4, 4), 1);
+    }
+}